@@ -10,4 +10,44 @@ pub enum EscrowError {
   InvalidMintA,
   #[msg("Invalid mint b")]
   InvalidMintB,
-}
\ No newline at end of file
+  #[msg("Escrow expired")]
+  EscrowExpired,
+  #[msg("Invalid taker")]
+  InvalidTaker,
+  #[msg("Invalid merkle proof")]
+  InvalidProof,
+  #[msg("Missing token account")]
+  MissingTokenAccount,
+  #[msg("Invalid fee")]
+  InvalidFee,
+  #[msg("Unauthorized")]
+  Unauthorized,
+  #[msg("Invalid fee recipient")]
+  InvalidFeeRecipient,
+  #[msg("Program paused")]
+  ProgramPaused,
+  #[msg("Escrow not expired")]
+  EscrowNotExpired,
+  #[msg("Invalid milestone")]
+  InvalidMilestone,
+  #[msg("Invalid schedule")]
+  InvalidSchedule,
+  #[msg("Invalid preimage")]
+  InvalidPreimage,
+  #[msg("Slippage exceeded")]
+  SlippageExceeded,
+  #[msg("Invalid price feed")]
+  InvalidPriceFeed,
+  #[msg("Stale price")]
+  StalePrice,
+  #[msg("Price too uncertain")]
+  PriceTooUncertain,
+  #[msg("Bid too low")]
+  BidTooLow,
+  #[msg("Leading bid can't be withdrawn")]
+  LeadingBid,
+  #[msg("Invalid NFT")]
+  InvalidNft,
+  #[msg("Invalid collection")]
+  InvalidCollection,
+}
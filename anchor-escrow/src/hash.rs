@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::{hash, hashv};
+use solana_keccak_hasher as keccak;
+
+/// Verifies that `preimage` opens a hashlock.
+///
+/// The SHA-256 hash is always checked, matching Bitcoin-style HTLCs; an
+/// all-zero `keccak_hash` means no Keccak-256 lock was set, otherwise it must
+/// match too so the same secret also settles an EVM-side contract.
+pub fn verify_hashlock(preimage: &[u8], sha256_hash: &[u8; 32], keccak_hash: &[u8; 32]) -> bool {
+    if hash(preimage).to_bytes() != *sha256_hash {
+        return false;
+    }
+
+    *keccak_hash == [0; 32] || keccak::hash(preimage).to_bytes() == *keccak_hash
+}
+
+/// Verifies that `key` is a leaf of the merkle tree with the given `root`.
+///
+/// Leaves are `sha256(key)` and each parent is the hash of its two children
+/// in sorted order, so `proof` is just the sibling hashes from the leaf up to the root.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: &[u8; 32], key: &Pubkey) -> bool {
+    let leaf = hash(key.as_ref()).to_bytes();
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        }
+    });
+
+    computed == *root
+}
@@ -0,0 +1,199 @@
+use crate::errors::EscrowError;
+use crate::state::{Config, Escrow, Offer};
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+#[derive(Accounts)]
+pub struct AcceptCounter<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        close=maker,
+        seeds=[b"escrow",maker.key().as_ref(),escrow.seed.to_le_bytes().as_ref()],
+        bump=escrow.bump,
+        has_one=maker @ EscrowError::InvalidMaker,
+        has_one=mint_a @ EscrowError::InvalidMintA,
+        has_one=mint_b @ EscrowError::InvalidMintB,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+    /// The taker funded the offer and its vault, so their rent goes back to the taker
+    #[account(
+        mut,
+        close=taker,
+        seeds=[b"offer",escrow.key().as_ref(),taker.key().as_ref()],
+        bump=offer.bump,
+        has_one=escrow,
+        has_one=taker @ EscrowError::InvalidTaker,
+    )]
+    pub offer: Box<Account<'info, Offer>>,
+    #[account(mut)]
+    pub taker: SystemAccount<'info>,
+
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint=mint_a,
+        associated_token::authority=escrow,
+        associated_token::token_program=token_program,
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint=mint_b,
+        associated_token::authority=offer,
+        associated_token::token_program=token_program,
+    )]
+    pub offer_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// The maker accepts, so the maker pays for any token account the swap needs
+    #[account(
+        init_if_needed,
+        payer=maker,
+        associated_token::mint=mint_a,
+        associated_token::authority=taker,
+        associated_token::token_program=token_program,
+    )]
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer=maker,
+        associated_token::mint=mint_b,
+        associated_token::authority=maker,
+        associated_token::token_program=token_program,
+    )]
+    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds=[b"config"],
+        bump=config.bump,
+        constraint=!config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(mut, address=config.fee_recipient @ EscrowError::InvalidFeeRecipient)]
+    pub treasury: SystemAccount<'info>,
+    /// Not needed when the protocol fee is zero
+    #[account(
+        init_if_needed,
+        payer=maker,
+        associated_token::mint=mint_b,
+        associated_token::authority=treasury,
+        associated_token::token_program=token_program,
+    )]
+    pub treasury_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AcceptCounter<'info> {
+    fn release_and_close_vault(&mut self) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.vault.to_account_info(),
+                    to: self.taker_ata_a.to_account_info(),
+                    mint: self.mint_a.to_account_info(),
+                    authority: self.escrow.to_account_info(),
+                },
+                &signer_seeds,
+            ),
+            self.vault.amount,
+            self.mint_a.decimals,
+        )?;
+
+        close_account(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.vault.to_account_info(),
+                authority: self.escrow.to_account_info(),
+                destination: self.maker.to_account_info(),
+            },
+            &signer_seeds,
+        ))?;
+        Ok(())
+    }
+    /// Pays the offered mint B to the maker, less the protocol fee, and closes the offer vault.
+    fn pay_offer_and_close_vault(&mut self) -> Result<()> {
+        let escrow_key = self.escrow.key();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"offer",
+            escrow_key.as_ref(),
+            self.taker.to_account_info().key.as_ref(),
+            &[self.offer.bump],
+        ]];
+
+        // The protocol fee comes out of the offered mint B, as it would on a take
+        let offered = self.offer_vault.amount;
+        let fee = self.config.fee_for(offered);
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.offer_vault.to_account_info(),
+                    to: self.maker_ata_b.to_account_info(),
+                    mint: self.mint_b.to_account_info(),
+                    authority: self.offer.to_account_info(),
+                },
+                &signer_seeds,
+            ),
+            offered - fee,
+            self.mint_b.decimals,
+        )?;
+        if fee > 0 {
+            let treasury_ata_b = self
+                .treasury_ata_b
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+            transfer_checked(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    TransferChecked {
+                        from: self.offer_vault.to_account_info(),
+                        to: treasury_ata_b.to_account_info(),
+                        mint: self.mint_b.to_account_info(),
+                        authority: self.offer.to_account_info(),
+                    },
+                    &signer_seeds,
+                ),
+                fee,
+                self.mint_b.decimals,
+            )?;
+        }
+
+        close_account(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.offer_vault.to_account_info(),
+                authority: self.offer.to_account_info(),
+                destination: self.taker.to_account_info(),
+            },
+            &signer_seeds,
+        ))?;
+        Ok(())
+    }
+}
+
+pub fn accept_counter_handler(ctx: Context<AcceptCounter>) -> Result<()> {
+    // A fill or update since the offer would hand the taker less mint A than they countered for
+    let deposit = ctx.accounts.offer.deposit;
+    require_gte!(ctx.accounts.escrow.deposit, deposit, EscrowError::SlippageExceeded);
+    require_gte!(ctx.accounts.vault.amount, deposit, EscrowError::SlippageExceeded);
+
+    // Both vaults swap in full; the escrow and the offer close once the handler returns
+    ctx.accounts.release_and_close_vault()?;
+    ctx.accounts.pay_offer_and_close_vault()?;
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use crate::{errors::EscrowError, state::VestingEscrow};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vesting", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+        has_one = maker @ EscrowError::InvalidMaker,
+        has_one = beneficiary @ EscrowError::InvalidTaker,
+        has_one = mint_a @ EscrowError::InvalidMintA,
+    )]
+    pub escrow: Box<Account<'info, VestingEscrow>>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        associated_token::mint = mint_a,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = token_program
+    )]
+    pub beneficiary_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Claim<'info> {
+    fn withdraw_vested(&mut self, amount: u64, is_final: bool) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"vesting",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        // The final claim sweeps the vault so it can be closed, including anything sent to it directly
+        let amount = if is_final { self.vault.amount } else { amount };
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.vault.to_account_info(),
+                    to: self.beneficiary_ata_a.to_account_info(),
+                    mint: self.mint_a.to_account_info(),
+                    authority: self.escrow.to_account_info(),
+                },
+                &signer_seeds,
+            ),
+            amount,
+            self.mint_a.decimals,
+        )?;
+
+        // Once fully vested and claimed, rent goes back to the maker who paid it
+        if is_final {
+            close_account(CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                CloseAccount {
+                    account: self.vault.to_account_info(),
+                    authority: self.escrow.to_account_info(),
+                    destination: self.maker.to_account_info(),
+                },
+                &signer_seeds,
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn claim_handler(ctx: Context<Claim>) -> Result<()> {
+    let clock = Clock::get()?;
+    let escrow = &mut ctx.accounts.escrow;
+    let amount = escrow.claimable_at(clock.unix_timestamp);
+    require_gt!(amount, 0, EscrowError::InvalidAmount);
+
+    escrow.withdrawn += amount;
+    let is_final = escrow.withdrawn == escrow.total;
+
+    ctx.accounts.withdraw_vested(amount, is_final)?;
+    if is_final {
+        ctx.accounts
+            .escrow
+            .close(ctx.accounts.maker.to_account_info())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use crate::errors::EscrowError;
+use crate::state::{Config, Escrow, Offer};
+use crate::transfer_fee::amount_after_fee;
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+#[derive(Accounts)]
+pub struct CounterOffer<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    /// Counters are paid in the escrow's own mint B; collection bids have none to match
+    #[account(
+        seeds=[b"escrow",escrow.maker.as_ref(),escrow.seed.to_le_bytes().as_ref()],
+        bump=escrow.bump,
+        has_one=mint_b @ EscrowError::InvalidMintB,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+    /// One open counter-offer per taker and escrow; revising it means withdrawing first
+    #[account(
+        init,
+        payer=taker,
+        space=Offer::INIT_SPACE + Offer::DISCRIMINATOR.len(),
+        seeds=[b"offer",escrow.key().as_ref(),taker.key().as_ref()],
+        bump,
+    )]
+    pub offer: Box<Account<'info, Offer>>,
+    #[account(
+        mint::token_program=token_program,
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint=mint_b,
+        associated_token::authority=taker,
+        associated_token::token_program=token_program,
+    )]
+    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer=taker,
+        associated_token::mint=mint_b,
+        associated_token::authority=offer,
+        associated_token::token_program=token_program,
+    )]
+    pub offer_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds=[b"config"],
+        bump=config.bump,
+        constraint=!config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CounterOffer<'info> {
+    fn deposit_offer(&mut self, amount: u64) -> Result<()> {
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.taker_ata_b.to_account_info(),
+                    mint: self.mint_b.to_account_info(),
+                    to: self.offer_vault.to_account_info(),
+                    authority: self.taker.to_account_info(),
+                },
+            ),
+            amount,
+            self.mint_b.decimals,
+        )?;
+        Ok(())
+    }
+}
+
+pub fn counter_offer_handler(ctx: Context<CounterOffer>, receive: u64) -> Result<()> {
+    require_gt!(receive, 0, EscrowError::InvalidAmount);
+
+    // An expired escrow can no longer be settled, so there is nothing to counter
+    let clock = Clock::get()?;
+    require!(
+        !ctx.accounts.escrow.is_expired(clock.unix_timestamp),
+        EscrowError::EscrowExpired
+    );
+
+    // Mint B may withhold a transfer fee, so the offer only counts what lands in its vault
+    let credited = amount_after_fee(&ctx.accounts.mint_b.to_account_info(), clock.epoch, receive)?;
+    require_gt!(credited, 0, EscrowError::InvalidAmount);
+
+    ctx.accounts.deposit_offer(receive)?;
+
+    let escrow = ctx.accounts.escrow.key();
+    let taker = ctx.accounts.taker.key();
+    let deposit = ctx.accounts.escrow.deposit;
+    ctx.accounts.offer.set_inner(Offer {
+        escrow,
+        taker,
+        receive: credited,
+        deposit,
+        bump: ctx.bumps.offer,
+    });
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use crate::{errors::EscrowError, state::ArbiterEscrow};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct Dispute<'info> {
+    /// Either party can freeze expiry; only the arbiter can settle from here
+    #[account(
+        constraint = authority.key() == escrow.maker
+            || authority.key() == escrow.beneficiary @ EscrowError::Unauthorized,
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"arbiter", escrow.maker.as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, ArbiterEscrow>,
+}
+
+pub fn dispute_handler(ctx: Context<Dispute>) -> Result<()> {
+    ctx.accounts.escrow.disputed = true;
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use crate::{errors::EscrowError, program::AnchorEscrow, state::Config};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    // The config is a singleton, so `init` only ever succeeds once
+    #[account(
+        init,
+        payer = admin,
+        space = Config::INIT_SPACE + Config::DISCRIMINATOR.len(),
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    // Only the upgrade authority may set up the config, so nobody can front-run the deployer
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, AnchorEscrow>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ EscrowError::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_config_handler(
+    ctx: Context<InitializeConfig>,
+    fee_recipient: Pubkey,
+    fee_bps: u16,
+    max_referral_bps: u16,
+) -> Result<()> {
+    require!(
+        Config::is_valid_fee(fee_bps, max_referral_bps),
+        EscrowError::InvalidFee
+    );
+
+    ctx.accounts.config.set_inner(Config {
+        admin: ctx.accounts.admin.key(),
+        fee_recipient,
+        fee_bps,
+        max_referral_bps,
+        paused: false,
+        bump: ctx.bumps.config,
+    });
+    Ok(())
+}
@@ -1,10 +1,16 @@
 use crate::errors::EscrowError;
-use crate::state::Escrow;
+use crate::state::{Config, DutchAuction, Escrow, OraclePricing};
+use crate::transfer_fee::amount_after_fee;
 use anchor_lang::prelude::*;
 
+use anchor_lang::system_program::{transfer, Transfer};
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+    token::spl_token::native_mint,
+    token_interface::{
+        sync_native, transfer_checked, Mint, SyncNative, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
 };
 #[derive(Accounts)]
 #[instruction(seed: u64)]
@@ -28,6 +34,7 @@ pub struct Make<'info> {
         mint::token_program=token_program,
     )]
     pub mint_b: InterfaceAccount<'info, Mint>,
+    /// Not needed when mint A is native SOL, which is wrapped from the maker's lamports
     #[account(
         mut,
         associated_token::mint=mint_a,
@@ -35,7 +42,7 @@ pub struct Make<'info> {
         associated_token::token_program=token_program,
 
     )]
-    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init,
         payer=maker,
@@ -44,6 +51,12 @@ pub struct Make<'info> {
         associated_token::token_program=token_program,
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds=[b"config"],
+        bump=config.bump,
+        constraint=!config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
 
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
@@ -51,23 +64,55 @@ pub struct Make<'info> {
 }
 
 impl<'info> Make<'info> {
-    fn populate_escrow(&mut self, seed: u64, amount: u64, bump: u8) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    fn populate_escrow(
+        &mut self,
+        seed: u64,
+        receive: u64,
+        deposit: u64,
+        expiry: i64,
+        taker: Option<Pubkey>,
+        merkle_root: Option<[u8; 32]>,
+        auction: Option<DutchAuction>,
+        oracle: Option<OraclePricing>,
+        collection: Option<Pubkey>,
+        bump: u8,
+    ) -> Result<()> {
+        // A collection bid accepts any of its NFTs, so no single mint B is stored
+        let mint_b = match collection {
+            Some(_) => Pubkey::default(),
+            None => self.mint_b.key(),
+        };
         self.escrow.set_inner(Escrow {
             seed,
             maker: self.maker.key(),
             mint_a: self.mint_a.key(),
-            mint_b: self.mint_b.key(),
-            receive: amount,
+            mint_b,
+            receive,
+            deposit,
+            expiry,
+            taker,
+            merkle_root,
+            auction,
+            oracle,
+            collection,
             bump,
         });
         Ok(())
     }
     fn deposit_tokens(&mut self, amount: u64) -> Result<()> {
+        if native_mint::check_id(&self.mint_a.key()) {
+            return self.deposit_native(amount);
+        }
+        let maker_ata_a = self
+            .maker_ata_a
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?;
         transfer_checked(
             CpiContext::new(
                 self.token_program.to_account_info(),
                 TransferChecked {
-                    from: self.maker_ata_a.to_account_info(),
+                    from: maker_ata_a.to_account_info(),
                     mint: self.mint_a.to_account_info(),
                     to: self.vault.to_account_info(),
                     authority: self.maker.to_account_info(),
@@ -78,14 +123,94 @@ impl<'info> Make<'info> {
         )?;
         Ok(())
     }
+    fn deposit_native(&mut self, amount: u64) -> Result<()> {
+        transfer(
+            CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.maker.to_account_info(),
+                    to: self.vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        sync_native(CpiContext::new(
+            self.token_program.to_account_info(),
+            SyncNative {
+                account: self.vault.to_account_info(),
+            },
+        ))?;
+        Ok(())
+    }
 }
 
-pub fn make_handler(ctx: Context<Make>, seed: u64, receive: u64, amount: u64) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub fn make_handler(
+    ctx: Context<Make>,
+    seed: u64,
+    receive: u64,
+    amount: u64,
+    expiry: i64,
+    taker: Option<Pubkey>,
+    merkle_root: Option<[u8; 32]>,
+    auction: Option<DutchAuction>,
+    oracle: Option<OraclePricing>,
+    collection: Option<Pubkey>,
+) -> Result<()> {
     require_gt!(receive, 0, EscrowError::InvalidAmount);
     require_gt!(amount, 0, EscrowError::InvalidAmount);
 
+    // An auction must open above its floor price and run for a non-empty window
+    if let Some(auction) = auction {
+        require!(
+            auction.start_receive > receive && auction.start < auction.end,
+            EscrowError::InvalidSchedule
+        );
+    }
+
+    // Oracle pricing names its feed, replaces the auction schedule and can't discount to nothing
+    if let Some(oracle) = oracle {
+        require!(
+            auction.is_none()
+                && oracle.feed_id != [0; 32]
+                && oracle.max_staleness > 0
+                && oracle.spread_bps > -10_000,
+            EscrowError::InvalidSchedule
+        );
+    }
+
+    // Collection bids are paid one whole NFT at a time, so neither schedule applies;
+    // the collection mint is passed as mint B
+    if let Some(collection) = collection {
+        require!(
+            auction.is_none() && oracle.is_none(),
+            EscrowError::InvalidSchedule
+        );
+        require_keys_eq!(
+            collection,
+            ctx.accounts.mint_b.key(),
+            EscrowError::InvalidCollection
+        );
+    }
+
+    // Mint A may withhold a transfer fee, so record what actually lands in the vault
+    let clock = Clock::get()?;
+    let deposit = amount_after_fee(&ctx.accounts.mint_a.to_account_info(), clock.epoch, amount)?;
+    require_gt!(deposit, 0, EscrowError::InvalidAmount);
+
     ctx.accounts
-        .populate_escrow(seed, receive, ctx.bumps.escrow)?;
+        .populate_escrow(
+            seed,
+            receive,
+            deposit,
+            expiry,
+            taker,
+            merkle_root,
+            auction,
+            oracle,
+            collection,
+            ctx.bumps.escrow,
+        )?;
     ctx.accounts.deposit_tokens(amount)?;
     Ok(())
 }
\ No newline at end of file
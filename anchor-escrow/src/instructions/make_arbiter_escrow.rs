@@ -0,0 +1,168 @@
+use crate::errors::EscrowError;
+use crate::state::{ArbiterEscrow, Config};
+use crate::transfer_fee::amount_after_fee;
+use anchor_lang::prelude::*;
+
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::spl_token::native_mint,
+    token_interface::{
+        sync_native, transfer_checked, Mint, SyncNative, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeArbiterEscrow<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init,
+        payer=maker,
+        space=ArbiterEscrow::INIT_SPACE + ArbiterEscrow::DISCRIMINATOR.len(),
+        seeds=[b"arbiter",maker.key().as_ref(),seed.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub escrow: Account<'info, ArbiterEscrow>,
+    #[account(
+      mint::token_program=token_program,
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    /// Not needed when mint A is native SOL, which is wrapped from the maker's lamports
+    #[account(
+        mut,
+        associated_token::mint=mint_a,
+        associated_token::authority=maker,
+        associated_token::token_program=token_program,
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer=maker,
+        associated_token::mint = mint_a,
+        associated_token::authority=escrow,
+        associated_token::token_program=token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds=[b"config"],
+        bump=config.bump,
+        constraint=!config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeArbiterEscrow<'info> {
+    fn populate_escrow(
+        &mut self,
+        seed: u64,
+        expiry: i64,
+        beneficiary: Pubkey,
+        arbiter: Pubkey,
+        milestones: [u64; ArbiterEscrow::MAX_MILESTONES],
+        bump: u8,
+    ) -> Result<()> {
+        self.escrow.set_inner(ArbiterEscrow {
+            seed,
+            maker: self.maker.key(),
+            beneficiary,
+            arbiter,
+            mint_a: self.mint_a.key(),
+            expiry,
+            milestones,
+            released: 0,
+            disputed: false,
+            bump,
+        });
+        Ok(())
+    }
+    fn deposit_tokens(&mut self, amount: u64) -> Result<()> {
+        if native_mint::check_id(&self.mint_a.key()) {
+            return self.deposit_native(amount);
+        }
+        let maker_ata_a = self
+            .maker_ata_a
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?;
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: maker_ata_a.to_account_info(),
+                    mint: self.mint_a.to_account_info(),
+                    to: self.vault.to_account_info(),
+                    authority: self.maker.to_account_info(),
+                },
+            ),
+            amount,
+            self.mint_a.decimals,
+        )?;
+        Ok(())
+    }
+    fn deposit_native(&mut self, amount: u64) -> Result<()> {
+        transfer(
+            CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.maker.to_account_info(),
+                    to: self.vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        sync_native(CpiContext::new(
+            self.token_program.to_account_info(),
+            SyncNative {
+                account: self.vault.to_account_info(),
+            },
+        ))?;
+        Ok(())
+    }
+}
+
+pub fn make_arbiter_escrow_handler(
+    ctx: Context<MakeArbiterEscrow>,
+    seed: u64,
+    expiry: i64,
+    beneficiary: Pubkey,
+    arbiter: Pubkey,
+    milestones: Vec<u64>,
+) -> Result<()> {
+    require_keys_neq!(beneficiary, Pubkey::default(), EscrowError::InvalidTaker);
+    require_keys_neq!(arbiter, Pubkey::default(), EscrowError::Unauthorized);
+
+    // One to MAX_MILESTONES tranches, all funded up front
+    require!(
+        !milestones.is_empty() && milestones.len() <= ArbiterEscrow::MAX_MILESTONES,
+        EscrowError::InvalidMilestone
+    );
+    let mut schedule = [0u64; ArbiterEscrow::MAX_MILESTONES];
+    let mut amount = 0u64;
+    for (slot, milestone) in schedule.iter_mut().zip(milestones) {
+        require_gt!(milestone, 0, EscrowError::InvalidAmount);
+        *slot = milestone;
+        amount = amount
+            .checked_add(milestone)
+            .ok_or(EscrowError::InvalidAmount)?;
+    }
+
+    // Mint A may withhold a transfer fee, so the tranches are scaled down to what
+    // actually lands in the vault; the last release sweeps any rounding dust
+    let clock = Clock::get()?;
+    let deposit = amount_after_fee(&ctx.accounts.mint_a.to_account_info(), clock.epoch, amount)?;
+    for slot in schedule.iter_mut().filter(|slot| **slot > 0) {
+        *slot = (*slot as u128 * deposit as u128 / amount as u128) as u64;
+        // An unused slot is zero, so a tranche scaled down to nothing would vanish
+        require_gt!(*slot, 0, EscrowError::InvalidAmount);
+    }
+
+    ctx.accounts
+        .populate_escrow(seed, expiry, beneficiary, arbiter, schedule, ctx.bumps.escrow)?;
+    ctx.accounts.deposit_tokens(amount)?;
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use crate::errors::EscrowError;
+use crate::state::{AuctionEscrow, Config};
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeAuction<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init,
+        payer=maker,
+        space=AuctionEscrow::INIT_SPACE + AuctionEscrow::DISCRIMINATOR.len(),
+        seeds=[b"auction",maker.key().as_ref(),seed.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub auction: Account<'info, AuctionEscrow>,
+    #[account(
+      mint::token_program=token_program,
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mint::token_program=token_program,
+    )]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint=mint_a,
+        associated_token::authority=maker,
+        associated_token::token_program=token_program,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer=maker,
+        associated_token::mint = mint_a,
+        associated_token::authority=auction,
+        associated_token::token_program=token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds=[b"config"],
+        bump=config.bump,
+        constraint=!config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeAuction<'info> {
+    fn populate_auction(
+        &mut self,
+        seed: u64,
+        reserve: u64,
+        min_increment: u64,
+        end: i64,
+        bump: u8,
+    ) -> Result<()> {
+        self.auction.set_inner(AuctionEscrow {
+            seed,
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            reserve,
+            min_increment,
+            end,
+            highest_bid: 0,
+            highest_bidder: None,
+            bump,
+        });
+        Ok(())
+    }
+    fn deposit_tokens(&mut self, amount: u64) -> Result<()> {
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.maker_ata_a.to_account_info(),
+                    mint: self.mint_a.to_account_info(),
+                    to: self.vault.to_account_info(),
+                    authority: self.maker.to_account_info(),
+                },
+            ),
+            amount,
+            self.mint_a.decimals,
+        )?;
+        Ok(())
+    }
+}
+
+pub fn make_auction_handler(
+    ctx: Context<MakeAuction>,
+    seed: u64,
+    amount: u64,
+    reserve: u64,
+    min_increment: u64,
+    end: i64,
+) -> Result<()> {
+    require_gt!(amount, 0, EscrowError::InvalidAmount);
+    require_gt!(reserve, 0, EscrowError::InvalidAmount);
+
+    // A zero increment would let bidders leapfrog each other forever
+    require_gt!(min_increment, 0, EscrowError::InvalidAmount);
+
+    // An auction that has already ended couldn't take a single bid
+    let clock = Clock::get()?;
+    require_gt!(end, clock.unix_timestamp, EscrowError::EscrowExpired);
+
+    ctx.accounts
+        .populate_auction(seed, reserve, min_increment, end, ctx.bumps.auction)?;
+    ctx.accounts.deposit_tokens(amount)?;
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use crate::errors::EscrowError;
+use crate::state::{has_duplicate_mints, BundleEscrow, Config, Leg, MAX_BUNDLE_LEGS};
+use crate::transfer_fee::amount_after_fee;
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::{
+        create, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{transfer_checked, Mint, TokenInterface, TransferChecked},
+};
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeBundle<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init,
+        payer=maker,
+        space=BundleEscrow::INIT_SPACE + BundleEscrow::DISCRIMINATOR.len(),
+        seeds=[b"bundle",maker.key().as_ref(),seed.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub bundle: Account<'info, BundleEscrow>,
+    #[account(
+        seeds=[b"config"],
+        bump=config.bump,
+        constraint=!config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    // remaining accounts: `[mint_a, maker_ata_a, vault]` for each mint A leg, in order
+}
+
+impl<'info> MakeBundle<'info> {
+    /// Opens the vault for one mint A leg and deposits `amount` into it, returning the
+    /// leg as credited after any transfer fee.
+    fn deposit_leg(
+        &self,
+        mint_a: &'info AccountInfo<'info>,
+        maker_ata_a: &'info AccountInfo<'info>,
+        vault: &'info AccountInfo<'info>,
+        amount: u64,
+        epoch: u64,
+    ) -> Result<Leg> {
+        require_keys_eq!(
+            *mint_a.owner,
+            self.token_program.key(),
+            ErrorCode::ConstraintMintTokenProgram
+        );
+        let mint = InterfaceAccount::<Mint>::try_from(mint_a)?;
+        require_keys_eq!(
+            maker_ata_a.key(),
+            get_associated_token_address_with_program_id(
+                &self.maker.key(),
+                &mint_a.key(),
+                &self.token_program.key()
+            ),
+            ErrorCode::ConstraintAssociated
+        );
+
+        // Each leg gets its own vault, the bundle's associated token account for that mint
+        create(CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            Create {
+                payer: self.maker.to_account_info(),
+                associated_token: vault.clone(),
+                authority: self.bundle.to_account_info(),
+                mint: mint_a.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+        ))?;
+
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: maker_ata_a.clone(),
+                    mint: mint_a.clone(),
+                    to: vault.clone(),
+                    authority: self.maker.to_account_info(),
+                },
+            ),
+            amount,
+            mint.decimals,
+        )?;
+
+        // Each mint may withhold a transfer fee, so record what actually lands in its vault
+        let deposit = amount_after_fee(mint_a, epoch, amount)?;
+        require_gt!(deposit, 0, EscrowError::InvalidAmount);
+        Ok(Leg {
+            mint: mint_a.key(),
+            amount: deposit,
+        })
+    }
+}
+
+pub fn make_bundle_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, MakeBundle<'info>>,
+    seed: u64,
+    amounts_a: Vec<u64>,
+    legs_b: Vec<Leg>,
+) -> Result<()> {
+    // Each side needs between one and MAX_BUNDLE_LEGS legs, none of them empty
+    require!(
+        (1..=MAX_BUNDLE_LEGS).contains(&amounts_a.len())
+            && (1..=MAX_BUNDLE_LEGS).contains(&legs_b.len()),
+        EscrowError::InvalidAmount
+    );
+    require!(
+        !amounts_a.contains(&0) && legs_b.iter().all(|leg| leg.amount > 0),
+        EscrowError::InvalidAmount
+    );
+    require!(!has_duplicate_mints(&legs_b), EscrowError::InvalidMintB);
+    require_eq!(
+        ctx.remaining_accounts.len(),
+        amounts_a.len() * 3,
+        ErrorCode::AccountNotEnoughKeys
+    );
+
+    let clock = Clock::get()?;
+    let mut legs_a = Vec::with_capacity(amounts_a.len());
+    for (accounts, amount) in ctx.remaining_accounts.chunks_exact(3).zip(amounts_a) {
+        let [mint_a, maker_ata_a, vault] = accounts else {
+            return err!(ErrorCode::AccountNotEnoughKeys);
+        };
+        legs_a.push(
+            ctx.accounts
+                .deposit_leg(mint_a, maker_ata_a, vault, amount, clock.epoch)?,
+        );
+    }
+    require!(!has_duplicate_mints(&legs_a), EscrowError::InvalidMintA);
+
+    let maker = ctx.accounts.maker.key();
+    ctx.accounts.bundle.set_inner(BundleEscrow {
+        seed,
+        maker,
+        legs_a,
+        legs_b,
+        bump: ctx.bumps.bundle,
+    });
+    Ok(())
+}
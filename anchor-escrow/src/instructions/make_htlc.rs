@@ -0,0 +1,116 @@
+use crate::errors::EscrowError;
+use crate::state::{Config, HtlcEscrow};
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeHtlc<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init,
+        payer=maker,
+        space=HtlcEscrow::INIT_SPACE + HtlcEscrow::DISCRIMINATOR.len(),
+        seeds=[b"htlc",maker.key().as_ref(),seed.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub escrow: Account<'info, HtlcEscrow>,
+    #[account(
+      mint::token_program=token_program,
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint=mint_a,
+        associated_token::authority=maker,
+        associated_token::token_program=token_program,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer=maker,
+        associated_token::mint = mint_a,
+        associated_token::authority=escrow,
+        associated_token::token_program=token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds=[b"config"],
+        bump=config.bump,
+        constraint=!config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeHtlc<'info> {
+    fn populate_escrow(
+        &mut self,
+        seed: u64,
+        recipient: Pubkey,
+        sha256_hash: [u8; 32],
+        keccak_hash: [u8; 32],
+        expiry: i64,
+        bump: u8,
+    ) -> Result<()> {
+        self.escrow.set_inner(HtlcEscrow {
+            seed,
+            maker: self.maker.key(),
+            recipient,
+            mint_a: self.mint_a.key(),
+            sha256_hash,
+            keccak_hash,
+            expiry,
+            bump,
+        });
+        Ok(())
+    }
+    fn deposit_tokens(&mut self, amount: u64) -> Result<()> {
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.maker_ata_a.to_account_info(),
+                    mint: self.mint_a.to_account_info(),
+                    to: self.vault.to_account_info(),
+                    authority: self.maker.to_account_info(),
+                },
+            ),
+            amount,
+            self.mint_a.decimals,
+        )?;
+        Ok(())
+    }
+}
+
+pub fn make_htlc_handler(
+    ctx: Context<MakeHtlc>,
+    seed: u64,
+    amount: u64,
+    expiry: i64,
+    recipient: Pubkey,
+    sha256_hash: [u8; 32],
+    keccak_hash: [u8; 32],
+) -> Result<()> {
+    require_gt!(amount, 0, EscrowError::InvalidAmount);
+    require_keys_neq!(recipient, Pubkey::default(), EscrowError::InvalidTaker);
+
+    // The SHA-256 lock is mandatory; the Keccak-256 one may be left zeroed
+    require!(sha256_hash != [0; 32], EscrowError::InvalidPreimage);
+
+    // A timelock already in the past would let the maker refund straight away
+    let clock = Clock::get()?;
+    require_gt!(expiry, clock.unix_timestamp, EscrowError::EscrowExpired);
+
+    ctx.accounts
+        .populate_escrow(seed, recipient, sha256_hash, keccak_hash, expiry, ctx.bumps.escrow)?;
+    ctx.accounts.deposit_tokens(amount)?;
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use crate::errors::EscrowError;
+use crate::state::{Config, VestingEscrow};
+use crate::transfer_fee::amount_after_fee;
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeVestingEscrow<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init,
+        payer=maker,
+        space=VestingEscrow::INIT_SPACE + VestingEscrow::DISCRIMINATOR.len(),
+        seeds=[b"vesting",maker.key().as_ref(),seed.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub escrow: Account<'info, VestingEscrow>,
+    #[account(
+      mint::token_program=token_program,
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint=mint_a,
+        associated_token::authority=maker,
+        associated_token::token_program=token_program,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer=maker,
+        associated_token::mint = mint_a,
+        associated_token::authority=escrow,
+        associated_token::token_program=token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds=[b"config"],
+        bump=config.bump,
+        constraint=!config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeVestingEscrow<'info> {
+    #[allow(clippy::too_many_arguments)]
+    fn populate_escrow(
+        &mut self,
+        seed: u64,
+        beneficiary: Pubkey,
+        start: i64,
+        cliff: i64,
+        end: i64,
+        total: u64,
+        bump: u8,
+    ) -> Result<()> {
+        self.escrow.set_inner(VestingEscrow {
+            seed,
+            maker: self.maker.key(),
+            beneficiary,
+            mint_a: self.mint_a.key(),
+            start,
+            cliff,
+            end,
+            total,
+            withdrawn: 0,
+            bump,
+        });
+        Ok(())
+    }
+    fn deposit_tokens(&mut self, amount: u64) -> Result<()> {
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.maker_ata_a.to_account_info(),
+                    mint: self.mint_a.to_account_info(),
+                    to: self.vault.to_account_info(),
+                    authority: self.maker.to_account_info(),
+                },
+            ),
+            amount,
+            self.mint_a.decimals,
+        )?;
+        Ok(())
+    }
+}
+
+pub fn make_vesting_escrow_handler(
+    ctx: Context<MakeVestingEscrow>,
+    seed: u64,
+    amount: u64,
+    start: i64,
+    cliff: i64,
+    end: i64,
+    beneficiary: Pubkey,
+) -> Result<()> {
+    require_gt!(amount, 0, EscrowError::InvalidAmount);
+    require_keys_neq!(beneficiary, Pubkey::default(), EscrowError::InvalidTaker);
+
+    // The cliff must fall inside a non-empty vesting window
+    require!(
+        start <= cliff && cliff <= end && start < end,
+        EscrowError::InvalidSchedule
+    );
+
+    // Mint A may withhold a transfer fee, so vest what actually lands in the vault
+    let clock = Clock::get()?;
+    let total = amount_after_fee(&ctx.accounts.mint_a.to_account_info(), clock.epoch, amount)?;
+    require_gt!(total, 0, EscrowError::InvalidAmount);
+
+    ctx.accounts
+        .populate_escrow(seed, beneficiary, start, cliff, end, total, ctx.bumps.escrow)?;
+    ctx.accounts.deposit_tokens(amount)?;
+    Ok(())
+}
@@ -5,4 +5,76 @@ pub mod refund;
 pub use refund::*;
 
 pub mod take;
-pub use take::*;
\ No newline at end of file
+pub use take::*;
+
+pub mod update;
+pub use update::*;
+
+pub mod initialize_config;
+pub use initialize_config::*;
+
+pub mod update_config;
+pub use update_config::*;
+
+pub mod set_paused;
+pub use set_paused::*;
+
+pub mod make_arbiter_escrow;
+pub use make_arbiter_escrow::*;
+
+pub mod release;
+pub use release::*;
+
+pub mod arbiter_refund;
+pub use arbiter_refund::*;
+
+pub mod dispute;
+pub use dispute::*;
+
+pub mod release_milestone;
+pub use release_milestone::*;
+
+pub mod make_vesting_escrow;
+pub use make_vesting_escrow::*;
+
+pub mod claim;
+pub use claim::*;
+
+pub mod make_htlc;
+pub use make_htlc::*;
+
+pub mod claim_htlc;
+pub use claim_htlc::*;
+
+pub mod refund_htlc;
+pub use refund_htlc::*;
+
+pub mod make_auction;
+pub use make_auction::*;
+
+pub mod place_bid;
+pub use place_bid::*;
+
+pub mod withdraw_bid;
+pub use withdraw_bid::*;
+
+pub mod settle;
+pub use settle::*;
+
+pub mod make_bundle;
+pub use make_bundle::*;
+
+pub mod take_bundle;
+pub use take_bundle::*;
+
+pub mod refund_bundle;
+pub use refund_bundle::*;
+
+pub mod counter_offer;
+pub use counter_offer::*;
+
+pub mod accept_counter;
+pub use accept_counter::*;
+
+pub mod withdraw_counter;
+pub use withdraw_counter::*;
@@ -0,0 +1,113 @@
+use crate::errors::EscrowError;
+use crate::state::{AuctionEscrow, Bid, Config};
+use crate::transfer_fee::amount_after_fee;
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    #[account(
+        mut,
+        seeds=[b"auction",auction.maker.as_ref(),auction.seed.to_le_bytes().as_ref()],
+        bump=auction.bump,
+        has_one=mint_b @ EscrowError::InvalidMintB,
+    )]
+    pub auction: Box<Account<'info, AuctionEscrow>>,
+    /// A bidder's first bid opens their bid account and vault; later bids top them up
+    #[account(
+        init_if_needed,
+        payer=bidder,
+        space=Bid::INIT_SPACE + Bid::DISCRIMINATOR.len(),
+        seeds=[b"bid",auction.key().as_ref(),bidder.key().as_ref()],
+        bump,
+    )]
+    pub bid: Box<Account<'info, Bid>>,
+    #[account(
+        mint::token_program=token_program,
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint=mint_b,
+        associated_token::authority=bidder,
+        associated_token::token_program=token_program,
+    )]
+    pub bidder_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer=bidder,
+        associated_token::mint=mint_b,
+        associated_token::authority=bid,
+        associated_token::token_program=token_program,
+    )]
+    pub bid_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds=[b"config"],
+        bump=config.bump,
+        constraint=!config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PlaceBid<'info> {
+    fn deposit_bid(&mut self, amount: u64) -> Result<()> {
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.bidder_ata_b.to_account_info(),
+                    mint: self.mint_b.to_account_info(),
+                    to: self.bid_vault.to_account_info(),
+                    authority: self.bidder.to_account_info(),
+                },
+            ),
+            amount,
+            self.mint_b.decimals,
+        )?;
+        Ok(())
+    }
+}
+
+pub fn place_bid_handler(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+    require_gt!(amount, 0, EscrowError::InvalidAmount);
+
+    let clock = Clock::get()?;
+    require!(
+        !ctx.accounts.auction.is_ended(clock.unix_timestamp),
+        EscrowError::EscrowExpired
+    );
+
+    // Mint B may withhold a transfer fee, so a bid only counts what lands in its vault
+    let credited = amount_after_fee(&ctx.accounts.mint_b.to_account_info(), clock.epoch, amount)?;
+    let total = ctx
+        .accounts
+        .bid
+        .amount
+        .checked_add(credited)
+        .ok_or(EscrowError::InvalidAmount)?;
+    require_gte!(total, ctx.accounts.auction.min_bid(), EscrowError::BidTooLow);
+
+    ctx.accounts.deposit_bid(amount)?;
+
+    let bidder = ctx.accounts.bidder.key();
+    let auction_key = ctx.accounts.auction.key();
+    let bid = &mut ctx.accounts.bid;
+    bid.auction = auction_key;
+    bid.bidder = bidder;
+    bid.amount = total;
+    bid.bump = ctx.bumps.bid;
+
+    let auction = &mut ctx.accounts.auction;
+    auction.highest_bid = total;
+    auction.highest_bidder = Some(bidder);
+    Ok(())
+}
@@ -2,6 +2,7 @@ use crate::{errors::EscrowError, state::Escrow};
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
+    token::spl_token::native_mint,
     token_interface::{
         close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
         TransferChecked,
@@ -34,6 +35,7 @@ pub struct Refund<'info> {
     )]
     pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    /// Not needed when mint A is native SOL, since the vault unwraps straight to the maker
     #[account(
         init_if_needed,
         payer = maker,
@@ -41,7 +43,7 @@ pub struct Refund<'info> {
         associated_token::authority = maker,
         associated_token::token_program = token_program
     )]
-    pub maker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub maker_ata_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     pub associated_token_program: Program<'info, AssociatedToken>,
 
@@ -59,20 +61,27 @@ impl<'info> Refund<'info> {
             &[self.escrow.bump],
         ]];
 
-        transfer_checked(
-            CpiContext::new_with_signer(
-                self.token_program.to_account_info(),
-                TransferChecked {
-                    from: self.vault.to_account_info(),
-                    to: self.maker_ata_a.to_account_info(),
-                    mint: self.mint_a.to_account_info(),
-                    authority: self.escrow.to_account_info(),
-                },
-                &signer_seeds,
-            ),
-            self.vault.amount,
-            self.mint_a.decimals,
-        )?;
+        // A native vault can be closed with a balance, which unwraps it straight to the maker
+        if !native_mint::check_id(&self.mint_a.key()) {
+            let maker_ata_a = self
+                .maker_ata_a
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+            transfer_checked(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    TransferChecked {
+                        from: self.vault.to_account_info(),
+                        to: maker_ata_a.to_account_info(),
+                        mint: self.mint_a.to_account_info(),
+                        authority: self.escrow.to_account_info(),
+                    },
+                    &signer_seeds,
+                ),
+                self.vault.amount,
+                self.mint_a.decimals,
+            )?;
+        }
 
         close_account(CpiContext::new_with_signer(
             self.token_program.to_account_info(),
@@ -0,0 +1,128 @@
+use crate::errors::EscrowError;
+use crate::state::{BundleEscrow, Leg};
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::{
+        create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+#[derive(Accounts)]
+pub struct RefundBundle<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        close=maker,
+        seeds=[b"bundle",maker.key().as_ref(),bundle.seed.to_le_bytes().as_ref()],
+        bump=bundle.bump,
+        has_one=maker @ EscrowError::InvalidMaker,
+    )]
+    pub bundle: Box<Account<'info, BundleEscrow>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    // remaining accounts: `[mint_a, vault, maker_ata_a]` for each mint A leg, in the order
+    // the bundle stores them
+}
+
+impl<'info> RefundBundle<'info> {
+    /// Returns one mint A vault to the maker and closes it.
+    fn refund_leg(&self, leg: &Leg, accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let [mint_a, vault, maker_ata_a] = accounts else {
+            return err!(ErrorCode::AccountNotEnoughKeys);
+        };
+        require_keys_eq!(mint_a.key(), leg.mint, ErrorCode::ConstraintAddress);
+        require_keys_eq!(
+            *mint_a.owner,
+            self.token_program.key(),
+            ErrorCode::ConstraintMintTokenProgram
+        );
+        let mint = InterfaceAccount::<Mint>::try_from(mint_a)?;
+        let token_program = self.token_program.key();
+        require_keys_eq!(
+            vault.key(),
+            get_associated_token_address_with_program_id(
+                &self.bundle.key(),
+                &leg.mint,
+                &token_program
+            ),
+            ErrorCode::ConstraintAssociated
+        );
+        require_keys_eq!(
+            maker_ata_a.key(),
+            get_associated_token_address_with_program_id(
+                &self.maker.key(),
+                &leg.mint,
+                &token_program
+            ),
+            ErrorCode::ConstraintAssociated
+        );
+        create_idempotent(CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            Create {
+                payer: self.maker.to_account_info(),
+                associated_token: maker_ata_a.clone(),
+                authority: self.maker.to_account_info(),
+                mint: mint_a.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+        ))?;
+        let amount = InterfaceAccount::<TokenAccount>::try_from(vault)?.amount;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"bundle",
+            self.maker.to_account_info().key.as_ref(),
+            &self.bundle.seed.to_le_bytes()[..],
+            &[self.bundle.bump],
+        ]];
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: vault.clone(),
+                    to: maker_ata_a.clone(),
+                    mint: mint_a.clone(),
+                    authority: self.bundle.to_account_info(),
+                },
+                &signer_seeds,
+            ),
+            amount,
+            mint.decimals,
+        )?;
+
+        close_account(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: vault.clone(),
+                authority: self.bundle.to_account_info(),
+                destination: self.maker.to_account_info(),
+            },
+            &signer_seeds,
+        ))?;
+        Ok(())
+    }
+}
+
+pub fn refund_bundle_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RefundBundle<'info>>,
+) -> Result<()> {
+    // Every vault must be emptied before the bundle can close
+    let legs_a = ctx.accounts.bundle.legs_a.clone();
+    require_eq!(
+        ctx.remaining_accounts.len(),
+        legs_a.len() * 3,
+        ErrorCode::AccountNotEnoughKeys
+    );
+
+    for (leg, accounts) in legs_a.iter().zip(ctx.remaining_accounts.chunks_exact(3)) {
+        ctx.accounts.refund_leg(leg, accounts)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use crate::{errors::EscrowError, state::ArbiterEscrow};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+#[derive(Accounts)]
+pub struct ReleaseMilestone<'info> {
+    /// Same authority as a full release: the arbiter, or the maker confirming delivery
+    #[account(
+        mut,
+        constraint = authority.key() == escrow.arbiter
+            || authority.key() == escrow.maker @ EscrowError::Unauthorized,
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    pub beneficiary: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"arbiter", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+        has_one = maker @ EscrowError::InvalidMaker,
+        has_one = beneficiary @ EscrowError::InvalidTaker,
+        has_one = mint_a @ EscrowError::InvalidMintA,
+    )]
+    pub escrow: Box<Account<'info, ArbiterEscrow>>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_a,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = token_program
+    )]
+    pub beneficiary_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ReleaseMilestone<'info> {
+    fn release_tranche(&mut self, amount: u64, is_final: bool) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"arbiter",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.vault.to_account_info(),
+                    to: self.beneficiary_ata_a.to_account_info(),
+                    mint: self.mint_a.to_account_info(),
+                    authority: self.escrow.to_account_info(),
+                },
+                &signer_seeds,
+            ),
+            amount,
+            self.mint_a.decimals,
+        )?;
+
+        if is_final {
+            close_account(CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                CloseAccount {
+                    account: self.vault.to_account_info(),
+                    authority: self.escrow.to_account_info(),
+                    destination: self.maker.to_account_info(),
+                },
+                &signer_seeds,
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn release_milestone_handler(ctx: Context<ReleaseMilestone>, index: u8) -> Result<()> {
+    let index = index as usize;
+    let escrow = &mut ctx.accounts.escrow;
+    require!(
+        index < escrow.milestone_count() && !escrow.is_milestone_released(index),
+        EscrowError::InvalidMilestone
+    );
+
+    escrow.released |= 1 << index;
+    let is_final = escrow.is_fully_released();
+
+    // The last tranche sweeps the vault, absorbing anything a transfer fee withheld
+    let amount = if is_final {
+        ctx.accounts.vault.amount
+    } else {
+        escrow.milestones[index]
+    };
+
+    ctx.accounts.release_tranche(amount, is_final)?;
+    if is_final {
+        ctx.accounts
+            .escrow
+            .close(ctx.accounts.maker.to_account_info())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use crate::{
+    errors::EscrowError,
+    state::{AuctionEscrow, Bid},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+#[derive(Accounts)]
+pub struct Settle<'info> {
+    /// Anyone may crank settlement once bidding has closed
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        close = maker,
+        seeds = [b"auction", maker.key().as_ref(), auction.seed.to_le_bytes().as_ref()],
+        bump = auction.bump,
+        has_one = maker @ EscrowError::InvalidMaker,
+        has_one = mint_a @ EscrowError::InvalidMintA,
+        has_one = mint_b @ EscrowError::InvalidMintB,
+        constraint = auction.highest_bidder.unwrap_or(auction.maker) == winner.key() @ EscrowError::InvalidTaker,
+    )]
+    pub auction: Box<Account<'info, AuctionEscrow>>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = auction,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The leading bidder, or the maker when nobody met the reserve
+    #[account(mut)]
+    pub winner: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_a,
+        associated_token::authority = winner,
+        associated_token::token_program = token_program
+    )]
+    pub winner_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Not needed when there is no winning bid
+    #[account(
+        mut,
+        seeds = [b"bid", auction.key().as_ref(), winner.key().as_ref()],
+        bump = bid.bump,
+    )]
+    pub bid: Option<Box<Account<'info, Bid>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = bid,
+        associated_token::token_program = token_program
+    )]
+    pub bid_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Settle<'info> {
+    fn release_and_close_vault(&mut self) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"auction",
+            self.maker.to_account_info().key.as_ref(),
+            &self.auction.seed.to_le_bytes()[..],
+            &[self.auction.bump],
+        ]];
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.vault.to_account_info(),
+                    to: self.winner_ata_a.to_account_info(),
+                    mint: self.mint_a.to_account_info(),
+                    authority: self.auction.to_account_info(),
+                },
+                &signer_seeds,
+            ),
+            self.vault.amount,
+            self.mint_a.decimals,
+        )?;
+
+        close_account(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.vault.to_account_info(),
+                authority: self.auction.to_account_info(),
+                destination: self.maker.to_account_info(),
+            },
+            &signer_seeds,
+        ))?;
+
+        Ok(())
+    }
+    /// Sweeps the winning bid vault to the maker and returns its rent to the winner.
+    fn pay_winning_bid(&mut self) -> Result<()> {
+        let (Some(bid), Some(bid_vault), Some(maker_ata_b)) =
+            (&self.bid, &self.bid_vault, &self.maker_ata_b)
+        else {
+            return err!(EscrowError::MissingTokenAccount);
+        };
+
+        let auction_key = self.auction.key();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"bid",
+            auction_key.as_ref(),
+            self.winner.to_account_info().key.as_ref(),
+            &[bid.bump],
+        ]];
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: bid_vault.to_account_info(),
+                    to: maker_ata_b.to_account_info(),
+                    mint: self.mint_b.to_account_info(),
+                    authority: bid.to_account_info(),
+                },
+                &signer_seeds,
+            ),
+            bid_vault.amount,
+            self.mint_b.decimals,
+        )?;
+
+        close_account(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: bid_vault.to_account_info(),
+                authority: bid.to_account_info(),
+                destination: self.winner.to_account_info(),
+            },
+            &signer_seeds,
+        ))?;
+
+        bid.close(self.winner.to_account_info())
+    }
+}
+
+pub fn settle_handler(ctx: Context<Settle>) -> Result<()> {
+    // Bids stay open until the end time, after which anyone may crank settlement
+    let clock = Clock::get()?;
+    require!(
+        ctx.accounts.auction.is_ended(clock.unix_timestamp),
+        EscrowError::EscrowNotExpired
+    );
+
+    // Without a bid at or above the reserve the lot goes back to the maker
+    ctx.accounts.release_and_close_vault()?;
+    if ctx.accounts.auction.highest_bidder.is_some() {
+        ctx.accounts.pay_winning_bid()?;
+    }
+
+    Ok(())
+}
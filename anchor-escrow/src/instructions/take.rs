@@ -1,9 +1,15 @@
 use crate::errors::EscrowError;
-use crate::state::Escrow;
+use crate::hash::verify_merkle_proof;
+use crate::metadata::Metadata;
+use crate::oracle::PriceFeed;
+use crate::transfer_fee::{amount_after_fee, amount_before_fee};
+use crate::state::{remaining_after_fill, Config, Escrow};
 use anchor_lang::prelude::*;
 
+use anchor_lang::system_program::{transfer, Transfer};
 use anchor_spl::{
     associated_token::AssociatedToken,
+    token::spl_token::native_mint,
     token_interface::{
         close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
         TransferChecked,
@@ -17,12 +23,12 @@ pub struct Take<'info> {
     pub maker: SystemAccount<'info>,
     #[account(
         mut,
-        close=maker,
         seeds=[b"escrow",maker.key().as_ref(),escrow.seed.to_le_bytes().as_ref()],
         bump=escrow.bump,
         has_one=maker @ EscrowError::InvalidMaker,
         has_one=mint_a @ EscrowError::InvalidMintA,
-        has_one=mint_b @ EscrowError::InvalidMintB,
+        constraint=escrow.collection.is_some() || escrow.mint_b == mint_b.key() @ EscrowError::InvalidMintB,
+        constraint=escrow.is_taker_allowed(&taker.key()) @ EscrowError::InvalidTaker,
     )]
     pub escrow: Box<Account<'info, Escrow>>,
 
@@ -35,6 +41,7 @@ pub struct Take<'info> {
         associated_token::token_program=token_program,
     )]
     pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// Not needed when mint A is native SOL, which is received through `unwrap_account`
     #[account(
         init_if_needed,
         payer=taker,
@@ -42,14 +49,27 @@ pub struct Take<'info> {
         associated_token::authority=taker,
         associated_token::token_program=token_program,
     )]
-    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub taker_ata_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    /// Temporary wSOL account for a native mint A, closed to the taker within the take
+    /// so exactly the filled amount is unwrapped
+    #[account(
+        init,
+        payer=taker,
+        seeds=[b"unwrap",taker.key().as_ref()],
+        bump,
+        token::mint=mint_a,
+        token::authority=taker,
+        token::token_program=token_program,
+    )]
+    pub unwrap_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    /// Not needed when mint B is native SOL, which is paid from the taker's lamports
     #[account(
        mut,
        associated_token::mint=mint_b,
        associated_token::authority=taker,
        associated_token::token_program=token_program,
     )]
-    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub taker_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
     #[account(
         init_if_needed,
         payer=taker,
@@ -57,66 +77,307 @@ pub struct Take<'info> {
         associated_token::authority=maker,
         associated_token::token_program=token_program,
     )]
-    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub maker_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(
+        seeds=[b"config"],
+        bump=config.bump,
+        constraint=!config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(mut, address=config.fee_recipient @ EscrowError::InvalidFeeRecipient)]
+    pub treasury: SystemAccount<'info>,
+    /// Not needed when mint B is native SOL or the protocol fee is zero
+    #[account(
+        init_if_needed,
+        payer=taker,
+        associated_token::mint=mint_b,
+        associated_token::authority=treasury,
+        associated_token::token_program=token_program,
+    )]
+    pub treasury_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    /// CHECK: the price feed of an oracle escrow, matched against the stored key
+    /// and parsed by `price` before its price is used.
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    /// CHECK: the metadata of the NFT offered to a collection bid, checked against
+    /// mint B's metadata address and parsed by `check_collection_nft`.
+    pub metadata: Option<UncheckedAccount<'info>>,
+    /// CHECK: the referrer's mint B token account, or their wallet when mint B is native SOL.
+    /// Validated in `transfer_to_maker` before anything is paid to it.
+    #[account(mut)]
+    pub referrer: Option<UncheckedAccount<'info>>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Take<'info> {
-    fn transfer_to_maker(&mut self) -> Result<()> {
+    /// Collection bids accept any NFT whose metadata carries the verified collection.
+    fn check_collection_nft(&self, collection: &Pubkey) -> Result<()> {
+        require!(
+            self.mint_b.decimals == 0 && self.mint_b.supply == 1,
+            EscrowError::InvalidNft
+        );
+        let metadata = self.metadata.as_ref().ok_or(EscrowError::InvalidNft)?;
+        let metadata = Metadata::load(&metadata.to_account_info(), &self.mint_b.key())?;
+        require!(
+            metadata.is_verified_member_of(collection),
+            EscrowError::InvalidCollection
+        );
+        Ok(())
+    }
+    /// Price in mint B for the whole remaining deposit, read from the price feed for
+    /// oracle escrows and never below the maker's `receive` floor.
+    fn price(&self, current_timestamp: i64) -> Result<u64> {
+        let Some(oracle) = self.escrow.oracle else {
+            return Ok(self.escrow.receive_at(current_timestamp));
+        };
+        let price_feed = self
+            .price_feed
+            .as_ref()
+            .filter(|price_feed| price_feed.key() == oracle.price_feed)
+            .ok_or(EscrowError::InvalidPriceFeed)?;
+        let feed = PriceFeed::load(&price_feed.to_account_info())?;
+        feed.check_feed_id(&oracle.feed_id)?;
+        feed.check_fresh(current_timestamp, oracle.max_staleness)?;
+        feed.check_confidence(oracle.max_conf_bps)?;
+
+        let quote = feed
+            .quote(
+                self.escrow.deposit,
+                self.mint_a.decimals,
+                self.mint_b.decimals,
+                oracle.spread_bps,
+            )
+            .ok_or(EscrowError::InvalidPriceFeed)?;
+        Ok(quote.max(self.escrow.receive))
+    }
+    fn transfer_to_maker(
+        &mut self,
+        receive: u64,
+        referral_bps: u16,
+        max_pay: u64,
+        epoch: u64,
+    ) -> Result<()> {
+        // The protocol fee comes out of the maker's mint B proceeds, and the referral out of the fee
+        let protocol_fee = self.config.fee_for(receive);
+        let referral_fee = match &self.referrer {
+            Some(referrer) => {
+                if !native_mint::check_id(&self.mint_b.key()) {
+                    require_keys_eq!(
+                        *referrer.owner,
+                        self.token_program.key(),
+                        ErrorCode::AccountOwnedByWrongProgram
+                    );
+                    let referrer_ata_b =
+                        TokenAccount::try_deserialize(&mut &referrer.try_borrow_data()?[..])?;
+                    require_keys_eq!(
+                        referrer_ata_b.mint,
+                        self.mint_b.key(),
+                        EscrowError::InvalidMintB
+                    );
+                }
+                self.config
+                    .referral_fee_for(protocol_fee, referral_bps)
+                    .ok_or(EscrowError::InvalidFee)?
+            }
+            None => {
+                require_eq!(referral_bps, 0, EscrowError::InvalidFee);
+                0
+            }
+        };
+        let fee = protocol_fee - referral_fee;
+        let maker_receive = receive - protocol_fee;
+
+        // Cap everything leaving the taker, transfer fee gross-ups included
+        let paid = self
+            .gross_mint_b(maker_receive, epoch)?
+            .saturating_add(self.gross_mint_b(fee, epoch)?)
+            .saturating_add(self.gross_mint_b(referral_fee, epoch)?);
+        require_gte!(max_pay, paid, EscrowError::SlippageExceeded);
+
+        let maker_ata_b = self.maker_ata_b.as_ref().map(|ata| ata.to_account_info());
+        self.pay_mint_b(self.maker.to_account_info(), maker_ata_b, maker_receive, epoch)?;
+        if fee > 0 {
+            let treasury_ata_b = self.treasury_ata_b.as_ref().map(|ata| ata.to_account_info());
+            self.pay_mint_b(self.treasury.to_account_info(), treasury_ata_b, fee, epoch)?;
+        }
+        if let Some(referrer) = self.referrer.as_ref().filter(|_| referral_fee > 0) {
+            let referrer = referrer.to_account_info();
+            self.pay_mint_b(referrer.clone(), Some(referrer), referral_fee, epoch)?;
+        }
+        Ok(())
+    }
+    /// Mint B the taker sends so the recipient gets exactly `amount`, grossed up for any transfer fee.
+    fn gross_mint_b(&self, amount: u64, epoch: u64) -> Result<u64> {
+        if native_mint::check_id(&self.mint_b.key()) {
+            return Ok(amount);
+        }
+        amount_before_fee(&self.mint_b.to_account_info(), epoch, amount)
+    }
+    /// Pays `amount` of mint B from the taker to `wallet`, or to its `ata` for SPL mints.
+    fn pay_mint_b(
+        &self,
+        wallet: AccountInfo<'info>,
+        ata: Option<AccountInfo<'info>>,
+        amount: u64,
+        epoch: u64,
+    ) -> Result<()> {
+        if native_mint::check_id(&self.mint_b.key()) {
+            return transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.taker.to_account_info(),
+                        to: wallet,
+                    },
+                ),
+                amount,
+            );
+        }
+        let (Some(taker_ata_b), Some(ata)) = (&self.taker_ata_b, ata) else {
+            return err!(EscrowError::MissingTokenAccount);
+        };
+
+        let amount = self.gross_mint_b(amount, epoch)?;
         transfer_checked(
             CpiContext::new(
                 self.token_program.to_account_info(),
                 TransferChecked {
-                    from: self.taker_ata_b.to_account_info(),
-                    to: self.maker_ata_b.to_account_info(),
+                    from: taker_ata_b.to_account_info(),
+                    to: ata,
                     mint: self.mint_b.to_account_info(),
                     authority: self.taker.to_account_info(),
                 },
             ),
-            self.escrow.receive,
+            amount,
             self.mint_b.decimals,
         )?;
         Ok(())
     }
-    fn withdraw_and_close_vault(&mut self) -> Result<()> {
+    fn withdraw_from_vault(&mut self, amount: u64, close_vault: bool) -> Result<()> {
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
             self.maker.to_account_info().key.as_ref(),
             &self.escrow.seed.to_le_bytes()[..],
             &[self.escrow.bump],
         ]];
+        // Native SOL lands in the temporary account, leaving any wSOL the taker holds alone
+        let is_native = native_mint::check_id(&self.mint_a.key());
+        let destination = if is_native {
+            self.unwrap_account.as_ref().map(|account| account.to_account_info())
+        } else {
+            self.taker_ata_a.as_ref().map(|ata| ata.to_account_info())
+        }
+        .ok_or(EscrowError::MissingTokenAccount)?;
+
         transfer_checked(
             CpiContext::new_with_signer(
                 self.token_program.to_account_info(),
                 TransferChecked {
                     from: self.vault.to_account_info(),
-                    to: self.taker_ata_a.to_account_info(),
+                    to: destination.clone(),
                     mint: self.mint_a.to_account_info(),
                     authority: self.escrow.to_account_info(),
                 },
                 &signer_seeds,
             ),
-            self.vault.amount,
+            amount,
             self.mint_a.decimals,
         )?;
 
-        close_account(CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
-            CloseAccount {
-                account: self.vault.to_account_info(),
-                authority: self.escrow.to_account_info(),
-                destination: self.maker.to_account_info(),
-            },
-            &signer_seeds,
-        ))?;
+        // Unwrap native SOL by closing the temporary account, so only this fill is paid out
+        if is_native {
+            close_account(CpiContext::new(
+                self.token_program.to_account_info(),
+                CloseAccount {
+                    account: destination,
+                    authority: self.taker.to_account_info(),
+                    destination: self.taker.to_account_info(),
+                },
+            ))?;
+        }
+
+        if close_vault {
+            close_account(CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                CloseAccount {
+                    account: self.vault.to_account_info(),
+                    authority: self.escrow.to_account_info(),
+                    destination: self.maker.to_account_info(),
+                },
+                &signer_seeds,
+            ))?;
+        }
         Ok(())
     }
 }
 
-pub fn take_handler(ctx: Context<Take>) -> Result<()> {
-    ctx.accounts.transfer_to_maker()?;
-    ctx.accounts.withdraw_and_close_vault()?;
+pub fn take_handler(
+    ctx: Context<Take>,
+    amount: u64,
+    referral_bps: u16,
+    max_pay: u64,
+    min_receive: u64,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    require!(
+        !ctx.accounts.escrow.is_expired(clock.unix_timestamp),
+        EscrowError::EscrowExpired
+    );
+
+    // Allowlisted escrows require a merkle proof of the taker's key
+    if let Some(merkle_root) = ctx.accounts.escrow.merkle_root {
+        require!(
+            verify_merkle_proof(&proof, &merkle_root, &ctx.accounts.taker.key()),
+            EscrowError::InvalidProof
+        );
+    }
+
+    if let Some(collection) = ctx.accounts.escrow.collection {
+        ctx.accounts.check_collection_nft(&collection)?;
+    }
+
+    require_gt!(amount, 0, EscrowError::InvalidAmount);
+
+    // The taker can fill at most the remaining price; auction and oracle prices move while
+    // a take is in flight, so an amount quoted above it fills in full at the current price
+    let price = ctx.accounts.price(clock.unix_timestamp)?;
+    let escrow = &ctx.accounts.escrow;
+    let receive = if escrow.auction.is_some() || escrow.oracle.is_some() {
+        amount.min(price)
+    } else {
+        amount
+    };
+    require_gte!(price, receive, EscrowError::InvalidAmount);
+
+    // The final fill sweeps whatever is left in the vault so it can be closed
+    let is_final_fill = receive == price;
+    let deposit = if is_final_fill {
+        ctx.accounts.vault.amount
+    } else {
+        ctx.accounts.escrow.deposit_at_price(receive, price)
+    };
+    require_gt!(deposit, 0, EscrowError::InvalidAmount);
+
+    // Mint A may withhold a transfer fee on the way out of the vault
+    let received = amount_after_fee(&ctx.accounts.mint_a.to_account_info(), clock.epoch, deposit)?;
+    require_gte!(received, min_receive, EscrowError::SlippageExceeded);
+
+    ctx.accounts
+        .transfer_to_maker(receive, referral_bps, max_pay, clock.epoch)?;
+    ctx.accounts.withdraw_from_vault(deposit, is_final_fill)?;
+
+    if is_final_fill {
+        let maker = ctx.accounts.maker.to_account_info();
+        ctx.accounts.escrow.close(maker)?;
+    } else {
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.receive = remaining_after_fill(escrow.receive, receive, price);
+        if let Some(auction) = escrow.auction.as_mut() {
+            auction.start_receive = remaining_after_fill(auction.start_receive, receive, price);
+        }
+        escrow.deposit -= deposit;
+    }
     Ok(())
-}
\ No newline at end of file
+}
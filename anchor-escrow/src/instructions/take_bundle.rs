@@ -0,0 +1,199 @@
+use crate::errors::EscrowError;
+use crate::state::{BundleEscrow, Config, Leg};
+use crate::transfer_fee::amount_before_fee;
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::{
+        create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+#[derive(Accounts)]
+pub struct TakeBundle<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    #[account(
+        mut,
+        close=maker,
+        seeds=[b"bundle",maker.key().as_ref(),bundle.seed.to_le_bytes().as_ref()],
+        bump=bundle.bump,
+        has_one=maker @ EscrowError::InvalidMaker,
+    )]
+    pub bundle: Box<Account<'info, BundleEscrow>>,
+    #[account(
+        seeds=[b"config"],
+        bump=config.bump,
+        constraint=!config.paused @ EscrowError::ProgramPaused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(address=config.fee_recipient @ EscrowError::InvalidFeeRecipient)]
+    pub treasury: SystemAccount<'info>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    // remaining accounts: `[mint_a, vault, taker_ata_a]` for each mint A leg, then
+    // `[mint_b, taker_ata_b, maker_ata_b, treasury_ata_b]` for each mint B leg, in the order the
+    // bundle stores them
+}
+
+impl<'info> TakeBundle<'info> {
+    fn load_mint(
+        &self,
+        leg: &Leg,
+        mint: &'info AccountInfo<'info>,
+    ) -> Result<InterfaceAccount<'info, Mint>> {
+        require_keys_eq!(mint.key(), leg.mint, ErrorCode::ConstraintAddress);
+        require_keys_eq!(
+            *mint.owner,
+            self.token_program.key(),
+            ErrorCode::ConstraintMintTokenProgram
+        );
+        InterfaceAccount::<Mint>::try_from(mint)
+    }
+    fn check_ata(&self, ata: &AccountInfo, authority: &Pubkey, mint: &Pubkey) -> Result<()> {
+        require_keys_eq!(
+            ata.key(),
+            get_associated_token_address_with_program_id(
+                authority,
+                mint,
+                &self.token_program.key()
+            ),
+            ErrorCode::ConstraintAssociated
+        );
+        Ok(())
+    }
+    fn init_ata_if_needed(
+        &self,
+        ata: &AccountInfo<'info>,
+        authority: AccountInfo<'info>,
+        mint: &AccountInfo<'info>,
+    ) -> Result<()> {
+        create_idempotent(CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            Create {
+                payer: self.taker.to_account_info(),
+                associated_token: ata.clone(),
+                authority,
+                mint: mint.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+        ))
+    }
+    /// Pays one mint B leg to the maker less the protocol fee, which goes to the treasury,
+    /// grossed up so both receive their full share.
+    fn pay_leg(&self, leg: &Leg, accounts: &'info [AccountInfo<'info>], epoch: u64) -> Result<()> {
+        let [mint_b, taker_ata_b, maker_ata_b, treasury_ata_b] = accounts else {
+            return err!(ErrorCode::AccountNotEnoughKeys);
+        };
+        let mint = self.load_mint(leg, mint_b)?;
+        self.check_ata(taker_ata_b, &self.taker.key(), &leg.mint)?;
+        self.check_ata(maker_ata_b, &self.maker.key(), &leg.mint)?;
+        self.init_ata_if_needed(maker_ata_b, self.maker.to_account_info(), mint_b)?;
+
+        let fee = self.config.fee_for(leg.amount);
+        self.pay(mint_b, taker_ata_b, maker_ata_b, leg.amount - fee, mint.decimals, epoch)?;
+        if fee > 0 {
+            self.check_ata(treasury_ata_b, &self.treasury.key(), &leg.mint)?;
+            self.init_ata_if_needed(treasury_ata_b, self.treasury.to_account_info(), mint_b)?;
+            self.pay(mint_b, taker_ata_b, treasury_ata_b, fee, mint.decimals, epoch)?;
+        }
+        Ok(())
+    }
+    fn pay(
+        &self,
+        mint_b: &AccountInfo<'info>,
+        taker_ata_b: &AccountInfo<'info>,
+        to: &AccountInfo<'info>,
+        amount: u64,
+        decimals: u8,
+        epoch: u64,
+    ) -> Result<()> {
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: taker_ata_b.clone(),
+                    mint: mint_b.clone(),
+                    to: to.clone(),
+                    authority: self.taker.to_account_info(),
+                },
+            ),
+            amount_before_fee(mint_b, epoch, amount)?,
+            decimals,
+        )
+    }
+    /// Sweeps one mint A vault to the taker and closes it.
+    fn release_leg(&self, leg: &Leg, accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let [mint_a, vault, taker_ata_a] = accounts else {
+            return err!(ErrorCode::AccountNotEnoughKeys);
+        };
+        let mint = self.load_mint(leg, mint_a)?;
+        self.check_ata(vault, &self.bundle.key(), &leg.mint)?;
+        self.check_ata(taker_ata_a, &self.taker.key(), &leg.mint)?;
+        self.init_ata_if_needed(taker_ata_a, self.taker.to_account_info(), mint_a)?;
+        let amount = InterfaceAccount::<TokenAccount>::try_from(vault)?.amount;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"bundle",
+            self.maker.to_account_info().key.as_ref(),
+            &self.bundle.seed.to_le_bytes()[..],
+            &[self.bundle.bump],
+        ]];
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: vault.clone(),
+                    to: taker_ata_a.clone(),
+                    mint: mint_a.clone(),
+                    authority: self.bundle.to_account_info(),
+                },
+                &signer_seeds,
+            ),
+            amount,
+            mint.decimals,
+        )?;
+
+        close_account(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: vault.clone(),
+                authority: self.bundle.to_account_info(),
+                destination: self.maker.to_account_info(),
+            },
+            &signer_seeds,
+        ))?;
+        Ok(())
+    }
+}
+
+pub fn take_bundle_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, TakeBundle<'info>>,
+) -> Result<()> {
+    // Every leg must be settled, so a missing leg fails the whole take
+    let legs_a = ctx.accounts.bundle.legs_a.clone();
+    let legs_b = ctx.accounts.bundle.legs_b.clone();
+    require_eq!(
+        ctx.remaining_accounts.len(),
+        legs_a.len() * 3 + legs_b.len() * 4,
+        ErrorCode::AccountNotEnoughKeys
+    );
+    let (accounts_a, accounts_b) = ctx.remaining_accounts.split_at(legs_a.len() * 3);
+
+    let clock = Clock::get()?;
+    for (leg, accounts) in legs_b.iter().zip(accounts_b.chunks_exact(4)) {
+        ctx.accounts.pay_leg(leg, accounts, clock.epoch)?;
+    }
+    for (leg, accounts) in legs_a.iter().zip(accounts_a.chunks_exact(3)) {
+        ctx.accounts.release_leg(leg, accounts)?;
+    }
+    Ok(())
+}
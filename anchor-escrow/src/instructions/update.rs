@@ -0,0 +1,194 @@
+use crate::errors::EscrowError;
+use crate::state::Escrow;
+use crate::transfer_fee::amount_after_fee;
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::spl_token::native_mint,
+    token_interface::{
+        close_account, sync_native, transfer_checked, CloseAccount, Mint, SyncNative,
+        TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+#[derive(Accounts)]
+pub struct Update<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+        has_one = maker @ EscrowError::InvalidMaker,
+        has_one = mint_a @ EscrowError::InvalidMintA,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Only needed to move mint A in or out, and not for native SOL, which is deposited from
+    /// the maker's lamports and withdrawn through `unwrap_account`
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Temporary wSOL account for a native mint A withdrawal, closed to the maker within the
+    /// update so exactly the withdrawn amount is unwrapped
+    #[account(
+        init,
+        payer = maker,
+        seeds = [b"unwrap", maker.key().as_ref()],
+        bump,
+        token::mint = mint_a,
+        token::authority = maker,
+        token::token_program = token_program
+    )]
+    pub unwrap_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Update<'info> {
+    fn deposit_tokens(&mut self, amount: u64) -> Result<()> {
+        if native_mint::check_id(&self.mint_a.key()) {
+            transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.maker.to_account_info(),
+                        to: self.vault.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+            return sync_native(CpiContext::new(
+                self.token_program.to_account_info(),
+                SyncNative {
+                    account: self.vault.to_account_info(),
+                },
+            ));
+        }
+        let maker_ata_a = self
+            .maker_ata_a
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?;
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: maker_ata_a.to_account_info(),
+                    mint: self.mint_a.to_account_info(),
+                    to: self.vault.to_account_info(),
+                    authority: self.maker.to_account_info(),
+                },
+            ),
+            amount,
+            self.mint_a.decimals,
+        )?;
+        Ok(())
+    }
+    fn withdraw_tokens(&mut self, amount: u64) -> Result<()> {
+        // Native SOL lands in the temporary account, leaving any wSOL the maker holds alone
+        let is_native = native_mint::check_id(&self.mint_a.key());
+        let destination = if is_native {
+            self.unwrap_account.as_ref().map(|account| account.to_account_info())
+        } else {
+            self.maker_ata_a.as_ref().map(|ata| ata.to_account_info())
+        }
+        .ok_or(EscrowError::MissingTokenAccount)?;
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.vault.to_account_info(),
+                    to: destination.clone(),
+                    mint: self.mint_a.to_account_info(),
+                    authority: self.escrow.to_account_info(),
+                },
+                &signer_seeds,
+            ),
+            amount,
+            self.mint_a.decimals,
+        )?;
+
+        // Unwrap native SOL by closing the temporary account, so only the withdrawal is paid out
+        if is_native {
+            close_account(CpiContext::new(
+                self.token_program.to_account_info(),
+                CloseAccount {
+                    account: destination,
+                    authority: self.maker.to_account_info(),
+                    destination: self.maker.to_account_info(),
+                },
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn update_handler(
+    ctx: Context<Update>,
+    receive: u64,
+    expiry: i64,
+    deposit: u64,
+    withdraw: u64,
+) -> Result<()> {
+    require_gt!(receive, 0, EscrowError::InvalidAmount);
+    // Depositing and withdrawing in the same update is ambiguous
+    require!(deposit == 0 || withdraw == 0, EscrowError::InvalidAmount);
+
+    // Mint A may withhold a transfer fee, so only credit what lands in the vault
+    let clock = Clock::get()?;
+    let credited = amount_after_fee(&ctx.accounts.mint_a.to_account_info(), clock.epoch, deposit)?;
+
+    // The escrow must keep something to sell; a full withdrawal is a refund
+    let remaining = ctx
+        .accounts
+        .escrow
+        .deposit
+        .checked_add(credited)
+        .and_then(|remaining| remaining.checked_sub(withdraw))
+        .ok_or(EscrowError::InvalidAmount)?;
+    require_gt!(remaining, 0, EscrowError::InvalidAmount);
+
+    if deposit > 0 {
+        ctx.accounts.deposit_tokens(deposit)?;
+    }
+    if withdraw > 0 {
+        ctx.accounts.withdraw_tokens(withdraw)?;
+    }
+
+    let escrow = &mut ctx.accounts.escrow;
+    // Repricing replaces any auction schedule with the new fixed price
+    escrow.receive = receive;
+    escrow.auction = None;
+    escrow.expiry = expiry;
+    escrow.deposit = remaining;
+    Ok(())
+}
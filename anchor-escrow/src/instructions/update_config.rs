@@ -0,0 +1,36 @@
+use crate::{errors::EscrowError, state::Config};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ EscrowError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+pub fn update_config_handler(
+    ctx: Context<UpdateConfig>,
+    admin: Pubkey,
+    fee_recipient: Pubkey,
+    fee_bps: u16,
+    max_referral_bps: u16,
+) -> Result<()> {
+    require!(
+        Config::is_valid_fee(fee_bps, max_referral_bps),
+        EscrowError::InvalidFee
+    );
+
+    // Only the current admin may change the config, including handing it over
+    let config = &mut ctx.accounts.config;
+    config.admin = admin;
+    config.fee_recipient = fee_recipient;
+    config.fee_bps = fee_bps;
+    config.max_referral_bps = max_referral_bps;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::{
+    errors::EscrowError,
+    state::{AuctionEscrow, Bid},
+};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+#[derive(Accounts)]
+pub struct WithdrawBid<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    /// CHECK: already closed once the auction has been settled; while it is still
+    /// open `withdraw_bid_handler` loads it to refuse withdrawing the leading bid.
+    pub auction: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = bidder,
+        seeds = [b"bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump = bid.bump,
+        has_one = bidder @ EscrowError::Unauthorized,
+        has_one = auction,
+    )]
+    pub bid: Box<Account<'info, Bid>>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = bid,
+        associated_token::token_program = token_program
+    )]
+    pub bid_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        associated_token::mint = mint_b,
+        associated_token::authority = bidder,
+        associated_token::token_program = token_program
+    )]
+    pub bidder_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawBid<'info> {
+    fn withdraw_and_close_vault(&mut self) -> Result<()> {
+        let auction_key = self.auction.key();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"bid",
+            auction_key.as_ref(),
+            self.bidder.to_account_info().key.as_ref(),
+            &[self.bid.bump],
+        ]];
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.bid_vault.to_account_info(),
+                    to: self.bidder_ata_b.to_account_info(),
+                    mint: self.mint_b.to_account_info(),
+                    authority: self.bid.to_account_info(),
+                },
+                &signer_seeds,
+            ),
+            self.bid_vault.amount,
+            self.mint_b.decimals,
+        )?;
+
+        close_account(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.bid_vault.to_account_info(),
+                authority: self.bid.to_account_info(),
+                destination: self.bidder.to_account_info(),
+            },
+            &signer_seeds,
+        ))?;
+
+        Ok(())
+    }
+}
+
+pub fn withdraw_bid_handler(ctx: Context<WithdrawBid>) -> Result<()> {
+    // The leading bid stays locked until settlement consumes it; once the auction
+    // is settled and closed every remaining bid was outbid
+    let auction = &ctx.accounts.auction;
+    if auction.owner == &crate::ID {
+        let auction = AuctionEscrow::try_deserialize(&mut &auction.try_borrow_data()?[..])?;
+        require!(
+            auction.highest_bidder != Some(ctx.accounts.bidder.key()),
+            EscrowError::LeadingBid
+        );
+    }
+
+    ctx.accounts.withdraw_and_close_vault()?;
+
+    Ok(())
+}
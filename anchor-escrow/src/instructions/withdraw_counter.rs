@@ -0,0 +1,96 @@
+use crate::{errors::EscrowError, state::Offer};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+#[derive(Accounts)]
+pub struct WithdrawCounter<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// Seeded by the stored escrow, so it can be withdrawn even once the escrow is gone
+    #[account(
+        mut,
+        close = taker,
+        seeds = [b"offer", offer.escrow.as_ref(), taker.key().as_ref()],
+        bump = offer.bump,
+        has_one = taker @ EscrowError::InvalidTaker,
+    )]
+    pub offer: Box<Account<'info, Offer>>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = offer,
+        associated_token::token_program = token_program
+    )]
+    pub offer_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawCounter<'info> {
+    fn withdraw_and_close_vault(&mut self) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"offer",
+            self.offer.escrow.as_ref(),
+            self.taker.to_account_info().key.as_ref(),
+            &[self.offer.bump],
+        ]];
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.offer_vault.to_account_info(),
+                    to: self.taker_ata_b.to_account_info(),
+                    mint: self.mint_b.to_account_info(),
+                    authority: self.offer.to_account_info(),
+                },
+                &signer_seeds,
+            ),
+            self.offer_vault.amount,
+            self.mint_b.decimals,
+        )?;
+
+        close_account(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.offer_vault.to_account_info(),
+                authority: self.offer.to_account_info(),
+                destination: self.taker.to_account_info(),
+            },
+            &signer_seeds,
+        ))?;
+
+        Ok(())
+    }
+}
+
+pub fn withdraw_counter_handler(ctx: Context<WithdrawCounter>) -> Result<()> {
+    // Only the taker who made the offer can pull it, at any time
+    ctx.accounts.withdraw_and_close_vault()?;
+
+    Ok(())
+}
@@ -2,9 +2,14 @@
 
 use anchor_lang::prelude::*;
 pub mod errors;
+pub mod hash;
 pub mod instructions;
 use instructions::*;
+pub mod metadata;
+pub mod oracle;
 pub mod state;
+use state::{DutchAuction, Leg, OraclePricing};
+pub mod transfer_fee;
 
 declare_id!("22222222222222222222222222222222222222222222");
 
@@ -12,15 +17,234 @@ declare_id!("22222222222222222222222222222222222222222222");
 pub mod anchor_escrow {
     use super::*;
     #[instruction(discriminator = 0)]
-    pub fn make(ctx: Context<Make>, seed: u64, receive: u64, amount: u64) -> Result<()> {
-        instructions::make::make_handler(ctx, seed, receive, amount)
+    #[allow(clippy::too_many_arguments)]
+    pub fn make(
+        ctx: Context<Make>,
+        seed: u64,
+        receive: u64,
+        amount: u64,
+        expiry: i64,
+        taker: Option<Pubkey>,
+        merkle_root: Option<[u8; 32]>,
+        auction: Option<DutchAuction>,
+        oracle: Option<OraclePricing>,
+        collection: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::make::make_handler(
+            ctx,
+            seed,
+            receive,
+            amount,
+            expiry,
+            taker,
+            merkle_root,
+            auction,
+            oracle,
+            collection,
+        )
     }
     #[instruction(discriminator = 1)]
-    pub fn take(ctx: Context<Take>) -> Result<()> {
-        instructions::take::take_handler(ctx)
+    pub fn take(
+        ctx: Context<Take>,
+        amount: u64,
+        referral_bps: u16,
+        max_pay: u64,
+        min_receive: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        instructions::take::take_handler(ctx, amount, referral_bps, max_pay, min_receive, proof)
     }
     #[instruction(discriminator = 2)]
     pub fn refund(ctx: Context<Refund>) -> Result<()> {
         instructions::refund::refund_handler(ctx)
     }
-}
\ No newline at end of file
+    #[instruction(discriminator = 4)]
+    pub fn update(
+        ctx: Context<Update>,
+        receive: u64,
+        expiry: i64,
+        deposit: u64,
+        withdraw: u64,
+    ) -> Result<()> {
+        instructions::update::update_handler(ctx, receive, expiry, deposit, withdraw)
+    }
+    #[instruction(discriminator = 5)]
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        fee_recipient: Pubkey,
+        fee_bps: u16,
+        max_referral_bps: u16,
+    ) -> Result<()> {
+        instructions::initialize_config::initialize_config_handler(
+            ctx,
+            fee_recipient,
+            fee_bps,
+            max_referral_bps,
+        )
+    }
+    #[instruction(discriminator = 6)]
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        admin: Pubkey,
+        fee_recipient: Pubkey,
+        fee_bps: u16,
+        max_referral_bps: u16,
+    ) -> Result<()> {
+        instructions::update_config::update_config_handler(
+            ctx,
+            admin,
+            fee_recipient,
+            fee_bps,
+            max_referral_bps,
+        )
+    }
+    #[instruction(discriminator = 7)]
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        instructions::set_paused::set_paused_handler(ctx, paused)
+    }
+    #[instruction(discriminator = 8)]
+    pub fn make_arbiter_escrow(
+        ctx: Context<MakeArbiterEscrow>,
+        seed: u64,
+        expiry: i64,
+        beneficiary: Pubkey,
+        arbiter: Pubkey,
+        milestones: Vec<u64>,
+    ) -> Result<()> {
+        instructions::make_arbiter_escrow::make_arbiter_escrow_handler(
+            ctx,
+            seed,
+            expiry,
+            beneficiary,
+            arbiter,
+            milestones,
+        )
+    }
+    #[instruction(discriminator = 9)]
+    pub fn release(ctx: Context<Release>) -> Result<()> {
+        instructions::release::release_handler(ctx)
+    }
+    #[instruction(discriminator = 10)]
+    pub fn arbiter_refund(ctx: Context<ArbiterRefund>) -> Result<()> {
+        instructions::arbiter_refund::arbiter_refund_handler(ctx)
+    }
+    #[instruction(discriminator = 11)]
+    pub fn dispute(ctx: Context<Dispute>) -> Result<()> {
+        instructions::dispute::dispute_handler(ctx)
+    }
+    #[instruction(discriminator = 12)]
+    pub fn release_milestone(ctx: Context<ReleaseMilestone>, index: u8) -> Result<()> {
+        instructions::release_milestone::release_milestone_handler(ctx, index)
+    }
+    #[instruction(discriminator = 13)]
+    pub fn make_vesting_escrow(
+        ctx: Context<MakeVestingEscrow>,
+        seed: u64,
+        amount: u64,
+        start: i64,
+        cliff: i64,
+        end: i64,
+        beneficiary: Pubkey,
+    ) -> Result<()> {
+        instructions::make_vesting_escrow::make_vesting_escrow_handler(
+            ctx,
+            seed,
+            amount,
+            start,
+            cliff,
+            end,
+            beneficiary,
+        )
+    }
+    #[instruction(discriminator = 14)]
+    pub fn claim(ctx: Context<Claim>) -> Result<()> {
+        instructions::claim::claim_handler(ctx)
+    }
+    #[instruction(discriminator = 15)]
+    pub fn make_htlc(
+        ctx: Context<MakeHtlc>,
+        seed: u64,
+        amount: u64,
+        expiry: i64,
+        recipient: Pubkey,
+        sha256_hash: [u8; 32],
+        keccak_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::make_htlc::make_htlc_handler(
+            ctx,
+            seed,
+            amount,
+            expiry,
+            recipient,
+            sha256_hash,
+            keccak_hash,
+        )
+    }
+    #[instruction(discriminator = 16)]
+    pub fn claim_htlc(ctx: Context<ClaimHtlc>, preimage: Vec<u8>) -> Result<()> {
+        instructions::claim_htlc::claim_htlc_handler(ctx, preimage)
+    }
+    #[instruction(discriminator = 17)]
+    pub fn refund_htlc(ctx: Context<RefundHtlc>) -> Result<()> {
+        instructions::refund_htlc::refund_htlc_handler(ctx)
+    }
+    #[instruction(discriminator = 18)]
+    pub fn make_auction(
+        ctx: Context<MakeAuction>,
+        seed: u64,
+        amount: u64,
+        reserve: u64,
+        min_increment: u64,
+        end: i64,
+    ) -> Result<()> {
+        instructions::make_auction::make_auction_handler(
+            ctx,
+            seed,
+            amount,
+            reserve,
+            min_increment,
+            end,
+        )
+    }
+    #[instruction(discriminator = 19)]
+    pub fn place_bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+        instructions::place_bid::place_bid_handler(ctx, amount)
+    }
+    #[instruction(discriminator = 20)]
+    pub fn withdraw_bid(ctx: Context<WithdrawBid>) -> Result<()> {
+        instructions::withdraw_bid::withdraw_bid_handler(ctx)
+    }
+    #[instruction(discriminator = 21)]
+    pub fn settle(ctx: Context<Settle>) -> Result<()> {
+        instructions::settle::settle_handler(ctx)
+    }
+    #[instruction(discriminator = 22)]
+    pub fn make_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MakeBundle<'info>>,
+        seed: u64,
+        amounts_a: Vec<u64>,
+        legs_b: Vec<Leg>,
+    ) -> Result<()> {
+        instructions::make_bundle::make_bundle_handler(ctx, seed, amounts_a, legs_b)
+    }
+    #[instruction(discriminator = 23)]
+    pub fn take_bundle<'info>(ctx: Context<'_, '_, 'info, 'info, TakeBundle<'info>>) -> Result<()> {
+        instructions::take_bundle::take_bundle_handler(ctx)
+    }
+    #[instruction(discriminator = 24)]
+    pub fn refund_bundle<'info>(ctx: Context<'_, '_, 'info, 'info, RefundBundle<'info>>) -> Result<()> {
+        instructions::refund_bundle::refund_bundle_handler(ctx)
+    }
+    #[instruction(discriminator = 25)]
+    pub fn counter_offer(ctx: Context<CounterOffer>, receive: u64) -> Result<()> {
+        instructions::counter_offer::counter_offer_handler(ctx, receive)
+    }
+    #[instruction(discriminator = 26)]
+    pub fn accept_counter(ctx: Context<AcceptCounter>) -> Result<()> {
+        instructions::accept_counter::accept_counter_handler(ctx)
+    }
+    #[instruction(discriminator = 27)]
+    pub fn withdraw_counter(ctx: Context<WithdrawCounter>) -> Result<()> {
+        instructions::withdraw_counter::withdraw_counter_handler(ctx)
+    }
+}
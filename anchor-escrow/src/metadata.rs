@@ -0,0 +1,119 @@
+use crate::errors::EscrowError;
+use anchor_lang::prelude::*;
+
+/// Metaplex Token Metadata program, owner of every NFT metadata account.
+pub const TOKEN_METADATA_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// `Key::MetadataV1`, the first byte of every metadata account.
+pub const KEY_METADATA_V1: u8 = 4;
+
+/// Size of a serialized `Creator`: address, verified flag and share.
+const CREATOR_LEN: usize = 32 + 1 + 1;
+
+/// The collection an NFT claims, trusted only once its authority has verified it.
+pub struct Collection {
+  pub verified: bool,
+  pub key: Pubkey,
+}
+
+/// The fields of a Metaplex `Metadata` account needed to check collection membership.
+pub struct Metadata {
+  pub mint: Pubkey,
+  pub collection: Option<Collection>,
+}
+
+impl Metadata {
+  /// Reads the canonical metadata account of `mint`.
+  pub fn load(account: &AccountInfo, mint: &Pubkey) -> Result<Self> {
+    require_keys_eq!(*account.owner, TOKEN_METADATA_ID, EscrowError::InvalidNft);
+
+    let (metadata_key, _) = Pubkey::find_program_address(
+      &[b"metadata", TOKEN_METADATA_ID.as_ref(), mint.as_ref()],
+      &TOKEN_METADATA_ID,
+    );
+    require_keys_eq!(*account.key, metadata_key, EscrowError::InvalidNft);
+
+    let metadata = Self::parse(&account.try_borrow_data()?).ok_or(EscrowError::InvalidNft)?;
+    require_keys_eq!(metadata.mint, *mint, EscrowError::InvalidNft);
+    Ok(metadata)
+  }
+
+  /// Walks the Borsh layout up to the collection field, skipping the variable-length
+  /// name, symbol, uri and creators along the way.
+  pub fn parse(data: &[u8]) -> Option<Self> {
+    let mut reader = Reader { data, offset: 0 };
+
+    if reader.u8()? != KEY_METADATA_V1 {
+      return None;
+    }
+    reader.take(32)?; // update authority
+    let mint = Pubkey::try_from(reader.take(32)?).ok()?;
+    reader.string()?; // name
+    reader.string()?; // symbol
+    reader.string()?; // uri
+    reader.take(2)?; // seller fee basis points
+    if reader.option()? {
+      let creators = reader.u32()? as usize;
+      reader.take(creators.checked_mul(CREATOR_LEN)?)?;
+    }
+    reader.take(2)?; // primary sale happened, is mutable
+    if reader.option()? {
+      reader.take(1)?; // edition nonce
+    }
+    if reader.option()? {
+      reader.take(1)?; // token standard
+    }
+    let collection = if reader.option()? {
+      Some(Collection {
+        verified: reader.u8()? != 0,
+        key: Pubkey::try_from(reader.take(32)?).ok()?,
+      })
+    } else {
+      None
+    };
+
+    Some(Self { mint, collection })
+  }
+
+  /// Unverified collections can be claimed by anyone, so they never count.
+  pub fn is_verified_member_of(&self, collection: &Pubkey) -> bool {
+    self
+      .collection
+      .as_ref()
+      .is_some_and(|claimed| claimed.verified && claimed.key == *collection)
+  }
+}
+
+struct Reader<'a> {
+  data: &'a [u8],
+  offset: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+    let bytes = self.data.get(self.offset..self.offset.checked_add(len)?)?;
+    self.offset += len;
+    Some(bytes)
+  }
+
+  fn u8(&mut self) -> Option<u8> {
+    Some(self.take(1)?[0])
+  }
+
+  fn u32(&mut self) -> Option<u32> {
+    Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+  }
+
+  fn string(&mut self) -> Option<&'a [u8]> {
+    let len = self.u32()? as usize;
+    self.take(len)
+  }
+
+  fn option(&mut self) -> Option<bool> {
+    match self.u8()? {
+      0 => Some(false),
+      1 => Some(true),
+      _ => None,
+    }
+  }
+}
@@ -8,5 +8,316 @@ use anchor_lang::prelude::*;
   pub mint_a: Pubkey,
   pub mint_b: Pubkey,
   pub receive: u64,
+  pub deposit: u64,
+  pub expiry: i64,
+  pub taker: Option<Pubkey>,
+  pub merkle_root: Option<[u8; 32]>,
+  /// Declining price schedule ending at `receive`; fixed price when absent.
+  pub auction: Option<DutchAuction>,
+  /// Market pricing with `receive` as the floor; fixed price when absent.
+  pub oracle: Option<OraclePricing>,
+  /// Collection whose verified NFTs are accepted as mint B; `mint_b` is unset when present.
+  pub collection: Option<Pubkey>,
   pub bump: u8,
-}
\ No newline at end of file
+}
+
+/// Dutch auction opening at `start_receive` for the remaining deposit and falling
+/// linearly to the escrow's `receive` between `start` and `end`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct DutchAuction {
+  pub start_receive: u64,
+  pub start: i64,
+  pub end: i64,
+}
+
+/// Prices the remaining deposit from a Pyth price feed quoting mint A in mint B.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct OraclePricing {
+  pub price_feed: Pubkey,
+  /// Pyth feed the price feed account must carry, since its write authority can post any feed.
+  pub feed_id: [u8; 32],
+  /// Oldest price accepted at take time, in seconds.
+  pub max_staleness: u64,
+  /// Widest confidence interval accepted, in basis points of the price.
+  pub max_conf_bps: u16,
+  /// Offset from the oracle price in basis points; negative sells below market.
+  pub spread_bps: i16,
+}
+
+impl Escrow {
+  /// An expiry of zero means the escrow never expires.
+  pub fn is_expired(&self, current_timestamp: i64) -> bool {
+    self.expiry > 0 && current_timestamp > self.expiry
+  }
+
+  /// Without a designated taker the escrow is public; otherwise only that signer may take it.
+  pub fn is_taker_allowed(&self, taker: &Pubkey) -> bool {
+    self.taker.is_none_or(|designated| designated == *taker)
+  }
+
+  /// Price in mint B for the whole remaining deposit at `current_timestamp`,
+  /// rounded up in favor of the maker while an auction is running.
+  pub fn receive_at(&self, current_timestamp: i64) -> u64 {
+    let Some(auction) = self.auction else {
+      return self.receive;
+    };
+    if current_timestamp >= auction.end {
+      return self.receive;
+    }
+    if current_timestamp <= auction.start {
+      return auction.start_receive;
+    }
+    let decline = auction.start_receive.saturating_sub(self.receive) as u128;
+    let elapsed = (current_timestamp - auction.start) as u128;
+    let duration = (auction.end - auction.start) as u128;
+    auction.start_receive - (decline * elapsed / duration) as u64
+  }
+
+  /// Amount of mint A released for a fill paying `receive` of mint B,
+  /// proportional to the remaining deposit and rounded down in favor of the maker.
+  pub fn deposit_for(&self, receive: u64) -> u64 {
+    self.deposit_at_price(receive, self.receive)
+  }
+
+  /// Like [`Escrow::deposit_for`], against a `price` for the whole remaining deposit.
+  pub fn deposit_at_price(&self, receive: u64, price: u64) -> u64 {
+    if price == 0 {
+      return 0;
+    }
+    (self.deposit as u128 * receive as u128 / price as u128) as u64
+  }
+}
+
+/// What remains of `value` after a fill paying `receive` out of `price`, so a
+/// partially filled auction keeps its per-unit schedule.
+pub fn remaining_after_fill(value: u64, receive: u64, price: u64) -> u64 {
+  if price == 0 {
+    return value;
+  }
+  value - (value as u128 * receive as u128 / price as u128) as u64
+}
+
+#[derive(InitSpace)]
+#[account(discriminator = 2)]
+pub struct Config {
+  pub admin: Pubkey,
+  pub fee_recipient: Pubkey,
+  pub fee_bps: u16,
+  pub max_referral_bps: u16,
+  /// Make and Take are halted while paused; refunds always stay open.
+  pub paused: bool,
+  pub bump: u8,
+}
+
+impl Config {
+  /// Basis points denominator for fees.
+  pub const MAX_BASIS_POINTS: u16 = 10_000;
+
+  /// Protocol fee owed on `amount`, rounded down in favor of the trader.
+  pub fn fee_for(&self, amount: u64) -> u64 {
+    Self::bps_of(amount, self.fee_bps)
+  }
+
+  /// Referrer's share of the protocol `fee` at `referral_bps`, or `None` above the configured cap.
+  /// It comes out of the fee rather than the maker's proceeds, so self-referral costs the maker nothing.
+  pub fn referral_fee_for(&self, fee: u64, referral_bps: u16) -> Option<u64> {
+    (referral_bps <= self.max_referral_bps).then(|| Self::bps_of(fee, referral_bps))
+  }
+
+  /// The referral is a share of the protocol fee, which is a share of the mint B leg.
+  pub fn is_valid_fee(fee_bps: u16, max_referral_bps: u16) -> bool {
+    fee_bps <= Self::MAX_BASIS_POINTS && max_referral_bps <= Self::MAX_BASIS_POINTS
+  }
+
+  fn bps_of(amount: u64, bps: u16) -> u64 {
+    (amount as u128 * bps as u128 / Self::MAX_BASIS_POINTS as u128) as u64
+  }
+}
+
+#[derive(InitSpace)]
+#[account(discriminator = 3)]
+pub struct ArbiterEscrow {
+  pub seed: u64,
+  pub maker: Pubkey,
+  pub beneficiary: Pubkey,
+  pub arbiter: Pubkey,
+  pub mint_a: Pubkey,
+  pub expiry: i64,
+  pub milestones: [u64; ArbiterEscrow::MAX_MILESTONES],
+  /// Bit `i` is set once milestone `i` has been paid out.
+  pub released: u8,
+  /// Set by either party to hand settlement to the arbiter alone.
+  pub disputed: bool,
+  pub bump: u8,
+}
+
+impl ArbiterEscrow {
+  /// Number of tranches an escrow can be split into; unused slots hold zero.
+  pub const MAX_MILESTONES: usize = 4;
+
+  /// Milestones fill the array from the front, so the first zero slot ends the schedule.
+  pub fn milestone_count(&self) -> usize {
+    self.milestones.iter().take_while(|&&amount| amount > 0).count()
+  }
+
+  pub fn is_milestone_released(&self, index: usize) -> bool {
+    self.released & (1 << index) != 0
+  }
+
+  /// Once every milestone is paid the vault and escrow can be closed.
+  pub fn is_fully_released(&self) -> bool {
+    (0..self.milestone_count()).all(|index| self.is_milestone_released(index))
+  }
+
+  /// A disputed escrow never expires, so the maker can't refund around the arbiter.
+  pub fn is_expired(&self, current_timestamp: i64) -> bool {
+    !self.disputed && self.expiry > 0 && current_timestamp > self.expiry
+  }
+}
+
+#[derive(InitSpace)]
+#[account(discriminator = 4)]
+pub struct VestingEscrow {
+  pub seed: u64,
+  pub maker: Pubkey,
+  pub beneficiary: Pubkey,
+  pub mint_a: Pubkey,
+  pub start: i64,
+  pub cliff: i64,
+  pub end: i64,
+  /// Amount that landed in the vault, net of any transfer fee.
+  pub total: u64,
+  pub withdrawn: u64,
+  pub bump: u8,
+}
+
+impl VestingEscrow {
+  /// Amount vested by `current_timestamp`, rounded down in favor of the maker.
+  pub fn unlocked_at(&self, current_timestamp: i64) -> u64 {
+    if current_timestamp < self.cliff {
+      return 0;
+    }
+    if current_timestamp >= self.end {
+      return self.total;
+    }
+    let elapsed = (current_timestamp - self.start) as u128;
+    let duration = (self.end - self.start) as u128;
+    (self.total as u128 * elapsed / duration) as u64
+  }
+
+  /// Vested amount not yet withdrawn by the beneficiary.
+  pub fn claimable_at(&self, current_timestamp: i64) -> u64 {
+    self.unlocked_at(current_timestamp).saturating_sub(self.withdrawn)
+  }
+}
+
+#[derive(InitSpace)]
+#[account(discriminator = 5)]
+pub struct HtlcEscrow {
+  pub seed: u64,
+  pub maker: Pubkey,
+  pub recipient: Pubkey,
+  pub mint_a: Pubkey,
+  pub sha256_hash: [u8; 32],
+  /// All zeroes when only the SHA-256 lock is set.
+  pub keccak_hash: [u8; 32],
+  pub expiry: i64,
+  pub bump: u8,
+}
+
+impl HtlcEscrow {
+  /// The timelock is mandatory, so unlike a swap escrow a zero expiry isn't "never".
+  pub fn is_expired(&self, current_timestamp: i64) -> bool {
+    current_timestamp > self.expiry
+  }
+}
+
+#[derive(InitSpace)]
+#[account(discriminator = 6)]
+pub struct AuctionEscrow {
+  pub seed: u64,
+  pub maker: Pubkey,
+  pub mint_a: Pubkey,
+  pub mint_b: Pubkey,
+  /// Lowest mint B bid the maker will sell the lot for.
+  pub reserve: u64,
+  /// Amount each new leading bid must add on top of the current one.
+  pub min_increment: u64,
+  pub end: i64,
+  pub highest_bid: u64,
+  pub highest_bidder: Option<Pubkey>,
+  pub bump: u8,
+}
+
+impl AuctionEscrow {
+  /// Bidding closes at `end`, after which anyone may settle.
+  pub fn is_ended(&self, current_timestamp: i64) -> bool {
+    current_timestamp >= self.end
+  }
+
+  /// Smallest total bid that takes the lead: the reserve, then one increment over the leader.
+  pub fn min_bid(&self) -> u64 {
+    match self.highest_bidder {
+      Some(_) => self.highest_bid.saturating_add(self.min_increment),
+      None => self.reserve,
+    }
+  }
+}
+
+#[derive(InitSpace)]
+#[account(discriminator = 7)]
+pub struct Bid {
+  pub auction: Pubkey,
+  pub bidder: Pubkey,
+  /// Mint B credited to the bid vault, net of any transfer fee.
+  pub amount: u64,
+  pub bump: u8,
+}
+
+/// Most mint/amount pairs a bundle holds on either side.
+pub const MAX_BUNDLE_LEGS: usize = 4;
+
+/// Several mints traded at once: each mint A leg is locked in its own vault owned by
+/// this account, and every mint B leg is owed to the maker in a single atomic take.
+#[derive(InitSpace)]
+#[account(discriminator = 8)]
+pub struct BundleEscrow {
+  pub seed: u64,
+  pub maker: Pubkey,
+  /// Deposited amounts, net of any transfer fee.
+  #[max_len(4)]
+  pub legs_a: Vec<Leg>,
+  /// Amounts the maker receives.
+  #[max_len(4)]
+  pub legs_b: Vec<Leg>,
+  pub bump: u8,
+}
+
+/// One mint/amount pair of a bundle.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct Leg {
+  pub mint: Pubkey,
+  pub amount: u64,
+}
+
+/// A mint repeated on one side would share a vault or an ATA with itself.
+pub fn has_duplicate_mints(legs: &[Leg]) -> bool {
+  legs
+    .iter()
+    .enumerate()
+    .any(|(i, leg)| legs[..i].iter().any(|other| other.mint == leg.mint))
+}
+
+/// A taker's counter-offer on an escrow: their proposed price in mint B, locked in the
+/// vault owned by this account, one per escrow and taker.
+#[derive(InitSpace)]
+#[account(discriminator = 9)]
+pub struct Offer {
+  pub escrow: Pubkey,
+  pub taker: Pubkey,
+  /// Mint B offered for the whole remaining deposit, net of any transfer fee.
+  pub receive: u64,
+  /// Mint A the escrow held when the offer was made; accepting fails if it has shrunk since.
+  pub deposit: u64,
+  pub bump: u8,
+}
@@ -0,0 +1,27 @@
+use crate::errors::EscrowError;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    get_mint_extension_data, spl_token_2022::extension::transfer_fee::TransferFeeConfig,
+};
+
+/// Amount that lands in the destination when `amount` of `mint` is transferred.
+pub fn amount_after_fee(mint: &AccountInfo, epoch: u64, amount: u64) -> Result<u64> {
+    match get_mint_extension_data::<TransferFeeConfig>(mint) {
+        Ok(config) => config
+            .get_epoch_fee(epoch)
+            .calculate_post_fee_amount(amount)
+            .ok_or(error!(EscrowError::InvalidAmount)),
+        Err(_) => Ok(amount),
+    }
+}
+
+/// Amount to transfer so that exactly `amount` of `mint` lands in the destination.
+pub fn amount_before_fee(mint: &AccountInfo, epoch: u64, amount: u64) -> Result<u64> {
+    match get_mint_extension_data::<TransferFeeConfig>(mint) {
+        Ok(config) => config
+            .get_epoch_fee(epoch)
+            .calculate_pre_fee_amount(amount)
+            .ok_or(error!(EscrowError::InvalidAmount)),
+        Err(_) => Ok(amount),
+    }
+}
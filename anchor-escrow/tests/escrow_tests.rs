@@ -1,6 +1,37 @@
-use anchor_escrow;
+use anchor_escrow::{
+    hash::{verify_hashlock, verify_merkle_proof},
+    metadata::{Metadata, KEY_METADATA_V1, TOKEN_METADATA_ID},
+    oracle::{PriceFeed, PRICE_UPDATE_V2_DISCRIMINATOR, PYTH_RECEIVER_ID, VERIFICATION_LEVEL_FULL},
+    state::{
+        has_duplicate_mints, remaining_after_fill, ArbiterEscrow, AuctionEscrow, BundleEscrow, Config,
+        DutchAuction, Escrow, HtlcEscrow, Leg, Offer, VestingEscrow, MAX_BUNDLE_LEGS,
+    },
+    transfer_fee::{amount_after_fee, amount_before_fee},
+};
+use anchor_lang::{
+    prelude::AccountInfo, solana_program::program_pack::Pack, AccountDeserialize, AccountSerialize,
+    InstructionData, Space, ToAccountMetas,
+};
+use anchor_spl::token_interface::spl_token_2022::{
+    self,
+    extension::{
+        transfer_fee::{TransferFee, TransferFeeConfig},
+        BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+    },
+    state::Mint,
+};
+use solana_program::hash::{hash, hashv};
 use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::{Keypair, Signer};
+use litesvm::LiteSVM;
+use litesvm_token::{CreateAssociatedTokenAccount, CreateMint, MintTo};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use std::path::PathBuf;
 
 /// Test that the program ID is correctly set
 #[test]
@@ -160,4 +191,877 @@ fn test_spl_program_ids() {
     );
     
     println!("✅ SPL program IDs test passed");
-}
\ No newline at end of file
+}
+
+/// Test the native SOL mint and the wSOL vault it is wrapped into
+#[test]
+fn test_native_sol_vault() {
+    let native_mint = spl_token::native_mint::ID;
+    assert_eq!(
+        native_mint.to_string(),
+        "So11111111111111111111111111111111111111112"
+    );
+    
+    // A native mint A is wrapped into an ordinary vault ATA owned by the escrow
+    let program_id = anchor_escrow::ID;
+    let maker = Keypair::new().pubkey();
+    let seed = 42u64;
+    let (escrow_pda, _) = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+        &program_id,
+    );
+    let vault = spl_associated_token_account::get_associated_token_address(&escrow_pda, &native_mint);
+    assert_ne!(vault, escrow_pda);
+    
+    println!("✅ Native SOL vault test passed");
+}
+
+/// Builds an open escrow selling 500_000_000 of mint A for 800_000_000 of mint B
+fn new_escrow() -> Escrow {
+    Escrow {
+        seed: 42,
+        maker: Pubkey::new_unique(),
+        mint_a: Pubkey::new_unique(),
+        mint_b: Pubkey::new_unique(),
+        receive: 800_000_000,
+        deposit: 500_000_000,
+        expiry: 0,
+        taker: None,
+        merkle_root: None,
+        auction: None,
+        oracle: None,
+        collection: None,
+        bump: 255,
+    }
+}
+
+/// Test the proportional deposit released by partial fills
+#[test]
+fn test_partial_fill_amounts() {
+    let mut escrow = new_escrow();
+    
+    // A quarter of the receive releases a quarter of the deposit
+    assert_eq!(escrow.deposit_for(200_000_000), 125_000_000);
+    
+    // Filling the whole receive releases the whole deposit
+    assert_eq!(escrow.deposit_for(800_000_000), 500_000_000);
+    
+    // Rounding favors the maker
+    escrow.receive = 3;
+    escrow.deposit = 2;
+    assert_eq!(escrow.deposit_for(1), 0);
+    assert_eq!(escrow.deposit_for(2), 1);
+    
+    println!("✅ Partial fill amounts test passed");
+}
+
+/// Loads the program built by `anchor build`, from `SBF_OUT_DIR` when run through
+/// `cargo test-sbf`. Returns `None` when there is no build, so the caller can skip.
+fn deploy() -> Option<LiteSVM> {
+    let program_path = std::env::var("SBF_OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/deploy"))
+        .join("anchor_escrow.so");
+    if !program_path.exists() {
+        println!("Skipping: {} not found, run `anchor build` first", program_path.display());
+        return None;
+    }
+    let mut svm = LiteSVM::new();
+    svm.add_program_from_file(anchor_escrow::ID, program_path).unwrap();
+    Some(svm)
+}
+
+/// Writes the config directly, since InitializeConfig needs the program's ProgramData account
+fn set_config(svm: &mut LiteSVM, admin: &Pubkey, fee_recipient: &Pubkey, fee_bps: u16) -> Pubkey {
+    let (config, bump) = Pubkey::find_program_address(&[b"config"], &anchor_escrow::ID);
+    let mut data = Vec::new();
+    Config {
+        admin: *admin,
+        fee_recipient: *fee_recipient,
+        fee_bps,
+        max_referral_bps: 0,
+        paused: false,
+        bump,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    
+    let account = Account {
+        lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner: anchor_escrow::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    svm.set_account(config, account).unwrap();
+    config
+}
+
+fn get_token_balance(svm: &LiteSVM, account: &Pubkey) -> u64 {
+    litesvm_token::get_spl_account::<anchor_spl::token::spl_token::state::Account>(svm, account)
+        .map(|account| account.amount)
+        .unwrap_or(0)
+}
+
+/// Test a partial take through the deployed program
+#[test]
+fn test_partial_take_escrow() {
+    let Some(mut svm) = deploy() else {
+        return;
+    };
+    let maker = Keypair::new();
+    let taker = Keypair::new();
+    let treasury = Keypair::new();
+    svm.airdrop(&maker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+    svm.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+    svm.airdrop(&treasury.pubkey(), LAMPORTS_PER_SOL).unwrap();
+    let config = set_config(&mut svm, &maker.pubkey(), &treasury.pubkey(), 0);
+    
+    let mint_a = CreateMint::new(&mut svm, &maker).decimals(9).send().unwrap();
+    let mint_b = CreateMint::new(&mut svm, &taker).decimals(9).send().unwrap();
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut svm, &maker, &mint_a).send().unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut svm, &taker, &mint_b).send().unwrap();
+    MintTo::new(&mut svm, &maker, &mint_a, &maker_ata_a, 1_000_000_000).send().unwrap();
+    MintTo::new(&mut svm, &taker, &mint_b, &taker_ata_b, 2_000_000_000).send().unwrap();
+    
+    let seed = 42u64;
+    let (escrow, _) = Pubkey::find_program_address(
+        &[b"escrow", maker.pubkey().as_ref(), &seed.to_le_bytes()],
+        &anchor_escrow::ID,
+    );
+    let vault = spl_associated_token_account::get_associated_token_address(&escrow, &mint_a);
+    let taker_ata_a = spl_associated_token_account::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let maker_ata_b = spl_associated_token_account::get_associated_token_address(&maker.pubkey(), &mint_b);
+    
+    let make_ix = Instruction {
+        program_id: anchor_escrow::ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker: maker.pubkey(),
+            escrow,
+            mint_a,
+            mint_b,
+            maker_ata_a: Some(maker_ata_a),
+            vault,
+            config,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: spl_token::ID,
+            system_program: solana_program::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_escrow::instruction::Make {
+            seed,
+            receive: 800_000_000,
+            amount: 500_000_000,
+            expiry: 0,
+            taker: None,
+            merkle_root: None,
+            auction: None,
+            oracle: None,
+            collection: None,
+        }
+        .data(),
+    };
+    let tx = Transaction::new_signed_with_payer(&[make_ix], Some(&maker.pubkey()), &[&maker], svm.latest_blockhash());
+    svm.send_transaction(tx).unwrap();
+    
+    // Fill a quarter of the escrow
+    let take_ix = Instruction {
+        program_id: anchor_escrow::ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(),
+            maker: maker.pubkey(),
+            escrow,
+            mint_a,
+            mint_b,
+            vault,
+            taker_ata_a: Some(taker_ata_a),
+            unwrap_account: None,
+            taker_ata_b: Some(taker_ata_b),
+            maker_ata_b: Some(maker_ata_b),
+            config,
+            treasury: treasury.pubkey(),
+            treasury_ata_b: None,
+            price_feed: None,
+            metadata: None,
+            referrer: None,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: spl_token::ID,
+            system_program: solana_program::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: anchor_escrow::instruction::Take {
+            amount: 200_000_000,
+            referral_bps: 0,
+            max_pay: 200_000_000,
+            min_receive: 125_000_000,
+            proof: vec![],
+        }
+        .data(),
+    };
+    let tx = Transaction::new_signed_with_payer(&[take_ix], Some(&taker.pubkey()), &[&taker], svm.latest_blockhash());
+    svm.send_transaction(tx).unwrap();
+    
+    assert_eq!(get_token_balance(&svm, &taker_ata_a), 125_000_000);
+    assert_eq!(get_token_balance(&svm, &vault), 375_000_000);
+    assert_eq!(get_token_balance(&svm, &taker_ata_b), 1_800_000_000);
+    assert_eq!(get_token_balance(&svm, &maker_ata_b), 200_000_000);
+    
+    // The escrow stays open for the rest at the same price
+    let escrow = Escrow::try_deserialize(&mut svm.get_account(&escrow).unwrap().data.as_slice()).unwrap();
+    assert_eq!(escrow.receive, 600_000_000);
+    assert_eq!(escrow.deposit, 375_000_000);
+    
+    println!("✅ Partial take escrow test passed");
+}
+
+/// Test that an updated escrow prices later fills off its new terms
+#[test]
+fn test_repriced_fill_amounts() {
+    let mut escrow = new_escrow();
+    
+    // Raise the price and withdraw a fifth of the deposit
+    escrow.receive = 1_000_000_000;
+    escrow.deposit = 400_000_000;
+    assert_eq!(escrow.deposit_for(250_000_000), 100_000_000);
+    
+    // Top the vault back up without touching the price
+    escrow.deposit = 600_000_000;
+    assert_eq!(escrow.deposit_for(250_000_000), 150_000_000);
+    assert_eq!(escrow.deposit_for(1_000_000_000), 600_000_000);
+    
+    println!("✅ Repriced fill amounts test passed");
+}
+
+/// Test the protocol fee taken from the mint B leg
+#[test]
+fn test_protocol_fee_amounts() {
+    let mut config = Config {
+        admin: Pubkey::new_unique(),
+        fee_recipient: Pubkey::new_unique(),
+        fee_bps: 30,
+        max_referral_bps: 50,
+        paused: false,
+        bump: 255,
+    };
+    
+    // 30 bps of the mint B leg goes to the treasury, rounded down
+    assert_eq!(config.fee_for(800_000_000), 2_400_000);
+    assert_eq!(config.fee_for(333), 0);
+    
+    // A zero fee leaves the maker's proceeds untouched
+    config.fee_bps = 0;
+    assert_eq!(config.fee_for(800_000_000), 0);
+    
+    // The config is a single PDA shared by every escrow
+    let (config_pda, _) = Pubkey::find_program_address(&[b"config"], &anchor_escrow::ID);
+    assert!(!config_pda.is_on_curve());
+    
+    println!("✅ Protocol fee amounts test passed");
+}
+
+/// Test the referral share is capped by the config and paid out of the protocol fee
+#[test]
+fn test_referral_fee_amounts() {
+    let config = Config {
+        admin: Pubkey::new_unique(),
+        fee_recipient: Pubkey::new_unique(),
+        fee_bps: 30,
+        max_referral_bps: 5_000,
+        paused: false,
+        bump: 255,
+    };
+    
+    // Referrers can claim up to the configured share of the 2_400_000 protocol fee
+    let fee = config.fee_for(800_000_000);
+    assert_eq!(config.referral_fee_for(fee, 2_500), Some(600_000));
+    assert_eq!(config.referral_fee_for(fee, 5_000), Some(1_200_000));
+    assert_eq!(config.referral_fee_for(fee, 5_001), None);
+    
+    // The maker's proceeds don't depend on the referral, so self-referral gains nothing from them
+    assert_eq!(800_000_000 - fee, 797_600_000);
+    
+    // Each rate is capped at the whole of what it is a share of
+    assert!(Config::is_valid_fee(30, 50));
+    assert!(Config::is_valid_fee(10_000, 10_000));
+    assert!(!Config::is_valid_fee(10_001, 0));
+    assert!(!Config::is_valid_fee(30, 10_001));
+    
+    println!("✅ Referral fee amounts test passed");
+}
+
+/// Test the pause flag lives on the shared config account
+#[test]
+fn test_config_pause_flag() {
+    use anchor_lang::{AccountDeserialize, AccountSerialize, Discriminator, Space};
+    
+    let config = Config {
+        admin: Pubkey::new_unique(),
+        fee_recipient: Pubkey::new_unique(),
+        fee_bps: 30,
+        max_referral_bps: 50,
+        paused: true,
+        bump: 255,
+    };
+    assert_eq!(Config::INIT_SPACE, 32 + 32 + 2 + 2 + 1 + 1);
+    
+    // The flag survives a round trip through the account data Make and Take read
+    let mut data = Vec::new();
+    config.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), Config::DISCRIMINATOR.len() + Config::INIT_SPACE);
+    let decoded = Config::try_deserialize(&mut data.as_slice()).unwrap();
+    assert!(decoded.paused);
+    
+    println!("✅ Config pause flag test passed");
+}
+
+/// Test a dispute freezes arbiter escrow expiry
+#[test]
+fn test_arbiter_escrow_dispute() {
+    use anchor_lang::{Discriminator, Space};
+    
+    let mut escrow = ArbiterEscrow {
+        seed: 42,
+        maker: Pubkey::new_unique(),
+        beneficiary: Pubkey::new_unique(),
+        arbiter: Pubkey::new_unique(),
+        mint_a: Pubkey::new_unique(),
+        expiry: 1_735_689_600,
+        milestones: [500_000_000, 0, 0, 0],
+        released: 0,
+        disputed: false,
+        bump: 255,
+    };
+    assert_eq!(ArbiterEscrow::INIT_SPACE, 8 + 32 * 4 + 8 + 8 * 4 + 1 + 1 + 1);
+    assert_eq!(ArbiterEscrow::DISCRIMINATOR, &[3]);
+    
+    // Undisputed escrows expire like swap escrows, opening the maker's refund
+    assert!(!escrow.is_expired(1_735_689_600));
+    assert!(escrow.is_expired(1_735_689_601));
+    
+    // Once disputed only the arbiter can settle
+    escrow.disputed = true;
+    assert!(!escrow.is_expired(i64::MAX));
+    
+    println!("✅ Arbiter escrow dispute test passed");
+}
+
+/// Test milestone bookkeeping matches the pinocchio program
+#[test]
+fn test_arbiter_escrow_milestones() {
+    let mut escrow = ArbiterEscrow {
+        seed: 42,
+        maker: Pubkey::new_unique(),
+        beneficiary: Pubkey::new_unique(),
+        arbiter: Pubkey::new_unique(),
+        mint_a: Pubkey::new_unique(),
+        expiry: 0,
+        milestones: [100, 200, 300, 0],
+        released: 0,
+        disputed: false,
+        bump: 255,
+    };
+    assert_eq!(escrow.milestone_count(), 3);
+    
+    // Tranches can be paid in any order; the escrow only closes once all are out
+    escrow.released |= 1 << 2;
+    escrow.released |= 1;
+    assert!(escrow.is_milestone_released(0));
+    assert!(!escrow.is_milestone_released(1));
+    assert!(!escrow.is_fully_released());
+    
+    escrow.released |= 1 << 1;
+    assert!(escrow.is_fully_released());
+    
+    println!("✅ Arbiter escrow milestones test passed");
+}
+
+/// Test linear vesting unlocks match the pinocchio program
+#[test]
+fn test_vesting_escrow_unlock() {
+    use anchor_lang::{Discriminator, Space};
+    
+    let mut escrow = VestingEscrow {
+        seed: 7,
+        maker: Pubkey::new_unique(),
+        beneficiary: Pubkey::new_unique(),
+        mint_a: Pubkey::new_unique(),
+        start: 1_000,
+        cliff: 1_250,
+        end: 2_000,
+        total: 1_000_000,
+        withdrawn: 0,
+        bump: 255,
+    };
+    assert_eq!(VestingEscrow::INIT_SPACE, 8 + 32 * 3 + 8 * 3 + 8 + 8 + 1);
+    assert_eq!(VestingEscrow::DISCRIMINATOR, &[4]);
+    
+    // Nothing unlocks before the cliff, then everything since start does
+    assert_eq!(escrow.unlocked_at(1_249), 0);
+    assert_eq!(escrow.unlocked_at(1_250), 250_000);
+    assert_eq!(escrow.unlocked_at(2_000), 1_000_000);
+    
+    // Claims only pay out what has vested since the last one
+    escrow.withdrawn = 500_000;
+    assert_eq!(escrow.claimable_at(1_500), 0);
+    assert_eq!(escrow.claimable_at(1_750), 250_000);
+    
+    println!("✅ Vesting escrow unlock test passed");
+}
+
+/// Test HTLC hashlocks accept only the preimage, with an optional Keccak-256 lock
+#[test]
+fn test_htlc_hashlock() {
+    use anchor_lang::Space;
+    
+    let preimage = [9u8; 32];
+    let sha256_hash = hash(&preimage).to_bytes();
+    let keccak_hash = solana_keccak_hasher::hash(&preimage).to_bytes();
+    
+    // SHA-256 only, as a Bitcoin counterparty would lock
+    assert!(verify_hashlock(&preimage, &sha256_hash, &[0; 32]));
+    assert!(!verify_hashlock(&[8u8; 32], &sha256_hash, &[0; 32]));
+    
+    // With a Keccak-256 lock as well, both must match
+    assert!(verify_hashlock(&preimage, &sha256_hash, &keccak_hash));
+    assert!(!verify_hashlock(&preimage, &sha256_hash, &sha256_hash));
+    
+    let escrow = HtlcEscrow {
+        seed: 1,
+        maker: Pubkey::new_unique(),
+        recipient: Pubkey::new_unique(),
+        mint_a: Pubkey::new_unique(),
+        sha256_hash,
+        keccak_hash,
+        expiry: 1_735_689_600,
+        bump: 255,
+    };
+    assert_eq!(HtlcEscrow::INIT_SPACE, 8 + 32 * 3 + 32 * 2 + 8 + 1);
+    
+    // Claims are open up to and including the timelock, refunds only after it
+    assert!(!escrow.is_expired(1_735_689_600));
+    assert!(escrow.is_expired(1_735_689_601));
+    
+    println!("✅ HTLC hashlock test passed");
+}
+
+/// Test Dutch auction prices match the pinocchio program
+#[test]
+fn test_dutch_auction_amounts() {
+    // Opens at 1_200_000_000 of mint B and falls to the 800_000_000 floor over 1_000 seconds
+    let mut escrow = new_escrow();
+    escrow.auction = Some(DutchAuction {
+        start_receive: 1_200_000_000,
+        start: 1_000,
+        end: 2_000,
+    });
+    
+    assert_eq!(escrow.receive_at(0), 1_200_000_000);
+    assert_eq!(escrow.receive_at(1_250), 1_100_000_000);
+    assert_eq!(escrow.receive_at(1_500), 1_000_000_000);
+    assert_eq!(escrow.receive_at(2_000), 800_000_000);
+    assert_eq!(new_escrow().receive_at(1_500), 800_000_000);
+    
+    // A fill for half the current price releases half the deposit
+    let price = escrow.receive_at(1_500);
+    assert_eq!(escrow.deposit_at_price(500_000_000, price), 250_000_000);
+    
+    // Both ends of the schedule shrink with the deposit, keeping the per-unit price curve
+    assert_eq!(remaining_after_fill(1_200_000_000, 500_000_000, price), 600_000_000);
+    assert_eq!(remaining_after_fill(escrow.receive, 500_000_000, price), 400_000_000);
+    
+    println!("✅ Dutch auction amounts test passed");
+}
+
+/// Test escrow expiry semantics match the pinocchio program
+#[test]
+fn test_escrow_expiry() {
+    let mut escrow = new_escrow();
+    
+    // Zero expiry never expires
+    assert!(!escrow.is_expired(i64::MAX));
+    
+    // Takes are allowed up to and including the expiry timestamp
+    escrow.expiry = 1735689600;
+    assert!(!escrow.is_expired(1735689599));
+    assert!(!escrow.is_expired(1735689600));
+    assert!(escrow.is_expired(1735689601));
+    
+    println!("✅ Escrow expiry test passed");
+}
+
+/// Test designated-taker escrows reject other signers
+#[test]
+fn test_designated_taker() {
+    let mut escrow = new_escrow();
+    let taker = Pubkey::new_unique();
+    let sniper = Pubkey::new_unique();
+    
+    // Without a designated taker anyone can take
+    assert!(escrow.is_taker_allowed(&taker));
+    assert!(escrow.is_taker_allowed(&sniper));
+    
+    // A designated taker locks out everyone else
+    escrow.taker = Some(taker);
+    assert!(escrow.is_taker_allowed(&taker));
+    assert!(!escrow.is_taker_allowed(&sniper));
+    
+    println!("✅ Designated taker test passed");
+}
+
+/// Test merkle allowlist proofs for restricted escrows
+#[test]
+fn test_allowlist_merkle_proof() {
+    let wallets: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let leaves: Vec<[u8; 32]> = wallets.iter().map(|w| hash(w.as_ref()).to_bytes()).collect();
+    let pair = |a: &[u8; 32], b: &[u8; 32]| {
+        if a <= b {
+            hashv(&[a, b]).to_bytes()
+        } else {
+            hashv(&[b, a]).to_bytes()
+        }
+    };
+    
+    // Build a four-leaf tree
+    let left = pair(&leaves[0], &leaves[1]);
+    let right = pair(&leaves[2], &leaves[3]);
+    let root = pair(&left, &right);
+    
+    // Every allowlisted wallet can prove membership
+    assert!(verify_merkle_proof(&[leaves[1], right], &root, &wallets[0]));
+    assert!(verify_merkle_proof(&[leaves[0], right], &root, &wallets[1]));
+    assert!(verify_merkle_proof(&[leaves[3], left], &root, &wallets[2]));
+    assert!(verify_merkle_proof(&[leaves[2], left], &root, &wallets[3]));
+    
+    // Outsiders and truncated proofs are rejected
+    assert!(!verify_merkle_proof(&[leaves[1], right], &root, &Pubkey::new_unique()));
+    assert!(!verify_merkle_proof(&[leaves[1]], &root, &wallets[0]));
+    assert!(!verify_merkle_proof(&[], &root, &wallets[0]));
+    
+    println!("✅ Allowlist merkle proof test passed");
+}
+
+/// Test transfer-fee aware amounts for Token-2022 mints
+#[test]
+fn test_transfer_fee_amounts() {
+    // Build a Token-2022 mint charging 1% capped at 5_000_000
+    let len = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+    let mut data = vec![0u8; len];
+    {
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        let fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: 5_000_000.into(),
+            transfer_fee_basis_points: 100.into(),
+        };
+        let config = mint.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.older_transfer_fee = fee;
+        config.newer_transfer_fee = fee;
+        mint.base.decimals = 9;
+        mint.base.is_initialized = true;
+        mint.pack_base();
+        mint.init_account_type().unwrap();
+    }
+    
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mint = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &spl_token_2022::ID, false, 0);
+    
+    // Only the net amount lands in the vault
+    assert_eq!(amount_after_fee(&mint, 0, 100_000_000).unwrap(), 99_000_000);
+    
+    // The taker pays enough for the maker to receive exactly the fill amount
+    assert_eq!(amount_before_fee(&mint, 0, 99_000_000).unwrap(), 100_000_000);
+    assert_eq!(amount_before_fee(&mint, 0, 800_000_000).unwrap(), 805_000_000);
+    
+    // Mints without the extension are untouched
+    let mut plain_data = vec![0u8; Mint::LEN];
+    let plain_key = Pubkey::new_unique();
+    let mut plain_lamports = 0;
+    let plain_mint = AccountInfo::new(&plain_key, false, false, &mut plain_lamports, &mut plain_data, &spl_token::ID, false, 0);
+    assert_eq!(amount_after_fee(&plain_mint, 0, 100_000_000).unwrap(), 100_000_000);
+    assert_eq!(amount_before_fee(&plain_mint, 0, 100_000_000).unwrap(), 100_000_000);
+    
+    println!("✅ Transfer fee amounts test passed");
+}
+
+/// Lays out a fully verified Pyth `PriceUpdateV2` account
+fn mock_price_update(feed_id: &[u8; 32], price: i64, conf: u64, exponent: i32, publish_time: i64) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&PRICE_UPDATE_V2_DISCRIMINATOR);
+    data.extend_from_slice(&[9; 32]); // write authority
+    data.push(VERIFICATION_LEVEL_FULL);
+    data.extend_from_slice(feed_id);
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(&conf.to_le_bytes());
+    data.extend_from_slice(&exponent.to_le_bytes());
+    data.extend_from_slice(&publish_time.to_le_bytes());
+    data.extend_from_slice(&(publish_time - 1).to_le_bytes()); // prev publish time
+    data.extend_from_slice(&price.to_le_bytes()); // ema price
+    data.extend_from_slice(&conf.to_le_bytes()); // ema conf
+    data.extend_from_slice(&1u64.to_le_bytes()); // posted slot
+    data
+}
+
+/// Test oracle quotes read from a mock price feed match the pinocchio program
+#[test]
+fn test_oracle_price_feed() {
+    let mut svm = LiteSVM::new();
+    
+    // SOL/USD at $150.00 +/- $0.15, published at 1_000
+    let key = Pubkey::new_unique();
+    svm.set_account(
+        key,
+        Account {
+            lamports: 1_000_000_000,
+            data: mock_price_update(&[4; 32], 15_000_000_000, 15_000_000, -8, 1_000),
+            owner: PYTH_RECEIVER_ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+    let mut account = svm.get_account(&key).unwrap();
+    let mut lamports = account.lamports;
+    let owner = account.owner;
+    let price_feed = AccountInfo::new(&key, false, false, &mut lamports, &mut account.data, &owner, false, 0);
+    
+    let feed = PriceFeed::load(&price_feed).unwrap();
+    assert_eq!(feed.price, 15_000_000_000);
+    assert_eq!(feed.exponent, -8);
+    
+    // Only the feed the escrow names is accepted
+    assert!(feed.check_feed_id(&[4; 32]).is_ok());
+    assert!(feed.check_feed_id(&[5; 32]).is_err());
+    
+    // Staleness is bounded in seconds since publication
+    assert!(feed.check_fresh(1_060, 60).is_ok());
+    assert!(feed.check_fresh(1_061, 60).is_err());
+    
+    // The confidence interval is 10 bps of the price
+    assert!(feed.check_confidence(10).is_ok());
+    assert!(feed.check_confidence(9).is_err());
+    
+    // 2 SOL (9 decimals) at market minus 1% costs 297 USDC (6 decimals)
+    assert_eq!(feed.quote(2_000_000_000, 9, 6, -100), Some(297_000_000));
+    assert_eq!(feed.quote(2_000_000_000, 9, 6, 0), Some(300_000_000));
+    assert_eq!(feed.quote(1, 9, 6, 0), Some(1));
+    
+    // Only accounts posted by the Pyth receiver are trusted
+    let mut data = mock_price_update(&[4; 32], 15_000_000_000, 15_000_000, -8, 1_000);
+    let mut spoof_lamports = 0;
+    let spoof_key = Pubkey::new_unique();
+    let spoofed = AccountInfo::new(&spoof_key, false, false, &mut spoof_lamports, &mut data, &spoof_key, false, 0);
+    assert!(PriceFeed::load(&spoofed).is_err());
+    
+    // Partially verified updates and non-positive prices are rejected
+    let mut partial = mock_price_update(&[4; 32], 15_000_000_000, 15_000_000, -8, 1_000);
+    partial[40] = 0;
+    assert!(PriceFeed::parse(&partial).is_err());
+    assert!(PriceFeed::parse(&mock_price_update(&[4; 32], -1, 0, -8, 1_000)).is_err());
+    
+    println!("✅ Oracle price feed test passed");
+}
+
+/// Test English auction bid rules match the pinocchio program
+#[test]
+fn test_english_auction_bids() {
+    let maker = Pubkey::new_unique();
+    let mut auction = AuctionEscrow {
+        seed: 7,
+        maker,
+        mint_a: Pubkey::new_unique(),
+        mint_b: Pubkey::new_unique(),
+        reserve: 1_000_000_000,
+        min_increment: 50_000_000,
+        end: 1_735_689_600,
+        highest_bid: 0,
+        highest_bidder: None,
+        bump: 254,
+    };
+    
+    // The first bid must meet the reserve, each later one must beat the leader by the increment
+    assert_eq!(auction.min_bid(), 1_000_000_000);
+    auction.highest_bid = 1_000_000_000;
+    auction.highest_bidder = Some(Pubkey::new_unique());
+    assert_eq!(auction.min_bid(), 1_050_000_000);
+    
+    // Bidding closes at the end time, which is also when settlement opens
+    assert!(!auction.is_ended(1_735_689_599));
+    assert!(auction.is_ended(1_735_689_600));
+    
+    // Bid PDAs are unique per auction and bidder
+    let program_id = anchor_escrow::ID;
+    let (auction_key, _) = Pubkey::find_program_address(
+        &[b"auction", maker.as_ref(), &7u64.to_le_bytes()],
+        &program_id,
+    );
+    let bidder = Pubkey::new_unique();
+    let (bid, _) = Pubkey::find_program_address(&[b"bid", auction_key.as_ref(), bidder.as_ref()], &program_id);
+    let (other_bid, _) = Pubkey::find_program_address(
+        &[b"bid", auction_key.as_ref(), Pubkey::new_unique().as_ref()],
+        &program_id,
+    );
+    assert_ne!(bid, other_bid);
+    
+    println!("✅ English auction bids test passed");
+}
+
+/// Lays out a Metaplex `Metadata` account for `mint`, with one creator and an optional collection
+fn mock_metadata(mint: &Pubkey, collection: Option<(bool, Pubkey)>) -> Vec<u8> {
+    let mut data = vec![KEY_METADATA_V1];
+    data.extend_from_slice(&[8; 32]); // update authority
+    data.extend_from_slice(mint.as_ref());
+    for field in [&b"Escrow Ape #1"[..], b"EAPE", b"https://example.com/1.json"] {
+        data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        data.extend_from_slice(field);
+    }
+    data.extend_from_slice(&500u16.to_le_bytes()); // seller fee basis points
+    data.push(1); // creators
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&[8; 32]);
+    data.extend_from_slice(&[1, 100]); // verified, share
+    data.extend_from_slice(&[1, 1]); // primary sale happened, is mutable
+    data.extend_from_slice(&[1, 255]); // edition nonce
+    data.extend_from_slice(&[1, 0]); // token standard
+    match collection {
+        Some((verified, key)) => {
+            data.extend_from_slice(&[1, verified as u8]);
+            data.extend_from_slice(key.as_ref());
+        }
+        None => data.push(0),
+    }
+    data.push(0); // uses
+    data
+}
+
+/// Test collection membership read from mock NFT metadata matches the pinocchio program
+#[test]
+fn test_collection_nft_metadata() {
+    let collection = Pubkey::new_unique();
+    let nft = Pubkey::new_unique();
+    
+    // Metadata lives at the canonical PDA of the NFT mint
+    let (key, _) = Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA_ID.as_ref(), nft.as_ref()],
+        &TOKEN_METADATA_ID,
+    );
+    let mut data = mock_metadata(&nft, Some((true, collection)));
+    let mut lamports = 1_000_000_000;
+    let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &TOKEN_METADATA_ID, false, 0);
+    
+    let metadata = Metadata::load(&account, &nft).unwrap();
+    assert!(metadata.is_verified_member_of(&collection));
+    assert!(!metadata.is_verified_member_of(&Pubkey::new_unique()));
+    
+    // Metadata of another mint can't vouch for this one
+    assert!(Metadata::load(&account, &Pubkey::new_unique()).is_err());
+    
+    // Anyone can claim a collection, so only verified ones count
+    let unverified = Metadata::parse(&mock_metadata(&nft, Some((false, collection)))).unwrap();
+    assert!(!unverified.is_verified_member_of(&collection));
+    let uncollected = Metadata::parse(&mock_metadata(&nft, None)).unwrap();
+    assert!(!uncollected.is_verified_member_of(&collection));
+    
+    // Truncated or foreign accounts don't parse
+    let full = mock_metadata(&nft, Some((true, collection)));
+    assert!(Metadata::parse(&full[..100]).is_none());
+    let mut edition = full.clone();
+    edition[0] = 6;
+    assert!(Metadata::parse(&edition).is_none());
+    
+    // Each take hands over one NFT, releasing a proportional share of the deposit
+    let mut escrow = new_escrow();
+    escrow.mint_b = Pubkey::default();
+    escrow.collection = Some(collection);
+    escrow.receive = 5;
+    assert_eq!(escrow.deposit_for(1), 100_000_000);
+    
+    println!("✅ Collection NFT metadata test passed");
+}
+
+/// Test bundle legs and vault derivation match the pinocchio program
+#[test]
+fn test_bundle_escrow_legs() {
+    let maker = Pubkey::new_unique();
+    let legs_a: Vec<Leg> = (0..3)
+        .map(|i| Leg { mint: Pubkey::new_unique(), amount: 100 * (i + 1) })
+        .collect();
+    let bundle = BundleEscrow {
+        seed: 9,
+        maker,
+        legs_a: legs_a.clone(),
+        legs_b: vec![Leg { mint: Pubkey::new_unique(), amount: 1_000 }],
+        bump: 252,
+    };
+    assert_eq!(bundle.legs_a.len() + bundle.legs_b.len(), 4);
+    
+    // Space is reserved for the maximum number of legs on both sides
+    assert_eq!(MAX_BUNDLE_LEGS, 4);
+    assert_eq!(BundleEscrow::INIT_SPACE, 8 + 32 + (4 + 40 * 4) * 2 + 1);
+    
+    // A mint repeated on one side is rejected
+    assert!(!has_duplicate_mints(&bundle.legs_a));
+    assert!(has_duplicate_mints(&[legs_a[0], legs_a[1], legs_a[0]]));
+    
+    // Every mint A leg has its own vault, all owned by the one bundle PDA
+    let program_id = anchor_escrow::ID;
+    let (bundle_pda, _) = Pubkey::find_program_address(
+        &[b"bundle", maker.as_ref(), &9u64.to_le_bytes()],
+        &program_id,
+    );
+    let vaults: Vec<Pubkey> = legs_a
+        .iter()
+        .map(|leg| {
+            anchor_spl::associated_token::get_associated_token_address_with_program_id(
+                &bundle_pda,
+                &leg.mint,
+                &spl_token::ID,
+            )
+        })
+        .collect();
+    assert_ne!(vaults[0], vaults[1]);
+    assert_ne!(vaults[1], vaults[2]);
+    
+    println!("✅ Bundle escrow legs test passed");
+}
+
+/// Test counter-offer accounts and fees match the pinocchio program
+#[test]
+fn test_counter_offer() {
+    let escrow = Pubkey::new_unique();
+    let taker = Pubkey::new_unique();
+    let offer = Offer {
+        escrow,
+        taker,
+        receive: 700_000_000,
+        deposit: 1_000_000_000,
+        bump: 251,
+    };
+    assert_eq!(Offer::INIT_SPACE, 32 + 32 + 8 + 8 + 1);
+    
+    // Each taker holds at most one offer per escrow, with its own mint B vault
+    let (offer_a, _) = Pubkey::find_program_address(
+        &[b"offer", escrow.as_ref(), taker.as_ref()],
+        &anchor_escrow::ID,
+    );
+    let (offer_b, _) = Pubkey::find_program_address(
+        &[b"offer", escrow.as_ref(), Pubkey::new_unique().as_ref()],
+        &anchor_escrow::ID,
+    );
+    assert_ne!(offer_a, offer_b);
+    
+    // Accepting charges the protocol fee on the offer, like a take would
+    let config = Config {
+        admin: Pubkey::new_unique(),
+        fee_recipient: Pubkey::new_unique(),
+        fee_bps: 30,
+        max_referral_bps: 50,
+        paused: false,
+        bump: 255,
+    };
+    let fee = config.fee_for(offer.receive);
+    assert_eq!(fee, 2_100_000);
+    assert_eq!(offer.receive - fee, 697_900_000);
+    
+    println!("✅ Counter offer test passed");
+}
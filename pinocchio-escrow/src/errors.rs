@@ -32,6 +32,51 @@ pub enum PinocchioError {
 
     #[error("Account already initialized")]
     AlreadyInitialized,
+
+    #[error("Escrow not expired")]
+    EscrowNotExpired,
+
+    #[error("Invalid taker")]
+    InvalidTaker,
+
+    #[error("Invalid merkle proof")]
+    InvalidProof,
+
+    #[error("Invalid fee")]
+    InvalidFee,
+
+    #[error("Program paused")]
+    ProgramPaused,
+
+    #[error("Invalid milestone")]
+    InvalidMilestone,
+
+    #[error("Invalid preimage")]
+    InvalidPreimage,
+
+    #[error("Slippage exceeded")]
+    SlippageExceeded,
+
+    #[error("Invalid price feed")]
+    InvalidPriceFeed,
+
+    #[error("Stale price")]
+    StalePrice,
+
+    #[error("Price too uncertain")]
+    PriceTooUncertain,
+
+    #[error("Bid too low")]
+    BidTooLow,
+
+    #[error("Leading bid can't be withdrawn")]
+    LeadingBid,
+
+    #[error("Invalid NFT")]
+    InvalidNft,
+
+    #[error("Invalid collection")]
+    InvalidCollection,
 }
 
 impl From<PinocchioError> for ProgramError {
@@ -0,0 +1,104 @@
+use pinocchio::pubkey::Pubkey;
+
+pub const HASH_BYTES: usize = 32;
+
+/// SHA-256 of the concatenation of `vals`, computed with the `sol_sha256` syscall on chain.
+#[inline(always)]
+pub fn sha256v(vals: &[&[u8]]) -> [u8; HASH_BYTES] {
+    #[cfg(target_os = "solana")]
+    {
+        let mut hash = core::mem::MaybeUninit::<[u8; HASH_BYTES]>::uninit();
+
+        unsafe {
+            pinocchio::syscalls::sol_sha256(
+                vals as *const _ as *const u8,
+                vals.len() as u64,
+                hash.as_mut_ptr() as *mut u8,
+            );
+        }
+
+        // SAFETY: The syscall has initialized the bytes.
+        unsafe { hash.assume_init() }
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        for val in vals {
+            hasher.update(val);
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// Keccak-256 of the concatenation of `vals`, computed with the `sol_keccak256` syscall on chain.
+#[inline(always)]
+pub fn keccak256v(vals: &[&[u8]]) -> [u8; HASH_BYTES] {
+    #[cfg(target_os = "solana")]
+    {
+        let mut hash = core::mem::MaybeUninit::<[u8; HASH_BYTES]>::uninit();
+
+        unsafe {
+            pinocchio::syscalls::sol_keccak256(
+                vals as *const _ as *const u8,
+                vals.len() as u64,
+                hash.as_mut_ptr() as *mut u8,
+            );
+        }
+
+        // SAFETY: The syscall has initialized the bytes.
+        unsafe { hash.assume_init() }
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    {
+        use sha3::{Digest, Keccak256};
+
+        let mut hasher = Keccak256::new();
+        for val in vals {
+            hasher.update(val);
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// Verifies that `preimage` opens a hashlock.
+///
+/// The SHA-256 hash is always checked, matching Bitcoin-style HTLCs; an
+/// all-zero `keccak_hash` means no Keccak-256 lock was set, otherwise it must
+/// match too so the same secret also settles an EVM-side contract.
+pub fn verify_hashlock(
+    preimage: &[u8],
+    sha256_hash: &[u8; HASH_BYTES],
+    keccak_hash: &[u8; HASH_BYTES],
+) -> bool {
+    if sha256v(&[preimage]) != *sha256_hash {
+        return false;
+    }
+
+    *keccak_hash == [0; HASH_BYTES] || keccak256v(&[preimage]) == *keccak_hash
+}
+
+/// Verifies that `key` is a leaf of the merkle tree with the given `root`.
+///
+/// Leaves are `sha256(key)` and each parent is the hash of its two children
+/// in sorted order, so `proof` is just the concatenated sibling hashes from
+/// the leaf up to the root.
+pub fn verify_merkle_proof(proof: &[u8], root: &[u8; HASH_BYTES], key: &Pubkey) -> bool {
+    if proof.len() % HASH_BYTES != 0 {
+        return false;
+    }
+
+    let mut node = sha256v(&[key.as_ref()]);
+    for sibling in proof.chunks_exact(HASH_BYTES) {
+        node = if node.as_slice() <= sibling {
+            sha256v(&[&node, sibling])
+        } else {
+            sha256v(&[sibling, &node])
+        };
+    }
+
+    node == *root
+}
@@ -0,0 +1,264 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{AssociatedTokenAccount, ConfigAccount, MintAccount, ProgramAccount, SignerAccount, TokenAccount, TokenProgram};
+use crate::state::{Config, Escrow, Offer};
+
+pub struct AcceptCounterAccounts<'a> {
+    pub maker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub offer: &'a AccountInfo,
+    pub taker: &'a AccountInfo,
+    pub mint_a: &'a AccountInfo,
+    pub mint_b: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub offer_vault: &'a AccountInfo,
+    pub taker_ata_a: &'a AccountInfo,
+    pub maker_ata_b: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub treasury: &'a AccountInfo,
+    pub treasury_ata_b: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    /// Token program owning mint A, which the vault and the taker's mint A account live under
+    pub token_program_a: &'a AccountInfo,
+    /// Token program owning mint B, which the offer vault and the payouts go through
+    pub token_program_b: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for AcceptCounterAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [maker, escrow, offer, taker, mint_a, mint_b, vault, offer_vault, taker_ata_a, maker_ata_b, config, treasury, treasury_ata_b, system_program, token_program_a, token_program_b, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(maker)?;
+        ProgramAccount::check(escrow)?;
+        ProgramAccount::check_len(offer, Offer::LEN)?;
+        MintAccount::check(mint_a)?;
+        MintAccount::check(mint_b)?;
+        TokenProgram::check(token_program_a, mint_a)?;
+        TokenProgram::check(token_program_b, mint_b)?;
+        AssociatedTokenAccount::check(vault, escrow, mint_a, token_program_a)?;
+        AssociatedTokenAccount::check(offer_vault, offer, mint_b, token_program_b)?;
+        ConfigAccount::check(config)?;
+
+        Ok(Self {
+            maker,
+            escrow,
+            offer,
+            taker,
+            mint_a,
+            mint_b,
+            vault,
+            offer_vault,
+            taker_ata_a,
+            maker_ata_b,
+            config,
+            treasury,
+            treasury_ata_b,
+            system_program,
+            token_program_a,
+            token_program_b,
+        })
+    }
+}
+
+pub struct AcceptCounter<'a> {
+    pub accounts: AcceptCounterAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for AcceptCounter<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = AcceptCounterAccounts::try_from(accounts)?;
+
+        // The maker accepts, so the maker pays for any token account the swap needs
+        AssociatedTokenAccount::init_if_needed(
+            accounts.taker_ata_a,
+            accounts.mint_a,
+            accounts.maker,
+            accounts.taker,
+            accounts.system_program,
+            accounts.token_program_a,
+        )?;
+        AssociatedTokenAccount::init_if_needed(
+            accounts.maker_ata_b,
+            accounts.mint_b,
+            accounts.maker,
+            accounts.maker,
+            accounts.system_program,
+            accounts.token_program_b,
+        )?;
+
+        let fee_bps = Config::load(&accounts.config.try_borrow_data()?)?.fee_bps();
+        if fee_bps > 0 {
+            AssociatedTokenAccount::init_if_needed(
+                accounts.treasury_ata_b,
+                accounts.mint_b,
+                accounts.maker,
+                accounts.treasury,
+                accounts.system_program,
+                accounts.token_program_b,
+            )?;
+        }
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> AcceptCounter<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &26;
+    pub const CONFIG_INDEX: usize = 10;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let data = self.accounts.escrow.try_borrow_data()?;
+        let escrow = Escrow::load(&data)?;
+
+        // Verify the maker account matches the stored maker
+        if escrow.maker != *self.accounts.maker.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+
+        // Verify the mints match the stored mints
+        if escrow.mint_a != *self.accounts.mint_a.key() || escrow.mint_b != *self.accounts.mint_b.key() {
+            return Err(crate::errors::PinocchioError::InvalidMint.into());
+        }
+
+        let escrow_key = create_program_address(
+            &[
+                b"escrow",
+                self.accounts.maker.key(),
+                &escrow.seed.to_le_bytes(),
+                &escrow.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &escrow_key != self.accounts.escrow.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        let offer_data = self.accounts.offer.try_borrow_data()?;
+        let offer = Offer::load(&offer_data)?;
+
+        // The offer must be the taker's counter on this very escrow
+        if offer.taker != *self.accounts.taker.key() {
+            return Err(crate::errors::PinocchioError::InvalidTaker.into());
+        }
+        let offer_key = create_program_address(
+            &[
+                b"offer",
+                self.accounts.escrow.key(),
+                self.accounts.taker.key(),
+                &offer.bump,
+            ],
+            &crate::ID,
+        )?;
+        if offer.escrow != *self.accounts.escrow.key() || &offer_key != self.accounts.offer.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        // A fill or update since the offer would hand the taker less mint A than they countered for
+        if escrow.deposit < offer.deposit || TokenAccount::get_amount(self.accounts.vault)? < offer.deposit {
+            return Err(crate::errors::PinocchioError::SlippageExceeded.into());
+        }
+
+        // The protocol fee comes out of the offered mint B, as it would on a take
+        let config_data = self.accounts.config.try_borrow_data()?;
+        let config = Config::load(&config_data)?;
+        if config.fee_recipient != *self.accounts.treasury.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+        let offered = TokenAccount::get_amount(self.accounts.offer_vault)?;
+        let fee = config.fee_for(offered);
+        drop(config_data);
+
+        let seed_binding = escrow.seed.to_le_bytes();
+        let bump_binding = escrow.bump;
+        let escrow_seeds = [
+            Seed::from(b"escrow"),
+            Seed::from(self.accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+        let escrow_signers = [Signer::from(&escrow_seeds)];
+
+        TransferChecked {
+            from: self.accounts.vault,
+            mint: self.accounts.mint_a,
+            to: self.accounts.taker_ata_a,
+            authority: self.accounts.escrow,
+            amount: TokenAccount::get_amount(self.accounts.vault)?,
+            decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+            token_program: self.accounts.token_program_a.key(),
+        }
+        .invoke_signed(&escrow_signers)?;
+
+        CloseAccount {
+            account: self.accounts.vault,
+            destination: self.accounts.maker,
+            authority: self.accounts.escrow,
+            token_program: self.accounts.token_program_a.key(),
+        }
+        .invoke_signed(&escrow_signers)?;
+
+        let offer_bump_binding = offer.bump;
+        let offer_seeds = [
+            Seed::from(b"offer"),
+            Seed::from(self.accounts.escrow.key().as_ref()),
+            Seed::from(self.accounts.taker.key().as_ref()),
+            Seed::from(&offer_bump_binding),
+        ];
+        let offer_signers = [Signer::from(&offer_seeds)];
+
+        let decimals_b = MintAccount::get_decimals(self.accounts.mint_b)?;
+        TransferChecked {
+            from: self.accounts.offer_vault,
+            mint: self.accounts.mint_b,
+            to: self.accounts.maker_ata_b,
+            authority: self.accounts.offer,
+            amount: offered - fee,
+            decimals: decimals_b,
+            token_program: self.accounts.token_program_b.key(),
+        }
+        .invoke_signed(&offer_signers)?;
+        if fee > 0 {
+            TransferChecked {
+                from: self.accounts.offer_vault,
+                mint: self.accounts.mint_b,
+                to: self.accounts.treasury_ata_b,
+                authority: self.accounts.offer,
+                amount: fee,
+                decimals: decimals_b,
+                token_program: self.accounts.token_program_b.key(),
+            }
+            .invoke_signed(&offer_signers)?;
+        }
+
+        // The taker funded the offer and its vault, so their rent goes back to the taker
+        CloseAccount {
+            account: self.accounts.offer_vault,
+            destination: self.accounts.taker,
+            authority: self.accounts.offer,
+            token_program: self.accounts.token_program_b.key(),
+        }
+        .invoke_signed(&offer_signers)?;
+
+        drop(offer_data);
+        drop(data);
+        ProgramAccount::close(self.accounts.offer, self.accounts.taker)?;
+        ProgramAccount::close(self.accounts.escrow, self.accounts.maker)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenAccount, TokenProgram};
+use crate::state::ArbiterEscrow;
+
+pub struct ArbiterRefundAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub maker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub mint_a: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub maker_ata_a: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for ArbiterRefundAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, maker, escrow, mint_a, vault, maker_ata_a, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(authority)?;
+        ProgramAccount::check_len(escrow, ArbiterEscrow::LEN)?;
+        MintAccount::check(mint_a)?;
+        TokenProgram::check(token_program, mint_a)?;
+        AssociatedTokenAccount::check(vault, escrow, mint_a, token_program)?;
+
+        Ok(Self {
+            authority,
+            maker,
+            escrow,
+            mint_a,
+            vault,
+            maker_ata_a,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct ArbiterRefund<'a> {
+    pub accounts: ArbiterRefundAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for ArbiterRefund<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = ArbiterRefundAccounts::try_from(accounts)?;
+        AssociatedTokenAccount::init_if_needed(
+            accounts.maker_ata_a,
+            accounts.mint_a,
+            accounts.authority,
+            accounts.maker,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> ArbiterRefund<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &10;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let data = self.accounts.escrow.try_borrow_data()?;
+        let escrow = ArbiterEscrow::load(&data)?;
+
+        // The arbiter may refund at any time; the maker only once an undisputed escrow expires
+        let authority = self.accounts.authority.key();
+        if escrow.arbiter != *authority {
+            if escrow.maker != *authority {
+                return Err(crate::errors::PinocchioError::InvalidOwner.into());
+            }
+            if !escrow.is_expired(Clock::get()?.unix_timestamp) {
+                return Err(crate::errors::PinocchioError::EscrowNotExpired.into());
+            }
+        }
+
+        // Verify the maker account matches the stored maker
+        if escrow.maker != *self.accounts.maker.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+
+        // Verify the mint matches the stored mint
+        if escrow.mint_a != *self.accounts.mint_a.key() {
+            return Err(crate::errors::PinocchioError::InvalidMint.into());
+        }
+
+        let escrow_key = create_program_address(
+            &[
+                b"arbiter",
+                self.accounts.maker.key(),
+                &escrow.seed.to_le_bytes(),
+                &escrow.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &escrow_key != self.accounts.escrow.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        let seed_binding = escrow.seed.to_le_bytes();
+        let bump_binding = escrow.bump;
+        let escrow_seeds = [
+            Seed::from(b"arbiter"),
+            Seed::from(self.accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+        let signers = [Signer::from(&escrow_seeds)];
+
+        TransferChecked {
+            from: self.accounts.vault,
+            mint: self.accounts.mint_a,
+            to: self.accounts.maker_ata_a,
+            authority: self.accounts.escrow,
+            amount: TokenAccount::get_amount(self.accounts.vault)?,
+            decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        CloseAccount {
+            account: self.accounts.vault,
+            destination: self.accounts.maker,
+            authority: self.accounts.escrow,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        drop(data);
+        ProgramAccount::close(self.accounts.escrow, self.accounts.maker)?;
+
+        Ok(())
+    }
+}
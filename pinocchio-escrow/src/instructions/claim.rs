@@ -0,0 +1,162 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenAccount, TokenProgram};
+use crate::state::VestingEscrow;
+
+pub struct ClaimAccounts<'a> {
+    pub beneficiary: &'a AccountInfo,
+    pub maker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub mint_a: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub beneficiary_ata_a: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for ClaimAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [beneficiary, maker, escrow, mint_a, vault, beneficiary_ata_a, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(beneficiary)?;
+        ProgramAccount::check_len(escrow, VestingEscrow::LEN)?;
+        MintAccount::check(mint_a)?;
+        TokenProgram::check(token_program, mint_a)?;
+        AssociatedTokenAccount::check(vault, escrow, mint_a, token_program)?;
+
+        Ok(Self {
+            beneficiary,
+            maker,
+            escrow,
+            mint_a,
+            vault,
+            beneficiary_ata_a,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct Claim<'a> {
+    pub accounts: ClaimAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for Claim<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = ClaimAccounts::try_from(accounts)?;
+        AssociatedTokenAccount::init_if_needed(
+            accounts.beneficiary_ata_a,
+            accounts.mint_a,
+            accounts.beneficiary,
+            accounts.beneficiary,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> Claim<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &14;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let mut data = self.accounts.escrow.try_borrow_mut_data()?;
+        let escrow = VestingEscrow::load_mut(data.as_mut())?;
+
+        // Only the beneficiary can claim what has vested
+        if escrow.beneficiary != *self.accounts.beneficiary.key() {
+            return Err(crate::errors::PinocchioError::InvalidTaker.into());
+        }
+
+        // Verify the maker account matches the stored maker
+        if escrow.maker != *self.accounts.maker.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+
+        // Verify the mint matches the stored mint
+        if escrow.mint_a != *self.accounts.mint_a.key() {
+            return Err(crate::errors::PinocchioError::InvalidMint.into());
+        }
+
+        let escrow_key = create_program_address(
+            &[
+                b"vesting",
+                self.accounts.maker.key(),
+                &escrow.seed.to_le_bytes(),
+                &escrow.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &escrow_key != self.accounts.escrow.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        let amount = escrow.claimable_at(Clock::get()?.unix_timestamp);
+        if amount == 0 {
+            return Err(crate::errors::PinocchioError::InvalidAmount.into());
+        }
+        escrow.set_withdrawn(escrow.withdrawn + amount);
+        let is_final = escrow.withdrawn == escrow.total;
+
+        // The final claim sweeps the vault so it can be closed, including anything sent to it directly
+        let amount = if is_final {
+            TokenAccount::get_amount(self.accounts.vault)?
+        } else {
+            amount
+        };
+
+        let seed_binding = escrow.seed.to_le_bytes();
+        let bump_binding = escrow.bump;
+        let escrow_seeds = [
+            Seed::from(b"vesting"),
+            Seed::from(self.accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+        let signers = [Signer::from(&escrow_seeds)];
+
+        TransferChecked {
+            from: self.accounts.vault,
+            mint: self.accounts.mint_a,
+            to: self.accounts.beneficiary_ata_a,
+            authority: self.accounts.escrow,
+            amount,
+            decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        // Once fully vested and claimed, rent goes back to the maker who paid it
+        if is_final {
+            CloseAccount {
+                account: self.accounts.vault,
+                destination: self.accounts.maker,
+                authority: self.accounts.escrow,
+                token_program: self.accounts.token_program.key(),
+            }
+            .invoke_signed(&signers)?;
+
+            drop(data);
+            ProgramAccount::close(self.accounts.escrow, self.accounts.maker)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenAccount, TokenProgram};
+use crate::hash::verify_hashlock;
+use crate::state::HtlcEscrow;
+
+pub struct ClaimHtlcAccounts<'a> {
+    pub claimer: &'a AccountInfo,
+    pub maker: &'a AccountInfo,
+    pub recipient: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub mint_a: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub recipient_ata_a: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for ClaimHtlcAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [claimer, maker, recipient, escrow, mint_a, vault, recipient_ata_a, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(claimer)?;
+        ProgramAccount::check_len(escrow, HtlcEscrow::LEN)?;
+        MintAccount::check(mint_a)?;
+        TokenProgram::check(token_program, mint_a)?;
+        AssociatedTokenAccount::check(vault, escrow, mint_a, token_program)?;
+
+        Ok(Self {
+            claimer,
+            maker,
+            recipient,
+            escrow,
+            mint_a,
+            vault,
+            recipient_ata_a,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct ClaimHtlcInstructionData<'a> {
+    pub preimage: &'a [u8],
+}
+
+impl<'a> TryFrom<&'a [u8]> for ClaimHtlcInstructionData<'a> {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self { preimage: data })
+    }
+}
+
+pub struct ClaimHtlc<'a> {
+    pub accounts: ClaimHtlcAccounts<'a>,
+    pub instruction_data: ClaimHtlcInstructionData<'a>,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for ClaimHtlc<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = ClaimHtlcAccounts::try_from(accounts)?;
+        let instruction_data = ClaimHtlcInstructionData::try_from(data)?;
+        AssociatedTokenAccount::init_if_needed(
+            accounts.recipient_ata_a,
+            accounts.mint_a,
+            accounts.claimer,
+            accounts.recipient,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> ClaimHtlc<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &16;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let data = self.accounts.escrow.try_borrow_data()?;
+        let escrow = HtlcEscrow::load(&data)?;
+
+        // Past the timelock the funds belong to the maker again
+        let clock = Clock::get()?;
+        if escrow.is_expired(clock.unix_timestamp) {
+            return Err(crate::errors::PinocchioError::EscrowExpired.into());
+        }
+
+        // Anyone holding the preimage may submit it, but only the recipient is paid
+        if !verify_hashlock(self.instruction_data.preimage, &escrow.sha256_hash, &escrow.keccak_hash) {
+            return Err(crate::errors::PinocchioError::InvalidPreimage.into());
+        }
+
+        // Verify the maker and recipient match the stored parties
+        if escrow.maker != *self.accounts.maker.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+        if escrow.recipient != *self.accounts.recipient.key() {
+            return Err(crate::errors::PinocchioError::InvalidTaker.into());
+        }
+
+        // Verify the mint matches the stored mint
+        if escrow.mint_a != *self.accounts.mint_a.key() {
+            return Err(crate::errors::PinocchioError::InvalidMint.into());
+        }
+
+        let escrow_key = create_program_address(
+            &[
+                b"htlc",
+                self.accounts.maker.key(),
+                &escrow.seed.to_le_bytes(),
+                &escrow.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &escrow_key != self.accounts.escrow.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        let seed_binding = escrow.seed.to_le_bytes();
+        let bump_binding = escrow.bump;
+        let escrow_seeds = [
+            Seed::from(b"htlc"),
+            Seed::from(self.accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+        let signers = [Signer::from(&escrow_seeds)];
+
+        TransferChecked {
+            from: self.accounts.vault,
+            mint: self.accounts.mint_a,
+            to: self.accounts.recipient_ata_a,
+            authority: self.accounts.escrow,
+            amount: TokenAccount::get_amount(self.accounts.vault)?,
+            decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        CloseAccount {
+            account: self.accounts.vault,
+            destination: self.accounts.maker,
+            authority: self.accounts.escrow,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        drop(data);
+        ProgramAccount::close(self.accounts.escrow, self.accounts.maker)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use pinocchio::{
+    account_info::AccountInfo, instruction::Seed, program_error::ProgramError,
+    pubkey::{create_program_address, find_program_address},
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use std::mem::size_of;
+use pinocchio_token_2022::instructions::TransferChecked;
+
+use crate::{AssociatedTokenAccount, ConfigAccount, MintAccount, ProgramAccount, SignerAccount, TokenProgram};
+use crate::state::{Escrow, Offer};
+
+pub struct CounterOfferAccounts<'a> {
+    pub taker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub offer: &'a AccountInfo,
+    pub mint_b: &'a AccountInfo,
+    pub taker_ata_b: &'a AccountInfo,
+    pub offer_vault: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for CounterOfferAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [taker, escrow, offer, mint_b, taker_ata_b, offer_vault, config, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(taker)?;
+        ProgramAccount::check(escrow)?;
+        MintAccount::check(mint_b)?;
+        TokenProgram::check(token_program, mint_b)?;
+        ConfigAccount::check(config)?;
+        AssociatedTokenAccount::check(taker_ata_b, taker, mint_b, token_program)?;
+
+        Ok(Self {
+            taker,
+            escrow,
+            offer,
+            mint_b,
+            taker_ata_b,
+            offer_vault,
+            config,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct CounterOfferInstructionData {
+    /// Mint B the taker proposes to pay for the whole remaining deposit
+    pub receive: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for CounterOfferInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let receive = u64::from_le_bytes(data[0..8].try_into().unwrap());
+
+        if receive == 0 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self { receive })
+    }
+}
+
+pub struct CounterOffer<'a> {
+    pub accounts: CounterOfferAccounts<'a>,
+    pub instruction_data: CounterOfferInstructionData,
+    pub bump: u8,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for CounterOffer<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = CounterOfferAccounts::try_from(accounts)?;
+        let instruction_data = CounterOfferInstructionData::try_from(data)?;
+
+        // The offer is keyed by the escrow address, so it must be the escrow's real PDA
+        let escrow_data = accounts.escrow.try_borrow_data()?;
+        let escrow = Escrow::load(&escrow_data)?;
+        let escrow_key = create_program_address(
+            &[
+                b"escrow",
+                &escrow.maker,
+                &escrow.seed.to_le_bytes(),
+                &escrow.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &escrow_key != accounts.escrow.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+        drop(escrow_data);
+
+        // One open counter-offer per taker and escrow; revising it means withdrawing first
+        let (_, bump) = find_program_address(
+            &[b"offer", accounts.escrow.key(), accounts.taker.key()],
+            &crate::ID,
+        );
+
+        let bump_binding = [bump];
+        let offer_seeds = [
+            Seed::from(b"offer"),
+            Seed::from(accounts.escrow.key().as_ref()),
+            Seed::from(accounts.taker.key().as_ref()),
+            Seed::from(&bump_binding),
+        ];
+
+        ProgramAccount::init::<Offer>(accounts.taker, accounts.offer, &offer_seeds, Offer::LEN)?;
+
+        AssociatedTokenAccount::init(
+            accounts.offer_vault,
+            accounts.mint_b,
+            accounts.taker,
+            accounts.offer,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+            bump,
+        })
+    }
+}
+
+impl<'a> CounterOffer<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &25;
+    pub const CONFIG_INDEX: usize = 6;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let data = self.accounts.escrow.try_borrow_data()?;
+        let escrow = Escrow::load(&data)?;
+
+        // An expired escrow can no longer be settled, so there is nothing to counter
+        let clock = Clock::get()?;
+        if escrow.is_expired(clock.unix_timestamp) {
+            return Err(crate::errors::PinocchioError::EscrowExpired.into());
+        }
+
+        // Counters are paid in the escrow's own mint B; collection bids have none to match
+        if escrow.mint_b != *self.accounts.mint_b.key() {
+            return Err(crate::errors::PinocchioError::InvalidMint.into());
+        }
+        let deposit = escrow.deposit;
+        drop(data);
+
+        // Mint B may withhold a transfer fee, so the offer only counts what lands in its vault
+        let receive = MintAccount::amount_after_fee(self.accounts.mint_b, clock.epoch, self.instruction_data.receive)?;
+        if receive == 0 {
+            return Err(crate::errors::PinocchioError::InvalidAmount.into());
+        }
+
+        TransferChecked {
+            from: self.accounts.taker_ata_b,
+            mint: self.accounts.mint_b,
+            to: self.accounts.offer_vault,
+            authority: self.accounts.taker,
+            amount: self.instruction_data.receive,
+            decimals: MintAccount::get_decimals(self.accounts.mint_b)?,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke()?;
+
+        let mut data = self.accounts.offer.try_borrow_mut_data()?;
+        let offer = Offer::load_mut(data.as_mut())?;
+        offer.set_inner(
+            *self.accounts.escrow.key(),
+            *self.accounts.taker.key(),
+            receive,
+            deposit,
+            [self.bump],
+        );
+
+        Ok(())
+    }
+}
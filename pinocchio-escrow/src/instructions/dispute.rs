@@ -0,0 +1,72 @@
+use pinocchio::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::create_program_address,
+    ProgramResult,
+};
+
+use crate::{ProgramAccount, SignerAccount};
+use crate::state::ArbiterEscrow;
+
+pub struct DisputeAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for DisputeAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, escrow] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(authority)?;
+        ProgramAccount::check_len(escrow, ArbiterEscrow::LEN)?;
+
+        Ok(Self { authority, escrow })
+    }
+}
+
+pub struct Dispute<'a> {
+    pub accounts: DisputeAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for Dispute<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = DisputeAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> Dispute<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &11;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let mut data = self.accounts.escrow.try_borrow_mut_data()?;
+        let escrow = ArbiterEscrow::load_mut(data.as_mut())?;
+
+        // Either party can freeze expiry; only the arbiter can settle from here
+        let authority = self.accounts.authority.key();
+        if escrow.maker != *authority && escrow.beneficiary != *authority {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+
+        let escrow_key = create_program_address(
+            &[
+                b"arbiter",
+                &escrow.maker,
+                &escrow.seed.to_le_bytes(),
+                &escrow.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &escrow_key != self.accounts.escrow.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        escrow.set_disputed(true);
+
+        Ok(())
+    }
+}
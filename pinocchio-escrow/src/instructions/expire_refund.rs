@@ -0,0 +1,170 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenAccount, TokenProgram};
+use crate::state::Escrow;
+
+pub struct ExpireRefundAccounts<'a> {
+    pub cranker: &'a AccountInfo,
+    pub maker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub mint_a: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub maker_ata_a: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for ExpireRefundAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [cranker, maker, escrow, mint_a, vault, maker_ata_a, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(cranker)?;
+        ProgramAccount::check(escrow)?;
+        MintAccount::check(mint_a)?;
+        TokenProgram::check(token_program, mint_a)?;
+        AssociatedTokenAccount::check(vault, escrow, mint_a, token_program)?;
+
+        Ok(Self {
+            cranker,
+            maker,
+            escrow,
+            mint_a,
+            vault,
+            maker_ata_a,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct ExpireRefund<'a> {
+    pub accounts: ExpireRefundAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for ExpireRefund<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = ExpireRefundAccounts::try_from(accounts)?;
+        if !MintAccount::is_native(accounts.mint_a) {
+            AssociatedTokenAccount::init_if_needed(
+                accounts.maker_ata_a,
+                accounts.mint_a,
+                accounts.cranker,
+                accounts.maker,
+                accounts.system_program,
+                accounts.token_program,
+            )?;
+        }
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> ExpireRefund<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &3;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let data = self.accounts.escrow.try_borrow_data()?;
+        let escrow = Escrow::load(&data)?;
+
+        // Anyone may crank, but only once the escrow has expired
+        let clock = Clock::get()?;
+        if !escrow.is_expired(clock.unix_timestamp) {
+            return Err(crate::errors::PinocchioError::EscrowNotExpired.into());
+        }
+
+        // Verify the maker account matches the stored maker
+        if escrow.maker != *self.accounts.maker.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+
+        // Verify the mint matches the stored mint
+        if escrow.mint_a != *self.accounts.mint_a.key() {
+            return Err(crate::errors::PinocchioError::InvalidMint.into());
+        }
+
+        let escrow_key = create_program_address(
+            &[
+                b"escrow",
+                self.accounts.maker.key(),
+                &escrow.seed.to_le_bytes(),
+                &escrow.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &escrow_key != self.accounts.escrow.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        let seed_binding = escrow.seed.to_le_bytes();
+        let bump_binding = escrow.bump;
+        let escrow_seeds = [
+            Seed::from(b"escrow"),
+            Seed::from(self.accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+        let signers = [Signer::from(&escrow_seeds)];
+
+        let amount = TokenAccount::get_amount(self.accounts.vault)?;
+
+        // Validate amount is greater than 0
+        if amount == 0 {
+            return Err(crate::errors::PinocchioError::InvalidAmount.into());
+        }
+
+        // A native vault can be closed with a balance, which unwraps it straight to the maker
+        if !MintAccount::is_native(self.accounts.mint_a) {
+            TransferChecked {
+                from: self.accounts.vault,
+                mint: self.accounts.mint_a,
+                to: self.accounts.maker_ata_a,
+                authority: self.accounts.escrow,
+                amount,
+                decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+                token_program: self.accounts.token_program.key(),
+            }
+            .invoke_signed(&signers)?;
+        }
+
+        CloseAccount {
+            account: self.accounts.vault,
+            destination: self.accounts.maker,
+            authority: self.accounts.escrow,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        let bounty = escrow.bounty;
+
+        drop(data);
+
+        // Pay the cranker out of the lamports the maker parked at make time
+        if bounty > 0 {
+            let mut escrow_lamports = self.accounts.escrow.try_borrow_mut_lamports()?;
+            *escrow_lamports = escrow_lamports
+                .checked_sub(bounty)
+                .ok_or(ProgramError::InsufficientFunds)?;
+            *self.accounts.cranker.try_borrow_mut_lamports()? += bounty;
+        }
+
+        ProgramAccount::close(self.accounts.escrow, self.accounts.maker)?;
+
+        Ok(())
+    }
+}
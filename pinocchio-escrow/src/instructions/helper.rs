@@ -2,13 +2,15 @@ use pinocchio::{
     account_info::AccountInfo,
     instruction::{Seed, Signer},
     program_error::ProgramError,
-    pubkey::find_program_address,
+    pubkey::{create_program_address, find_program_address, Pubkey},
     sysvars::{rent::Rent, Sysvar},
     ProgramResult,
 };
+use std::mem::size_of;
 use pinocchio_associated_token_account::instructions::Create;
-use pinocchio_system::instructions::CreateAccount;
+use pinocchio_system::instructions::{Allocate, Assign, CreateAccount, Transfer as SystemTransfer};
 use pinocchio_token::instructions::InitializeMint2;
+use pinocchio_token_2022::instructions::{CloseAccount, InitializeAccount3, TransferChecked};
 
 use crate::errors::PinocchioError;
 
@@ -39,20 +41,204 @@ impl SystemAccount {
     }
 }
 
+/// The wrapped SOL mint, "So11111111111111111111111111111111111111112"
+pub const NATIVE_MINT: Pubkey = [
+    0x06, 0x9b, 0x88, 0x57, 0xfe, 0xab, 0x81, 0x84,
+    0xfb, 0x68, 0x7f, 0x63, 0x46, 0x18, 0xc0, 0x35,
+    0xda, 0xc4, 0x39, 0xdc, 0x1a, 0xeb, 0x3b, 0x55,
+    0x98, 0xa0, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x01,
+];
+
+/// Token-2022 accounts carrying extensions store their account type right after
+/// the base token account layout, mints included (they are padded up to it).
+const ACCOUNT_TYPE_OFFSET: usize = pinocchio_token::state::TokenAccount::LEN;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+/// Extensions follow the account type byte as type/length/value entries.
+const EXTENSIONS_OFFSET: usize = ACCOUNT_TYPE_OFFSET + 1;
+const EXTENSION_TYPE_UNINITIALIZED: u16 = 0;
+const EXTENSION_TYPE_TRANSFER_FEE_CONFIG: u16 = 1;
+const TRANSFER_FEE_CONFIG_LEN: usize = 108;
+const MAX_FEE_BASIS_POINTS: u128 = 10_000;
+
+pub struct TokenProgram;
+
+impl TokenProgram {
+    /// Accepts either the legacy token program or Token-2022, as long as it owns `mint`.
+    pub fn check(account: &AccountInfo, mint: &AccountInfo) -> Result<(), ProgramError> {
+        if account.key().ne(&pinocchio_token::ID) && account.key().ne(&pinocchio_token_2022::ID) {
+            return Err(PinocchioError::InvalidAddress.into());
+        }
+
+        if !mint.is_owned_by(account.key()) {
+            return Err(PinocchioError::InvalidOwner.into());
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn owns(account: &AccountInfo) -> bool {
+        account.is_owned_by(&pinocchio_token::ID) || account.is_owned_by(&pinocchio_token_2022::ID)
+    }
+
+    #[inline(always)]
+    fn has_extensions(account: &AccountInfo, account_type: u8) -> Result<bool, ProgramError> {
+        if !account.is_owned_by(&pinocchio_token_2022::ID) || account.data_len() <= ACCOUNT_TYPE_OFFSET {
+            return Ok(false);
+        }
+
+        Ok(account.try_borrow_data()?[ACCOUNT_TYPE_OFFSET] == account_type)
+    }
+}
+
 pub struct MintAccount;
 
 impl MintAccount {
     pub fn check(account: &AccountInfo) -> Result<(), ProgramError> {
-        if !account.is_owned_by(&pinocchio_token::ID) {
+        if !TokenProgram::owns(account) {
             return Err(PinocchioError::InvalidOwner.into());
         }
 
-        if account.data_len() != pinocchio_token::state::Mint::LEN {
+        if account.data_len() != pinocchio_token::state::Mint::LEN
+            && !TokenProgram::has_extensions(account, ACCOUNT_TYPE_MINT)?
+        {
             return Err(PinocchioError::InvalidAccountData.into());
         }
 
         Ok(())
     }
+
+    /// Native SOL legs are wrapped and unwrapped by the program instead of the user.
+    #[inline(always)]
+    pub fn is_native(account: &AccountInfo) -> bool {
+        account.key().eq(&NATIVE_MINT)
+    }
+
+    /// Reads the decimals of a mint owned by either token program.
+    pub fn get_decimals(account: &AccountInfo) -> Result<u8, ProgramError> {
+        Self::check(account)?;
+
+        let data = account.try_borrow_data()?;
+        let mint = unsafe { pinocchio_token::state::Mint::from_bytes_unchecked(&data) };
+
+        Ok(mint.decimals())
+    }
+
+    /// Reads the supply of a mint owned by either token program.
+    pub fn get_supply(account: &AccountInfo) -> Result<u64, ProgramError> {
+        Self::check(account)?;
+
+        let data = account.try_borrow_data()?;
+        let mint = unsafe { pinocchio_token::state::Mint::from_bytes_unchecked(&data) };
+
+        Ok(mint.supply())
+    }
+
+    /// A decimals-0 mint with a supply of one is an NFT; its metadata is checked separately.
+    pub fn is_nft(account: &AccountInfo) -> Result<bool, ProgramError> {
+        Ok(Self::get_decimals(account)? == 0 && Self::get_supply(account)? == 1)
+    }
+
+    /// Amount that lands in the destination when `amount` of this mint is transferred.
+    pub fn amount_after_fee(account: &AccountInfo, epoch: u64, amount: u64) -> Result<u64, ProgramError> {
+        match TransferFee::get(account, epoch)? {
+            Some(transfer_fee) => Ok(amount - transfer_fee.calculate_fee(amount)),
+            None => Ok(amount),
+        }
+    }
+
+    /// Amount to transfer so that exactly `amount` of this mint lands in the destination.
+    pub fn amount_before_fee(account: &AccountInfo, epoch: u64, amount: u64) -> Result<u64, ProgramError> {
+        match TransferFee::get(account, epoch)? {
+            Some(transfer_fee) => transfer_fee
+                .calculate_pre_fee_amount(amount)
+                .ok_or(PinocchioError::InvalidAmount.into()),
+            None => Ok(amount),
+        }
+    }
+}
+
+/// The Token-2022 transfer fee in effect for a given epoch.
+pub struct TransferFee {
+    pub maximum_fee: u64,
+    pub transfer_fee_basis_points: u16,
+}
+
+impl TransferFee {
+    /// Reads the `TransferFeeConfig` extension of `mint`, if it has one.
+    pub fn get(mint: &AccountInfo, epoch: u64) -> Result<Option<Self>, ProgramError> {
+        if !TokenProgram::has_extensions(mint, ACCOUNT_TYPE_MINT)? {
+            return Ok(None);
+        }
+
+        let data = mint.try_borrow_data()?;
+        let mut offset = EXTENSIONS_OFFSET;
+
+        while offset + 4 <= data.len() {
+            let extension_type = u16::from_le_bytes([data[offset], data[offset + 1]]);
+            let length = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+            offset += 4;
+
+            if extension_type == EXTENSION_TYPE_UNINITIALIZED {
+                break;
+            }
+
+            if extension_type == EXTENSION_TYPE_TRANSFER_FEE_CONFIG {
+                if length != TRANSFER_FEE_CONFIG_LEN || offset + length > data.len() {
+                    return Err(PinocchioError::InvalidAccountData.into());
+                }
+
+                // Skip both authorities and the withheld amount to reach the older and newer fees
+                let older = &data[offset + 72..offset + 90];
+                let newer = &data[offset + 90..offset + 108];
+                let newer_epoch = u64::from_le_bytes(newer[0..8].try_into().unwrap());
+                let fee = if epoch >= newer_epoch { newer } else { older };
+
+                return Ok(Some(Self {
+                    maximum_fee: u64::from_le_bytes(fee[8..16].try_into().unwrap()),
+                    transfer_fee_basis_points: u16::from_le_bytes(fee[16..18].try_into().unwrap()),
+                }));
+            }
+
+            offset += length;
+        }
+
+        Ok(None)
+    }
+
+    /// Fee withheld from a transfer of `pre_fee_amount`, rounded up and capped at the maximum fee.
+    pub fn calculate_fee(&self, pre_fee_amount: u64) -> u64 {
+        let basis_points = self.transfer_fee_basis_points as u128;
+        if basis_points == 0 || pre_fee_amount == 0 {
+            return 0;
+        }
+
+        let raw_fee = (pre_fee_amount as u128 * basis_points).div_ceil(MAX_FEE_BASIS_POINTS) as u64;
+        raw_fee.min(self.maximum_fee)
+    }
+
+    /// Smallest transfer amount that nets `post_fee_amount` after the fee, as computed by Token-2022.
+    pub fn calculate_pre_fee_amount(&self, post_fee_amount: u64) -> Option<u64> {
+        let basis_points = self.transfer_fee_basis_points as u128;
+        match (basis_points, post_fee_amount) {
+            (0, _) => Some(post_fee_amount),
+            (_, 0) => Some(0),
+            (MAX_FEE_BASIS_POINTS, _) => post_fee_amount.checked_add(self.maximum_fee),
+            _ => {
+                let numerator = post_fee_amount as u128 * MAX_FEE_BASIS_POINTS;
+                let denominator = MAX_FEE_BASIS_POINTS.checked_sub(basis_points)?;
+                let raw_pre_fee_amount = numerator.div_ceil(denominator);
+
+                if raw_pre_fee_amount - post_fee_amount as u128 >= self.maximum_fee as u128 {
+                    post_fee_amount.checked_add(self.maximum_fee)
+                } else {
+                    u64::try_from(raw_pre_fee_amount).ok()
+                }
+            }
+        }
+    }
 }
 
 pub trait MintInit {
@@ -118,19 +304,161 @@ pub struct TokenAccount;
 
 impl TokenAccount {
     pub fn check(account: &AccountInfo) -> Result<(), ProgramError> {
-        if !account.is_owned_by(&pinocchio_token::ID) {
+        if !TokenProgram::owns(account) {
             return Err(PinocchioError::InvalidOwner.into());
         }
 
         if account
             .data_len()
             .ne(&pinocchio_token::state::TokenAccount::LEN)
+            && !TokenProgram::has_extensions(account, ACCOUNT_TYPE_ACCOUNT)?
         {
             return Err(PinocchioError::InvalidAccountData.into());
         }
 
         Ok(())
     }
+
+    /// Reads the token balance of an account owned by either token program.
+    pub fn get_amount(account: &AccountInfo) -> Result<u64, ProgramError> {
+        Self::check(account)?;
+
+        let data = account.try_borrow_data()?;
+        let token_account = unsafe { pinocchio_token::state::TokenAccount::from_bytes_unchecked(&data) };
+
+        Ok(token_account.amount())
+    }
+
+    /// Reads the mint of an account owned by either token program.
+    pub fn get_mint(account: &AccountInfo) -> Result<Pubkey, ProgramError> {
+        Self::check(account)?;
+
+        let data = account.try_borrow_data()?;
+        let token_account = unsafe { pinocchio_token::state::TokenAccount::from_bytes_unchecked(&data) };
+
+        Ok(*token_account.mint())
+    }
+}
+
+/// A temporary wSOL account at `["unwrap", owner]`, used to hand out native SOL as lamports
+/// without touching any wSOL the owner already holds.
+pub struct NativeAccount;
+
+impl NativeAccount {
+    /// Creates and initializes the temporary account, with `owner` paying its rent.
+    pub fn init(
+        account: &AccountInfo,
+        mint: &AccountInfo,
+        owner: &AccountInfo,
+        token_program: &AccountInfo,
+    ) -> ProgramResult {
+        let (account_key, bump) = find_program_address(&[b"unwrap", owner.key()], &crate::ID);
+        if &account_key != account.key() {
+            return Err(PinocchioError::InvalidAddress.into());
+        }
+
+        let bump_binding = [bump];
+        let seeds = [
+            Seed::from(b"unwrap"),
+            Seed::from(owner.key().as_ref()),
+            Seed::from(&bump_binding),
+        ];
+        let signers = [Signer::from(&seeds)];
+
+        let space = pinocchio_token::state::TokenAccount::LEN;
+        let lamports = Rent::get()?.minimum_balance(space);
+        let balance = account.lamports();
+        if balance == 0 {
+            CreateAccount {
+                from: owner,
+                to: account,
+                lamports,
+                space: space as u64,
+                owner: token_program.key(),
+            }
+            .invoke_signed(&signers)?;
+        } else {
+            // Lamports sent to the address ahead of time would make CreateAccount fail
+            if balance < lamports {
+                SystemTransfer {
+                    from: owner,
+                    to: account,
+                    lamports: lamports - balance,
+                }
+                .invoke()?;
+            }
+            Allocate {
+                account,
+                space: space as u64,
+            }
+            .invoke_signed(&signers)?;
+            Assign {
+                account,
+                owner: token_program.key(),
+            }
+            .invoke_signed(&signers)?;
+        }
+
+        InitializeAccount3 {
+            account,
+            mint,
+            owner: owner.key(),
+            token_program: token_program.key(),
+        }
+        .invoke()
+    }
+
+    /// Closes the temporary account, paying its balance and rent back to `owner` as lamports.
+    pub fn close(account: &AccountInfo, owner: &AccountInfo, token_program: &AccountInfo) -> ProgramResult {
+        CloseAccount {
+            account,
+            destination: owner,
+            authority: owner,
+            token_program: token_program.key(),
+        }
+        .invoke()
+    }
+}
+
+/// The taker's side of a mint B payment, in lamports for native SOL or from `taker_ata_b` otherwise.
+pub struct TakerPayment<'a> {
+    pub taker: &'a AccountInfo,
+    pub taker_ata_b: &'a AccountInfo,
+    pub mint_b: &'a AccountInfo,
+    pub token_program_b: &'a AccountInfo,
+}
+
+impl TakerPayment<'_> {
+    /// Mint B the taker sends so the recipient gets exactly `amount`, grossed up for any transfer fee.
+    pub fn gross(&self, amount: u64, epoch: u64) -> Result<u64, ProgramError> {
+        if MintAccount::is_native(self.mint_b) {
+            return Ok(amount);
+        }
+        MintAccount::amount_before_fee(self.mint_b, epoch, amount)
+    }
+
+    /// Pays `amount` of mint B from the taker to `wallet`, or to its `ata` for SPL mints.
+    pub fn pay(&self, wallet: &AccountInfo, ata: &AccountInfo, amount: u64, epoch: u64) -> ProgramResult {
+        if MintAccount::is_native(self.mint_b) {
+            SystemTransfer {
+                from: self.taker,
+                to: wallet,
+                lamports: amount,
+            }
+            .invoke()
+        } else {
+            TransferChecked {
+                from: self.taker_ata_b,
+                mint: self.mint_b,
+                to: ata,
+                authority: self.taker,
+                amount: self.gross(amount, epoch)?,
+                decimals: MintAccount::get_decimals(self.mint_b)?,
+                token_program: self.token_program_b.key(),
+            }
+            .invoke()
+        }
+    }
 }
 
 pub struct AssociatedTokenAccount;
@@ -144,6 +472,10 @@ impl AssociatedTokenAccount {
     ) -> Result<(), ProgramError> {
         TokenAccount::check(account)?;
 
+        if !account.is_owned_by(token_program.key()) {
+            return Err(PinocchioError::InvalidOwner.into());
+        }
+
         if find_program_address(
             &[authority.key(), token_program.key(), mint.key()],
             &pinocchio_associated_token_account::ID,
@@ -216,11 +548,16 @@ pub struct ProgramAccount;
 
 impl ProgramAccount {
     pub fn check(account: &AccountInfo) -> Result<(), ProgramError> {
+        Self::check_len(account, crate::state::Escrow::LEN)
+    }
+
+    /// Checks a program-owned account of any of the program's account types.
+    pub fn check_len(account: &AccountInfo, len: usize) -> Result<(), ProgramError> {
         if !account.is_owned_by(&crate::ID) {
             return Err(PinocchioError::InvalidOwner.into());
         }
 
-        if account.data_len().ne(&crate::state::Escrow::LEN) {
+        if account.data_len().ne(&len) {
             return Err(PinocchioError::InvalidAccountData.into());
         }
 
@@ -228,6 +565,71 @@ impl ProgramAccount {
     }
 }
 
+pub struct ConfigAccount;
+
+impl ConfigAccount {
+    /// The config is a singleton PDA, so its address is checked against its stored bump.
+    pub fn check(account: &AccountInfo) -> Result<(), ProgramError> {
+        if !account.is_owned_by(&crate::ID) {
+            return Err(PinocchioError::InvalidOwner.into());
+        }
+
+        let data = account.try_borrow_data()?;
+        let config = crate::state::Config::load(&data)?;
+        let config_key = create_program_address(&[b"config", &config.bump], &crate::ID)?;
+        if &config_key != account.key() {
+            return Err(PinocchioError::InvalidAddress.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// The upgradeable BPF loader, "BPFLoaderUpgradeab1e11111111111111111111111"
+pub const BPF_LOADER_UPGRADEABLE_ID: Pubkey = [
+    0x02, 0xa8, 0xf6, 0x91, 0x4e, 0x88, 0xa1, 0xb0,
+    0xe2, 0x10, 0x15, 0x3e, 0xf7, 0x63, 0xae, 0x2b,
+    0x00, 0xc2, 0xb9, 0x3d, 0x16, 0xc1, 0x24, 0xd2,
+    0xc0, 0x53, 0x7a, 0x10, 0x04, 0x80, 0x00, 0x00,
+];
+
+/// `UpgradeableLoaderState::ProgramData` tag, followed by the deploy slot and the
+/// optional upgrade authority.
+const PROGRAM_DATA_TAG: u32 = 3;
+const PROGRAM_DATA_AUTHORITY_OFFSET: usize = size_of::<u32>() + size_of::<u64>();
+
+pub struct ProgramDataAccount;
+
+impl ProgramDataAccount {
+    /// Only the upgrade authority may set up the config, so nobody can front-run the deployer.
+    pub fn check_authority(account: &AccountInfo, authority: &AccountInfo) -> Result<(), ProgramError> {
+        if !account.is_owned_by(&BPF_LOADER_UPGRADEABLE_ID) {
+            return Err(PinocchioError::InvalidOwner.into());
+        }
+
+        let (program_data_key, _) = find_program_address(&[crate::ID.as_ref()], &BPF_LOADER_UPGRADEABLE_ID);
+        if &program_data_key != account.key() {
+            return Err(PinocchioError::InvalidAddress.into());
+        }
+
+        let data = account.try_borrow_data()?;
+        let authority_start = PROGRAM_DATA_AUTHORITY_OFFSET + 1;
+        if data.len() < authority_start + size_of::<Pubkey>()
+            || u32::from_le_bytes(data[0..4].try_into().unwrap()) != PROGRAM_DATA_TAG
+        {
+            return Err(PinocchioError::InvalidAccountData.into());
+        }
+
+        // An immutable program has no upgrade authority, and so no one to set up its config
+        let upgrade_authority = &data[authority_start..authority_start + size_of::<Pubkey>()];
+        if data[PROGRAM_DATA_AUTHORITY_OFFSET] != 1 || upgrade_authority != authority.key().as_ref() {
+            return Err(PinocchioError::InvalidOwner.into());
+        }
+
+        Ok(())
+    }
+}
+
 pub trait ProgramAccountInit {
     fn init<'a, T: Sized>(
         payer: &AccountInfo,
@@ -0,0 +1,120 @@
+use pinocchio::{
+    account_info::AccountInfo, instruction::Seed, program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    ProgramResult,
+};
+use std::mem::size_of;
+
+use crate::{ProgramAccount, ProgramDataAccount, SignerAccount};
+use crate::state::{Config, MAX_BASIS_POINTS};
+
+pub struct InitializeConfigAccounts<'a> {
+    pub admin: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    /// The program's ProgramData account, whose upgrade authority must be the admin
+    pub program_data: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for InitializeConfigAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [admin, config, program_data, system_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(admin)?;
+        ProgramDataAccount::check_authority(program_data, admin)?;
+
+        Ok(Self {
+            admin,
+            config,
+            program_data,
+            system_program,
+        })
+    }
+}
+
+pub struct InitializeConfigInstructionData {
+    pub fee_recipient: Pubkey,
+    pub fee_bps: u16,
+    pub max_referral_bps: u16,
+}
+
+impl<'a> TryFrom<&'a [u8]> for InitializeConfigInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<Pubkey>() + size_of::<u16>() * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let fee_recipient: Pubkey = data[0..32].try_into().unwrap();
+        let fee_bps = u16::from_le_bytes(data[32..34].try_into().unwrap());
+        let max_referral_bps = u16::from_le_bytes(data[34..36].try_into().unwrap());
+
+        // The referral is a share of the protocol fee, which is a share of the mint B leg
+        if fee_bps as u64 > MAX_BASIS_POINTS || max_referral_bps as u64 > MAX_BASIS_POINTS {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            fee_recipient,
+            fee_bps,
+            max_referral_bps,
+        })
+    }
+}
+
+pub struct InitializeConfig<'a> {
+    pub accounts: InitializeConfigAccounts<'a>,
+    pub instruction_data: InitializeConfigInstructionData,
+    pub bump: u8,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for InitializeConfig<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = InitializeConfigAccounts::try_from(accounts)?;
+        let instruction_data = InitializeConfigInstructionData::try_from(data)?;
+
+        let (_, bump) = find_program_address(&[b"config"], &crate::ID);
+
+        let bump_binding = [bump];
+        let config_seeds = [Seed::from(b"config"), Seed::from(&bump_binding)];
+
+        // Creating the PDA fails if it already exists, so the config can only be set up once
+        ProgramAccount::init::<Config>(
+            accounts.admin,
+            accounts.config,
+            &config_seeds,
+            Config::LEN,
+        )?;
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+            bump,
+        })
+    }
+}
+
+impl<'a> InitializeConfig<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &5;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let mut data = self.accounts.config.try_borrow_mut_data()?;
+        let config = Config::load_mut(data.as_mut())?;
+
+        config.set_inner(
+            *self.accounts.admin.key(),
+            self.instruction_data.fee_recipient,
+            self.instruction_data.fee_bps,
+            self.instruction_data.max_referral_bps,
+            [self.bump],
+        );
+
+        Ok(())
+    }
+}
@@ -1,12 +1,15 @@
 use pinocchio::{
     account_info::AccountInfo, instruction::Seed, program_error::ProgramError,
-    pubkey::find_program_address, ProgramResult,
+    pubkey::{find_program_address, Pubkey},
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
 };
 use std::mem::size_of;
-use pinocchio_token::instructions::Transfer;
+use pinocchio_system::instructions::Transfer as SystemTransfer;
+use pinocchio_token_2022::instructions::{SyncNative, TransferChecked};
 
-use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount};
-use crate::state::Escrow;
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenProgram};
+use crate::state::{Escrow, MAX_BASIS_POINTS};
 
 pub struct MakeAccounts<'a> {
     pub maker: &'a AccountInfo,
@@ -15,6 +18,7 @@ pub struct MakeAccounts<'a> {
     pub mint_b: &'a AccountInfo,
     pub maker_ata_a: &'a AccountInfo,
     pub vault: &'a AccountInfo,
+    pub config: &'a AccountInfo,
     pub system_program: &'a AccountInfo,
     pub token_program: &'a AccountInfo,
 }
@@ -23,7 +27,7 @@ impl<'a> TryFrom<&'a [AccountInfo]> for MakeAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [maker, escrow, mint_a, mint_b, maker_ata_a, vault, system_program, token_program, _] =
+        let [maker, escrow, mint_a, mint_b, maker_ata_a, vault, config, system_program, token_program, _] =
             accounts
         else {
             return Err(ProgramError::NotEnoughAccountKeys);
@@ -31,7 +35,11 @@ impl<'a> TryFrom<&'a [AccountInfo]> for MakeAccounts<'a> {
         SignerAccount::check(maker)?;
         MintAccount::check(mint_a)?;
         MintAccount::check(mint_b)?;
-        AssociatedTokenAccount::check(maker_ata_a, maker, mint_a, token_program)?;
+        TokenProgram::check(token_program, mint_a)?;
+        // Native SOL is wrapped straight from the maker's lamports, so no ATA is needed
+        if !MintAccount::is_native(mint_a) {
+            AssociatedTokenAccount::check(maker_ata_a, maker, mint_a, token_program)?;
+        }
 
         Ok(Self {
             maker,
@@ -40,6 +48,7 @@ impl<'a> TryFrom<&'a [AccountInfo]> for MakeAccounts<'a> {
             mint_b,
             maker_ata_a,
             vault,
+            config,
             system_program,
             token_program,
         })
@@ -51,13 +60,39 @@ pub struct MakeInstructionData {
     pub receive: u64,
     pub amount: u64,
     pub expiry: i64,
+    pub bounty: u64,
+    pub taker: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub start_receive: u64,
+    pub auction_start: i64,
+    pub auction_end: i64,
+    /// Price feed quoting mint A in mint B, with `receive` as the floor; zeroed for a fixed price
+    pub price_feed: Pubkey,
+    /// Pyth feed id the price feed account must carry
+    pub feed_id: [u8; 32],
+    /// Oldest price accepted at take time, in seconds
+    pub max_staleness: u64,
+    /// Widest confidence interval accepted, in basis points of the price
+    pub max_conf_bps: u16,
+    /// Offset from the oracle price in basis points; negative sells below market
+    pub spread_bps: i16,
+    /// Collection whose verified NFTs are accepted as mint B, passed as the mint B
+    /// account; zeroed for a fixed mint B
+    pub collection: Pubkey,
 }
 
 impl<'a> TryFrom<&'a [u8]> for MakeInstructionData {
     type Error = ProgramError;
 
     fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
-        if data.len() != size_of::<u64>() * 3 + size_of::<i64>() {
+        if data.len()
+            != size_of::<u64>() * 6
+                + size_of::<i64>() * 3
+                + size_of::<Pubkey>() * 3
+                + size_of::<[u8; 32]>() * 2
+                + size_of::<u16>()
+                + size_of::<i16>()
+        {
             return Err(ProgramError::InvalidInstructionData);
         }
 
@@ -65,8 +100,40 @@ impl<'a> TryFrom<&'a [u8]> for MakeInstructionData {
         let receive = u64::from_le_bytes(data[8..16].try_into().unwrap());
         let amount = u64::from_le_bytes(data[16..24].try_into().unwrap());
         let expiry = i64::from_le_bytes(data[24..32].try_into().unwrap());
+        let bounty = u64::from_le_bytes(data[32..40].try_into().unwrap());
+        let taker: Pubkey = data[40..72].try_into().unwrap();
+        let merkle_root: [u8; 32] = data[72..104].try_into().unwrap();
+        let start_receive = u64::from_le_bytes(data[104..112].try_into().unwrap());
+        let auction_start = i64::from_le_bytes(data[112..120].try_into().unwrap());
+        let auction_end = i64::from_le_bytes(data[120..128].try_into().unwrap());
+        let price_feed: Pubkey = data[128..160].try_into().unwrap();
+        let feed_id: [u8; 32] = data[160..192].try_into().unwrap();
+        let max_staleness = u64::from_le_bytes(data[192..200].try_into().unwrap());
+        let max_conf_bps = u16::from_le_bytes(data[200..202].try_into().unwrap());
+        let spread_bps = i16::from_le_bytes(data[202..204].try_into().unwrap());
+        let collection: Pubkey = data[204..236].try_into().unwrap();
+
+        if amount == 0 || receive == 0 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        // An auction must open above its floor price and run for a non-empty window
+        if start_receive != 0 && (start_receive <= receive || auction_start >= auction_end) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        // Oracle pricing names its feed, replaces the auction schedule and can't discount to nothing
+        if price_feed != [0; 32]
+            && (start_receive != 0
+                || feed_id == [0; 32]
+                || max_staleness == 0
+                || spread_bps <= -(MAX_BASIS_POINTS as i16))
+        {
+            return Err(ProgramError::InvalidInstructionData);
+        }
 
-        if amount == 0 {
+        // Collection bids are paid one whole NFT at a time, so neither schedule applies
+        if collection != [0; 32] && (start_receive != 0 || price_feed != [0; 32]) {
             return Err(ProgramError::InvalidInstructionData);
         }
 
@@ -75,6 +142,18 @@ impl<'a> TryFrom<&'a [u8]> for MakeInstructionData {
             receive,
             amount,
             expiry,
+            bounty,
+            taker,
+            merkle_root,
+            start_receive,
+            auction_start,
+            auction_end,
+            price_feed,
+            feed_id,
+            max_staleness,
+            max_conf_bps,
+            spread_bps,
+            collection,
         })
     }
 }
@@ -92,6 +171,10 @@ impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Make<'a> {
         let accounts = MakeAccounts::try_from(accounts)?;
         let instruction_data = MakeInstructionData::try_from(data)?;
 
+        if instruction_data.collection != [0; 32] && instruction_data.collection != *accounts.mint_b.key() {
+            return Err(crate::errors::PinocchioError::InvalidCollection.into());
+        }
+
         let (_, bump) = find_program_address(
             &[
                 b"escrow",
@@ -136,8 +219,20 @@ impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Make<'a> {
 
 impl<'a> Make<'a> {
     pub const DISCRIMINATOR: &'a u8 = &0;
+    pub const CONFIG_INDEX: usize = 6;
 
     pub fn process(&mut self) -> ProgramResult {
+        // Mint A may withhold a transfer fee, so record what actually lands in the vault
+        let clock = Clock::get()?;
+        let deposit = MintAccount::amount_after_fee(
+            self.accounts.mint_a,
+            clock.epoch,
+            self.instruction_data.amount,
+        )?;
+        if deposit == 0 {
+            return Err(crate::errors::PinocchioError::InvalidAmount.into());
+        }
+
         // Populate the escrow account
         let mut data = self.accounts.escrow.try_borrow_mut_data()?;
         let escrow = Escrow::load_mut(data.as_mut())?;
@@ -148,17 +243,64 @@ impl<'a> Make<'a> {
             *self.accounts.mint_a.key(),
             *self.accounts.mint_b.key(),
             self.instruction_data.receive,
+            deposit,
             self.instruction_data.expiry,
+            self.instruction_data.bounty,
+            self.instruction_data.taker,
+            self.instruction_data.merkle_root,
             [self.bump],
         );
+        escrow.set_auction(
+            self.instruction_data.start_receive,
+            self.instruction_data.auction_start,
+            self.instruction_data.auction_end,
+        );
+        escrow.set_oracle(
+            self.instruction_data.price_feed,
+            self.instruction_data.feed_id,
+            self.instruction_data.max_staleness,
+            self.instruction_data.max_conf_bps,
+            self.instruction_data.spread_bps,
+        );
+        escrow.set_collection(self.instruction_data.collection);
+        drop(data);
+
+        if MintAccount::is_native(self.accounts.mint_a) {
+            // Wrap the deposit directly into the vault
+            SystemTransfer {
+                from: self.accounts.maker,
+                to: self.accounts.vault,
+                lamports: self.instruction_data.amount,
+            }
+            .invoke()?;
+
+            SyncNative {
+                native_token: self.accounts.vault,
+                token_program: self.accounts.token_program.key(),
+            }
+            .invoke()?;
+        } else {
+            TransferChecked {
+                from: self.accounts.maker_ata_a,
+                mint: self.accounts.mint_a,
+                to: self.accounts.vault,
+                authority: self.accounts.maker,
+                amount: self.instruction_data.amount,
+                decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+                token_program: self.accounts.token_program.key(),
+            }
+            .invoke()?;
+        }
 
-        Transfer {
-            from: self.accounts.maker_ata_a,
-            to: self.accounts.vault,
-            authority: self.accounts.maker,
-            amount: self.instruction_data.amount,
+        // Park the crank bounty on the escrow account, on top of its rent
+        if self.instruction_data.bounty > 0 {
+            SystemTransfer {
+                from: self.accounts.maker,
+                to: self.accounts.escrow,
+                lamports: self.instruction_data.bounty,
+            }
+            .invoke()?;
         }
-        .invoke()?;
 
         Ok(())
     }
@@ -0,0 +1,230 @@
+use pinocchio::{
+    account_info::AccountInfo, instruction::Seed, program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use std::mem::size_of;
+use pinocchio_system::instructions::Transfer as SystemTransfer;
+use pinocchio_token_2022::instructions::{SyncNative, TransferChecked};
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenProgram};
+use crate::state::{ArbiterEscrow, MAX_MILESTONES};
+
+pub struct MakeArbiterEscrowAccounts<'a> {
+    pub maker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub mint_a: &'a AccountInfo,
+    pub maker_ata_a: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for MakeArbiterEscrowAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [maker, escrow, mint_a, maker_ata_a, vault, config, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(maker)?;
+        MintAccount::check(mint_a)?;
+        TokenProgram::check(token_program, mint_a)?;
+        // Native SOL is wrapped straight from the maker's lamports, so no ATA is needed
+        if !MintAccount::is_native(mint_a) {
+            AssociatedTokenAccount::check(maker_ata_a, maker, mint_a, token_program)?;
+        }
+
+        Ok(Self {
+            maker,
+            escrow,
+            mint_a,
+            maker_ata_a,
+            vault,
+            config,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct MakeArbiterEscrowInstructionData {
+    pub seed: u64,
+    pub expiry: i64,
+    pub beneficiary: Pubkey,
+    pub arbiter: Pubkey,
+    pub milestones: [u64; MAX_MILESTONES],
+    /// Sum of the milestones, deposited up front
+    pub amount: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for MakeArbiterEscrowInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        const HEADER_LEN: usize = size_of::<u64>() + size_of::<i64>() + size_of::<Pubkey>() * 2;
+
+        // One to MAX_MILESTONES tranche amounts follow the fixed fields
+        let schedule = data.get(HEADER_LEN..).ok_or(ProgramError::InvalidInstructionData)?;
+        if schedule.is_empty()
+            || schedule.len() % size_of::<u64>() != 0
+            || schedule.len() / size_of::<u64>() > MAX_MILESTONES
+        {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let seed = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let expiry = i64::from_le_bytes(data[8..16].try_into().unwrap());
+        let beneficiary: Pubkey = data[16..48].try_into().unwrap();
+        let arbiter: Pubkey = data[48..80].try_into().unwrap();
+
+        let mut milestones = [0u64; MAX_MILESTONES];
+        let mut amount = 0u64;
+        for (milestone, bytes) in milestones.iter_mut().zip(schedule.chunks_exact(size_of::<u64>())) {
+            *milestone = u64::from_le_bytes(bytes.try_into().unwrap());
+            if *milestone == 0 {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            amount = amount
+                .checked_add(*milestone)
+                .ok_or(ProgramError::InvalidInstructionData)?;
+        }
+
+        if beneficiary == [0; 32] || arbiter == [0; 32] {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            seed,
+            expiry,
+            beneficiary,
+            arbiter,
+            milestones,
+            amount,
+        })
+    }
+}
+
+pub struct MakeArbiterEscrow<'a> {
+    pub accounts: MakeArbiterEscrowAccounts<'a>,
+    pub instruction_data: MakeArbiterEscrowInstructionData,
+    pub bump: u8,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for MakeArbiterEscrow<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = MakeArbiterEscrowAccounts::try_from(accounts)?;
+        let instruction_data = MakeArbiterEscrowInstructionData::try_from(data)?;
+
+        let (_, bump) = find_program_address(
+            &[
+                b"arbiter",
+                accounts.maker.key(),
+                &instruction_data.seed.to_le_bytes(),
+            ],
+            &crate::ID,
+        );
+
+        let seed_binding = instruction_data.seed.to_le_bytes();
+        let bump_binding = [bump];
+        let escrow_seeds = [
+            Seed::from(b"arbiter"),
+            Seed::from(accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+
+        ProgramAccount::init::<ArbiterEscrow>(
+            accounts.maker,
+            accounts.escrow,
+            &escrow_seeds,
+            ArbiterEscrow::LEN,
+        )?;
+
+        AssociatedTokenAccount::init(
+            accounts.vault,
+            accounts.mint_a,
+            accounts.maker,
+            accounts.escrow,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+            bump,
+        })
+    }
+}
+
+impl<'a> MakeArbiterEscrow<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &8;
+    pub const CONFIG_INDEX: usize = 5;
+
+    pub fn process(&mut self) -> ProgramResult {
+        // Mint A may withhold a transfer fee, so the tranches are scaled down to what
+        // actually lands in the vault; the last release sweeps any rounding dust
+        let clock = Clock::get()?;
+        let amount = self.instruction_data.amount;
+        let deposit = MintAccount::amount_after_fee(self.accounts.mint_a, clock.epoch, amount)?;
+        let mut milestones = [0u64; MAX_MILESTONES];
+        for (milestone, gross) in milestones.iter_mut().zip(self.instruction_data.milestones) {
+            *milestone = (gross as u128 * deposit as u128 / amount as u128) as u64;
+            // An unused slot is zero, so a tranche scaled down to nothing would vanish
+            if gross > 0 && *milestone == 0 {
+                return Err(crate::errors::PinocchioError::InvalidAmount.into());
+            }
+        }
+
+        let mut data = self.accounts.escrow.try_borrow_mut_data()?;
+        let escrow = ArbiterEscrow::load_mut(data.as_mut())?;
+
+        escrow.set_inner(
+            self.instruction_data.seed,
+            *self.accounts.maker.key(),
+            self.instruction_data.beneficiary,
+            self.instruction_data.arbiter,
+            *self.accounts.mint_a.key(),
+            self.instruction_data.expiry,
+            milestones,
+            [self.bump],
+        );
+        drop(data);
+
+        if MintAccount::is_native(self.accounts.mint_a) {
+            // Wrap the deposit directly into the vault
+            SystemTransfer {
+                from: self.accounts.maker,
+                to: self.accounts.vault,
+                lamports: amount,
+            }
+            .invoke()?;
+
+            SyncNative {
+                native_token: self.accounts.vault,
+                token_program: self.accounts.token_program.key(),
+            }
+            .invoke()?;
+        } else {
+            TransferChecked {
+                from: self.accounts.maker_ata_a,
+                mint: self.accounts.mint_a,
+                to: self.accounts.vault,
+                authority: self.accounts.maker,
+                amount,
+                decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+                token_program: self.accounts.token_program.key(),
+            }
+            .invoke()?;
+        }
+
+        Ok(())
+    }
+}
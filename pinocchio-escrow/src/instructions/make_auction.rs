@@ -0,0 +1,186 @@
+use pinocchio::{
+    account_info::AccountInfo, instruction::Seed, program_error::ProgramError,
+    pubkey::find_program_address,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use std::mem::size_of;
+use pinocchio_token_2022::instructions::TransferChecked;
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenProgram};
+use crate::state::AuctionEscrow;
+
+pub struct MakeAuctionAccounts<'a> {
+    pub maker: &'a AccountInfo,
+    pub auction: &'a AccountInfo,
+    pub mint_a: &'a AccountInfo,
+    pub mint_b: &'a AccountInfo,
+    pub maker_ata_a: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for MakeAuctionAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [maker, auction, mint_a, mint_b, maker_ata_a, vault, config, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(maker)?;
+        MintAccount::check(mint_a)?;
+        MintAccount::check(mint_b)?;
+        TokenProgram::check(token_program, mint_a)?;
+        AssociatedTokenAccount::check(maker_ata_a, maker, mint_a, token_program)?;
+
+        Ok(Self {
+            maker,
+            auction,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            vault,
+            config,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct MakeAuctionInstructionData {
+    pub seed: u64,
+    pub amount: u64,
+    /// Lowest mint B bid the maker will sell the lot for
+    pub reserve: u64,
+    pub min_increment: u64,
+    pub end: i64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for MakeAuctionInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() * 4 + size_of::<i64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let seed = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let amount = u64::from_le_bytes(data[8..16].try_into().unwrap());
+        let reserve = u64::from_le_bytes(data[16..24].try_into().unwrap());
+        let min_increment = u64::from_le_bytes(data[24..32].try_into().unwrap());
+        let end = i64::from_le_bytes(data[32..40].try_into().unwrap());
+
+        // A zero increment would let bidders leapfrog each other forever
+        if amount == 0 || reserve == 0 || min_increment == 0 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            seed,
+            amount,
+            reserve,
+            min_increment,
+            end,
+        })
+    }
+}
+
+pub struct MakeAuction<'a> {
+    pub accounts: MakeAuctionAccounts<'a>,
+    pub instruction_data: MakeAuctionInstructionData,
+    pub bump: u8,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for MakeAuction<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = MakeAuctionAccounts::try_from(accounts)?;
+        let instruction_data = MakeAuctionInstructionData::try_from(data)?;
+
+        let (_, bump) = find_program_address(
+            &[
+                b"auction",
+                accounts.maker.key(),
+                &instruction_data.seed.to_le_bytes(),
+            ],
+            &crate::ID,
+        );
+
+        let seed_binding = instruction_data.seed.to_le_bytes();
+        let bump_binding = [bump];
+        let auction_seeds = [
+            Seed::from(b"auction"),
+            Seed::from(accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+
+        ProgramAccount::init::<AuctionEscrow>(
+            accounts.maker,
+            accounts.auction,
+            &auction_seeds,
+            AuctionEscrow::LEN,
+        )?;
+
+        AssociatedTokenAccount::init(
+            accounts.vault,
+            accounts.mint_a,
+            accounts.maker,
+            accounts.auction,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+            bump,
+        })
+    }
+}
+
+impl<'a> MakeAuction<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &18;
+    pub const CONFIG_INDEX: usize = 6;
+
+    pub fn process(&mut self) -> ProgramResult {
+        // An auction that has already ended couldn't take a single bid
+        let clock = Clock::get()?;
+        if self.instruction_data.end <= clock.unix_timestamp {
+            return Err(crate::errors::PinocchioError::EscrowExpired.into());
+        }
+
+        let mut data = self.accounts.auction.try_borrow_mut_data()?;
+        let auction = AuctionEscrow::load_mut(data.as_mut())?;
+
+        auction.set_inner(
+            self.instruction_data.seed,
+            *self.accounts.maker.key(),
+            *self.accounts.mint_a.key(),
+            *self.accounts.mint_b.key(),
+            self.instruction_data.reserve,
+            self.instruction_data.min_increment,
+            self.instruction_data.end,
+            [self.bump],
+        );
+        drop(data);
+
+        TransferChecked {
+            from: self.accounts.maker_ata_a,
+            mint: self.accounts.mint_a,
+            to: self.accounts.vault,
+            authority: self.accounts.maker,
+            amount: self.instruction_data.amount,
+            decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+use pinocchio::{
+    account_info::AccountInfo, instruction::Seed, program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use std::mem::size_of;
+use pinocchio_token_2022::instructions::TransferChecked;
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenProgram};
+use crate::state::{has_duplicate_mints, BundleEscrow, Leg, MAX_BUNDLE_LEGS};
+
+/// Accounts passed per mint A leg: the mint, the token program owning it, the maker's token
+/// account and the vault.
+pub const MAKE_BUNDLE_LEG_ACCOUNTS: usize = 4;
+
+pub struct MakeBundleAccounts<'a> {
+    pub maker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    /// `[mint_a, token_program, maker_ata_a, vault]` for each mint A leg, in order
+    pub legs: &'a [AccountInfo],
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for MakeBundleAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [maker, escrow, config, system_program, _, legs @ ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(maker)?;
+
+        Ok(Self {
+            maker,
+            escrow,
+            config,
+            system_program,
+            legs,
+        })
+    }
+}
+
+pub struct MakeBundleInstructionData {
+    pub seed: u64,
+    /// Amounts deposited for each mint A leg, matched to the leg accounts in order
+    pub amounts_a: [u64; MAX_BUNDLE_LEGS],
+    pub legs_a_len: usize,
+    pub legs_b: [Leg; MAX_BUNDLE_LEGS],
+    pub legs_b_len: usize,
+}
+
+impl<'a> TryFrom<&'a [u8]> for MakeBundleInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        // seed, leg counts, then an amount per mint A leg and a mint and amount per mint B leg
+        let header = size_of::<u64>() + size_of::<u8>() * 2;
+        if data.len() < header {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let seed = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let legs_a_len = data[8] as usize;
+        let legs_b_len = data[9] as usize;
+        if !(1..=MAX_BUNDLE_LEGS).contains(&legs_a_len) || !(1..=MAX_BUNDLE_LEGS).contains(&legs_b_len) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        if data.len()
+            != header
+                + size_of::<u64>() * legs_a_len
+                + (size_of::<Pubkey>() + size_of::<u64>()) * legs_b_len
+        {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let mut amounts_a = [0; MAX_BUNDLE_LEGS];
+        for (amount, bytes) in amounts_a.iter_mut().zip(data[header..].chunks_exact(8).take(legs_a_len)) {
+            *amount = u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+
+        let mut legs_b = [Leg::default(); MAX_BUNDLE_LEGS];
+        let legs_b_data = &data[header + size_of::<u64>() * legs_a_len..];
+        for (leg, bytes) in legs_b.iter_mut().zip(legs_b_data.chunks_exact(40)) {
+            leg.mint = bytes[0..32].try_into().unwrap();
+            leg.amount = u64::from_le_bytes(bytes[32..40].try_into().unwrap());
+        }
+
+        if amounts_a[..legs_a_len].contains(&0)
+            || legs_b[..legs_b_len].iter().any(|leg| leg.amount == 0)
+            || has_duplicate_mints(&legs_b[..legs_b_len])
+        {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            seed,
+            amounts_a,
+            legs_a_len,
+            legs_b,
+            legs_b_len,
+        })
+    }
+}
+
+pub struct MakeBundle<'a> {
+    pub accounts: MakeBundleAccounts<'a>,
+    pub instruction_data: MakeBundleInstructionData,
+    pub bump: u8,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for MakeBundle<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = MakeBundleAccounts::try_from(accounts)?;
+        let instruction_data = MakeBundleInstructionData::try_from(data)?;
+
+        if accounts.legs.len() != instruction_data.legs_a_len * MAKE_BUNDLE_LEG_ACCOUNTS {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let (_, bump) = find_program_address(
+            &[
+                b"bundle",
+                accounts.maker.key(),
+                &instruction_data.seed.to_le_bytes(),
+            ],
+            &crate::ID,
+        );
+
+        let seed_binding = instruction_data.seed.to_le_bytes();
+        let bump_binding = [bump];
+        let escrow_seeds = [
+            Seed::from(b"bundle"),
+            Seed::from(accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+
+        ProgramAccount::init::<BundleEscrow>(
+            accounts.maker,
+            accounts.escrow,
+            &escrow_seeds,
+            BundleEscrow::LEN,
+        )?;
+
+        // Every mint A leg gets its own vault, the escrow's associated token account for that mint
+        for leg in accounts.legs.chunks_exact(MAKE_BUNDLE_LEG_ACCOUNTS) {
+            let [mint_a, token_program, maker_ata_a, vault] = leg else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            MintAccount::check(mint_a)?;
+            TokenProgram::check(token_program, mint_a)?;
+            AssociatedTokenAccount::check(maker_ata_a, accounts.maker, mint_a, token_program)?;
+
+            AssociatedTokenAccount::init(
+                vault,
+                mint_a,
+                accounts.maker,
+                accounts.escrow,
+                accounts.system_program,
+                token_program,
+            )?;
+        }
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+            bump,
+        })
+    }
+}
+
+impl<'a> MakeBundle<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &22;
+    pub const CONFIG_INDEX: usize = 2;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let clock = Clock::get()?;
+
+        let mut legs_a = [Leg::default(); MAX_BUNDLE_LEGS];
+        for (i, leg) in self.accounts.legs.chunks_exact(MAKE_BUNDLE_LEG_ACCOUNTS).enumerate() {
+            let [mint_a, token_program, maker_ata_a, vault] = leg else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let amount = self.instruction_data.amounts_a[i];
+
+            // Each mint may withhold a transfer fee, so record what actually lands in its vault
+            let deposit = MintAccount::amount_after_fee(mint_a, clock.epoch, amount)?;
+            if deposit == 0 {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            legs_a[i] = Leg {
+                mint: *mint_a.key(),
+                amount: deposit,
+            };
+
+            TransferChecked {
+                from: maker_ata_a,
+                mint: mint_a,
+                to: vault,
+                authority: self.accounts.maker,
+                amount,
+                decimals: MintAccount::get_decimals(mint_a)?,
+                token_program: token_program.key(),
+            }
+            .invoke()?;
+        }
+        let legs_a = &legs_a[..self.instruction_data.legs_a_len];
+
+        // A repeated mint A would have failed to open its vault twice; checked anyway so the
+        // stored legs never alias
+        if has_duplicate_mints(legs_a) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let mut data = self.accounts.escrow.try_borrow_mut_data()?;
+        let escrow = BundleEscrow::load_mut(data.as_mut())?;
+        escrow.set_inner(
+            self.instruction_data.seed,
+            *self.accounts.maker.key(),
+            legs_a,
+            &self.instruction_data.legs_b[..self.instruction_data.legs_b_len],
+            [self.bump],
+        );
+
+        Ok(())
+    }
+}
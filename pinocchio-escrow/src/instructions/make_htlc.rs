@@ -0,0 +1,186 @@
+use pinocchio::{
+    account_info::AccountInfo, instruction::Seed, program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use std::mem::size_of;
+use pinocchio_token_2022::instructions::TransferChecked;
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenProgram};
+use crate::hash::HASH_BYTES;
+use crate::state::HtlcEscrow;
+
+pub struct MakeHtlcAccounts<'a> {
+    pub maker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub mint_a: &'a AccountInfo,
+    pub maker_ata_a: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for MakeHtlcAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [maker, escrow, mint_a, maker_ata_a, vault, config, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(maker)?;
+        MintAccount::check(mint_a)?;
+        TokenProgram::check(token_program, mint_a)?;
+        AssociatedTokenAccount::check(maker_ata_a, maker, mint_a, token_program)?;
+
+        Ok(Self {
+            maker,
+            escrow,
+            mint_a,
+            maker_ata_a,
+            vault,
+            config,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct MakeHtlcInstructionData {
+    pub seed: u64,
+    pub amount: u64,
+    pub expiry: i64,
+    pub recipient: Pubkey,
+    pub sha256_hash: [u8; HASH_BYTES],
+    pub keccak_hash: [u8; HASH_BYTES],
+}
+
+impl<'a> TryFrom<&'a [u8]> for MakeHtlcInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() * 2 + size_of::<i64>() + size_of::<Pubkey>() + HASH_BYTES * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let seed = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let amount = u64::from_le_bytes(data[8..16].try_into().unwrap());
+        let expiry = i64::from_le_bytes(data[16..24].try_into().unwrap());
+        let recipient: Pubkey = data[24..56].try_into().unwrap();
+        let sha256_hash: [u8; HASH_BYTES] = data[56..88].try_into().unwrap();
+        let keccak_hash: [u8; HASH_BYTES] = data[88..120].try_into().unwrap();
+
+        // The SHA-256 lock is mandatory; the Keccak-256 one may be left zeroed
+        if amount == 0 || recipient == [0; 32] || sha256_hash == [0; HASH_BYTES] {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            seed,
+            amount,
+            expiry,
+            recipient,
+            sha256_hash,
+            keccak_hash,
+        })
+    }
+}
+
+pub struct MakeHtlc<'a> {
+    pub accounts: MakeHtlcAccounts<'a>,
+    pub instruction_data: MakeHtlcInstructionData,
+    pub bump: u8,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for MakeHtlc<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = MakeHtlcAccounts::try_from(accounts)?;
+        let instruction_data = MakeHtlcInstructionData::try_from(data)?;
+
+        let (_, bump) = find_program_address(
+            &[
+                b"htlc",
+                accounts.maker.key(),
+                &instruction_data.seed.to_le_bytes(),
+            ],
+            &crate::ID,
+        );
+
+        let seed_binding = instruction_data.seed.to_le_bytes();
+        let bump_binding = [bump];
+        let escrow_seeds = [
+            Seed::from(b"htlc"),
+            Seed::from(accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+
+        ProgramAccount::init::<HtlcEscrow>(
+            accounts.maker,
+            accounts.escrow,
+            &escrow_seeds,
+            HtlcEscrow::LEN,
+        )?;
+
+        AssociatedTokenAccount::init(
+            accounts.vault,
+            accounts.mint_a,
+            accounts.maker,
+            accounts.escrow,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+            bump,
+        })
+    }
+}
+
+impl<'a> MakeHtlc<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &15;
+    pub const CONFIG_INDEX: usize = 5;
+
+    pub fn process(&mut self) -> ProgramResult {
+        // A timelock already in the past would let the maker refund straight away
+        let clock = Clock::get()?;
+        if self.instruction_data.expiry <= clock.unix_timestamp {
+            return Err(crate::errors::PinocchioError::EscrowExpired.into());
+        }
+
+        let mut data = self.accounts.escrow.try_borrow_mut_data()?;
+        let escrow = HtlcEscrow::load_mut(data.as_mut())?;
+
+        escrow.set_inner(
+            self.instruction_data.seed,
+            *self.accounts.maker.key(),
+            self.instruction_data.recipient,
+            *self.accounts.mint_a.key(),
+            self.instruction_data.sha256_hash,
+            self.instruction_data.keccak_hash,
+            self.instruction_data.expiry,
+            [self.bump],
+        );
+        drop(data);
+
+        TransferChecked {
+            from: self.accounts.maker_ata_a,
+            mint: self.accounts.mint_a,
+            to: self.accounts.vault,
+            authority: self.accounts.maker,
+            amount: self.instruction_data.amount,
+            decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke()?;
+
+        Ok(())
+    }
+}
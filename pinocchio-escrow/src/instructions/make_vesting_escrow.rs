@@ -0,0 +1,191 @@
+use pinocchio::{
+    account_info::AccountInfo, instruction::Seed, program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use std::mem::size_of;
+use pinocchio_token_2022::instructions::TransferChecked;
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenProgram};
+use crate::state::VestingEscrow;
+
+pub struct MakeVestingEscrowAccounts<'a> {
+    pub maker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub mint_a: &'a AccountInfo,
+    pub maker_ata_a: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for MakeVestingEscrowAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [maker, escrow, mint_a, maker_ata_a, vault, config, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(maker)?;
+        MintAccount::check(mint_a)?;
+        TokenProgram::check(token_program, mint_a)?;
+        AssociatedTokenAccount::check(maker_ata_a, maker, mint_a, token_program)?;
+
+        Ok(Self {
+            maker,
+            escrow,
+            mint_a,
+            maker_ata_a,
+            vault,
+            config,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct MakeVestingEscrowInstructionData {
+    pub seed: u64,
+    pub amount: u64,
+    pub start: i64,
+    pub cliff: i64,
+    pub end: i64,
+    pub beneficiary: Pubkey,
+}
+
+impl<'a> TryFrom<&'a [u8]> for MakeVestingEscrowInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() * 2 + size_of::<i64>() * 3 + size_of::<Pubkey>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let seed = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let amount = u64::from_le_bytes(data[8..16].try_into().unwrap());
+        let start = i64::from_le_bytes(data[16..24].try_into().unwrap());
+        let cliff = i64::from_le_bytes(data[24..32].try_into().unwrap());
+        let end = i64::from_le_bytes(data[32..40].try_into().unwrap());
+        let beneficiary: Pubkey = data[40..72].try_into().unwrap();
+
+        // The cliff must fall inside a non-empty vesting window
+        if amount == 0 || beneficiary == [0; 32] || start > cliff || cliff > end || start == end {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            seed,
+            amount,
+            start,
+            cliff,
+            end,
+            beneficiary,
+        })
+    }
+}
+
+pub struct MakeVestingEscrow<'a> {
+    pub accounts: MakeVestingEscrowAccounts<'a>,
+    pub instruction_data: MakeVestingEscrowInstructionData,
+    pub bump: u8,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for MakeVestingEscrow<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = MakeVestingEscrowAccounts::try_from(accounts)?;
+        let instruction_data = MakeVestingEscrowInstructionData::try_from(data)?;
+
+        let (_, bump) = find_program_address(
+            &[
+                b"vesting",
+                accounts.maker.key(),
+                &instruction_data.seed.to_le_bytes(),
+            ],
+            &crate::ID,
+        );
+
+        let seed_binding = instruction_data.seed.to_le_bytes();
+        let bump_binding = [bump];
+        let escrow_seeds = [
+            Seed::from(b"vesting"),
+            Seed::from(accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+
+        ProgramAccount::init::<VestingEscrow>(
+            accounts.maker,
+            accounts.escrow,
+            &escrow_seeds,
+            VestingEscrow::LEN,
+        )?;
+
+        AssociatedTokenAccount::init(
+            accounts.vault,
+            accounts.mint_a,
+            accounts.maker,
+            accounts.escrow,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+            bump,
+        })
+    }
+}
+
+impl<'a> MakeVestingEscrow<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &13;
+    pub const CONFIG_INDEX: usize = 5;
+
+    pub fn process(&mut self) -> ProgramResult {
+        // Mint A may withhold a transfer fee, so vest what actually lands in the vault
+        let clock = Clock::get()?;
+        let total = MintAccount::amount_after_fee(
+            self.accounts.mint_a,
+            clock.epoch,
+            self.instruction_data.amount,
+        )?;
+        if total == 0 {
+            return Err(crate::errors::PinocchioError::InvalidAmount.into());
+        }
+
+        let mut data = self.accounts.escrow.try_borrow_mut_data()?;
+        let escrow = VestingEscrow::load_mut(data.as_mut())?;
+
+        escrow.set_inner(
+            self.instruction_data.seed,
+            *self.accounts.maker.key(),
+            self.instruction_data.beneficiary,
+            *self.accounts.mint_a.key(),
+            self.instruction_data.start,
+            self.instruction_data.cliff,
+            self.instruction_data.end,
+            total,
+            [self.bump],
+        );
+        drop(data);
+
+        TransferChecked {
+            from: self.accounts.maker_ata_a,
+            mint: self.accounts.mint_a,
+            to: self.accounts.vault,
+            authority: self.accounts.maker,
+            amount: self.instruction_data.amount,
+            decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke()?;
+
+        Ok(())
+    }
+}
@@ -1,9 +1,61 @@
 pub mod make;
 pub mod take;
 pub mod refund;
+pub mod expire_refund;
+pub mod update;
+pub mod initialize_config;
+pub mod update_config;
+pub mod set_paused;
+pub mod make_arbiter_escrow;
+pub mod release;
+pub mod arbiter_refund;
+pub mod dispute;
+pub mod release_milestone;
+pub mod make_vesting_escrow;
+pub mod claim;
+pub mod make_htlc;
+pub mod claim_htlc;
+pub mod refund_htlc;
+pub mod make_auction;
+pub mod place_bid;
+pub mod withdraw_bid;
+pub mod settle;
+pub mod make_bundle;
+pub mod take_bundle;
+pub mod refund_bundle;
+pub mod counter_offer;
+pub mod accept_counter;
+pub mod withdraw_counter;
+pub mod take_many;
 pub mod helper;
 
 pub use make::*;
 pub use take::*;
 pub use refund::*;
+pub use expire_refund::*;
+pub use update::*;
+pub use initialize_config::*;
+pub use update_config::*;
+pub use set_paused::*;
+pub use make_arbiter_escrow::*;
+pub use release::*;
+pub use arbiter_refund::*;
+pub use dispute::*;
+pub use release_milestone::*;
+pub use make_vesting_escrow::*;
+pub use claim::*;
+pub use make_htlc::*;
+pub use claim_htlc::*;
+pub use refund_htlc::*;
+pub use make_auction::*;
+pub use place_bid::*;
+pub use withdraw_bid::*;
+pub use settle::*;
+pub use make_bundle::*;
+pub use take_bundle::*;
+pub use refund_bundle::*;
+pub use counter_offer::*;
+pub use accept_counter::*;
+pub use withdraw_counter::*;
+pub use take_many::*;
 pub use helper::*;
\ No newline at end of file
@@ -0,0 +1,204 @@
+use pinocchio::{
+    account_info::AccountInfo, instruction::Seed, program_error::ProgramError,
+    pubkey::{create_program_address, find_program_address},
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use std::mem::size_of;
+use pinocchio_token_2022::instructions::TransferChecked;
+
+use crate::{AssociatedTokenAccount, ConfigAccount, MintAccount, ProgramAccount, SignerAccount, TokenProgram};
+use crate::state::{AuctionEscrow, Bid};
+
+pub struct PlaceBidAccounts<'a> {
+    pub bidder: &'a AccountInfo,
+    pub auction: &'a AccountInfo,
+    pub bid: &'a AccountInfo,
+    pub mint_b: &'a AccountInfo,
+    pub bidder_ata_b: &'a AccountInfo,
+    pub bid_vault: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for PlaceBidAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [bidder, auction, bid, mint_b, bidder_ata_b, bid_vault, config, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(bidder)?;
+        ProgramAccount::check_len(auction, AuctionEscrow::LEN)?;
+        MintAccount::check(mint_b)?;
+        TokenProgram::check(token_program, mint_b)?;
+        ConfigAccount::check(config)?;
+        AssociatedTokenAccount::check(bidder_ata_b, bidder, mint_b, token_program)?;
+
+        Ok(Self {
+            bidder,
+            auction,
+            bid,
+            mint_b,
+            bidder_ata_b,
+            bid_vault,
+            config,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct PlaceBidInstructionData {
+    /// Mint B added to the bidder's locked bid
+    pub amount: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for PlaceBidInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let amount = u64::from_le_bytes(data[0..8].try_into().unwrap());
+
+        if amount == 0 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self { amount })
+    }
+}
+
+pub struct PlaceBid<'a> {
+    pub accounts: PlaceBidAccounts<'a>,
+    pub instruction_data: PlaceBidInstructionData,
+    pub bump: u8,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for PlaceBid<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = PlaceBidAccounts::try_from(accounts)?;
+        let instruction_data = PlaceBidInstructionData::try_from(data)?;
+
+        let (bid_key, bump) = find_program_address(
+            &[b"bid", accounts.auction.key(), accounts.bidder.key()],
+            &crate::ID,
+        );
+        if &bid_key != accounts.bid.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        // A bidder's first bid opens their bid account and vault; later bids top them up
+        if accounts.bid.is_owned_by(&crate::ID) {
+            ProgramAccount::check_len(accounts.bid, Bid::LEN)?;
+            AssociatedTokenAccount::check(
+                accounts.bid_vault,
+                accounts.bid,
+                accounts.mint_b,
+                accounts.token_program,
+            )?;
+        } else {
+            let bump_binding = [bump];
+            let bid_seeds = [
+                Seed::from(b"bid"),
+                Seed::from(accounts.auction.key().as_ref()),
+                Seed::from(accounts.bidder.key().as_ref()),
+                Seed::from(&bump_binding),
+            ];
+
+            ProgramAccount::init::<Bid>(accounts.bidder, accounts.bid, &bid_seeds, Bid::LEN)?;
+
+            AssociatedTokenAccount::init(
+                accounts.bid_vault,
+                accounts.mint_b,
+                accounts.bidder,
+                accounts.bid,
+                accounts.system_program,
+                accounts.token_program,
+            )?;
+        }
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+            bump,
+        })
+    }
+}
+
+impl<'a> PlaceBid<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &19;
+    pub const CONFIG_INDEX: usize = 6;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let mut auction_data = self.accounts.auction.try_borrow_mut_data()?;
+        let auction = AuctionEscrow::load_mut(auction_data.as_mut())?;
+
+        let clock = Clock::get()?;
+        if auction.is_ended(clock.unix_timestamp) {
+            return Err(crate::errors::PinocchioError::EscrowExpired.into());
+        }
+
+        // Verify the mint matches the stored mint
+        if auction.mint_b != *self.accounts.mint_b.key() {
+            return Err(crate::errors::PinocchioError::InvalidMint.into());
+        }
+
+        let auction_key = create_program_address(
+            &[
+                b"auction",
+                &auction.maker,
+                &auction.seed.to_le_bytes(),
+                &auction.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &auction_key != self.accounts.auction.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        let mut bid_data = self.accounts.bid.try_borrow_mut_data()?;
+        let bid = Bid::load_mut(bid_data.as_mut())?;
+        if bid.discriminator == [0; 8] {
+            bid.set_inner(*self.accounts.auction.key(), *self.accounts.bidder.key(), [self.bump]);
+        }
+
+        // Mint B may withhold a transfer fee, so a bid only counts what lands in its vault
+        let credited = MintAccount::amount_after_fee(
+            self.accounts.mint_b,
+            clock.epoch,
+            self.instruction_data.amount,
+        )?;
+        let total = bid
+            .amount
+            .checked_add(credited)
+            .ok_or(crate::errors::PinocchioError::InvalidAmount)?;
+        if total < auction.min_bid() {
+            return Err(crate::errors::PinocchioError::BidTooLow.into());
+        }
+
+        TransferChecked {
+            from: self.accounts.bidder_ata_b,
+            mint: self.accounts.mint_b,
+            to: self.accounts.bid_vault,
+            authority: self.accounts.bidder,
+            amount: self.instruction_data.amount,
+            decimals: MintAccount::get_decimals(self.accounts.mint_b)?,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke()?;
+
+        bid.set_amount(total);
+        auction.set_highest_bid(*self.accounts.bidder.key(), total);
+
+        Ok(())
+    }
+}
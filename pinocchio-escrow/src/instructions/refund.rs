@@ -5,12 +5,9 @@ use pinocchio::{
     pubkey::create_program_address,
     ProgramResult,
 };
-use pinocchio_token::{
-    instructions::{CloseAccount, Transfer},
-    state::TokenAccount,
-};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
 
-use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount};
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenAccount, TokenProgram};
 use crate::state::Escrow;
 
 pub struct RefundAccounts<'a> {
@@ -36,6 +33,7 @@ impl<'a> TryFrom<&'a [AccountInfo]> for RefundAccounts<'a> {
         SignerAccount::check(maker)?;
         ProgramAccount::check(escrow)?;
         MintAccount::check(mint_a)?;
+        TokenProgram::check(token_program, mint_a)?;
         AssociatedTokenAccount::check(vault, escrow, mint_a, token_program)?;
 
         Ok(Self {
@@ -59,14 +57,16 @@ impl<'a> TryFrom<&'a [AccountInfo]> for Refund<'a> {
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
         let accounts = RefundAccounts::try_from(accounts)?;
-        AssociatedTokenAccount::init_if_needed(
-            accounts.maker_ata_a,
-            accounts.mint_a,
-            accounts.maker,
-            accounts.maker,
-            accounts.system_program,
-            accounts.token_program,
-        )?;
+        if !MintAccount::is_native(accounts.mint_a) {
+            AssociatedTokenAccount::init_if_needed(
+                accounts.maker_ata_a,
+                accounts.mint_a,
+                accounts.maker,
+                accounts.maker,
+                accounts.system_program,
+                accounts.token_program,
+            )?;
+        }
 
         Ok(Self { accounts })
     }
@@ -110,29 +110,36 @@ impl<'a> Refund<'a> {
             Seed::from(&seed_binding),
             Seed::from(&bump_binding),
         ];
-        let signer = Signer::from(&escrow_seeds);
+        let signers = [Signer::from(&escrow_seeds)];
 
-        let amount = TokenAccount::from_account_info(self.accounts.vault)?.amount();
+        let amount = TokenAccount::get_amount(self.accounts.vault)?;
         
         // Validate amount is greater than 0
         if amount == 0 {
             return Err(crate::errors::PinocchioError::InvalidAmount.into());
         }
 
-        Transfer {
-            from: self.accounts.vault,
-            to: self.accounts.maker_ata_a,
-            authority: self.accounts.escrow,
-            amount,
+        // A native vault can be closed with a balance, which unwraps it straight to the maker
+        if !MintAccount::is_native(self.accounts.mint_a) {
+            TransferChecked {
+                from: self.accounts.vault,
+                mint: self.accounts.mint_a,
+                to: self.accounts.maker_ata_a,
+                authority: self.accounts.escrow,
+                amount,
+                decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+                token_program: self.accounts.token_program.key(),
+            }
+            .invoke_signed(&signers)?;
         }
-        .invoke_signed(&[signer.clone()])?;
 
         CloseAccount {
             account: self.accounts.vault,
             destination: self.accounts.maker,
             authority: self.accounts.escrow,
+            token_program: self.accounts.token_program.key(),
         }
-        .invoke_signed(&[signer.clone()])?;
+        .invoke_signed(&signers)?;
 
         drop(data);
         ProgramAccount::close(self.accounts.escrow, self.accounts.maker)?;
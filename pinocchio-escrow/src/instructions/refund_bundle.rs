@@ -0,0 +1,143 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenAccount, TokenProgram};
+use crate::state::BundleEscrow;
+
+/// Accounts passed per mint A leg: the mint, the token program owning it, the vault and the
+/// maker's token account.
+pub const REFUND_BUNDLE_LEG_ACCOUNTS: usize = 4;
+
+pub struct RefundBundleAccounts<'a> {
+    pub maker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    /// `[mint_a, token_program, vault, maker_ata_a]` for each mint A leg, in the order the escrow stores them
+    pub legs: &'a [AccountInfo],
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RefundBundleAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [maker, escrow, system_program, _, legs @ ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(maker)?;
+        ProgramAccount::check_len(escrow, BundleEscrow::LEN)?;
+
+        Ok(Self {
+            maker,
+            escrow,
+            system_program,
+            legs,
+        })
+    }
+}
+
+pub struct RefundBundle<'a> {
+    pub accounts: RefundBundleAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RefundBundle<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = RefundBundleAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> RefundBundle<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &24;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let data = self.accounts.escrow.try_borrow_data()?;
+        let escrow = BundleEscrow::load(&data)?;
+
+        // Verify the maker account matches the stored maker
+        if escrow.maker != *self.accounts.maker.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+
+        let escrow_key = create_program_address(
+            &[
+                b"bundle",
+                self.accounts.maker.key(),
+                &escrow.seed.to_le_bytes(),
+                &escrow.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &escrow_key != self.accounts.escrow.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        // Every vault must be emptied before the escrow can close
+        let legs_a = escrow.legs_a();
+        if self.accounts.legs.len() != legs_a.len() * REFUND_BUNDLE_LEG_ACCOUNTS {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let seed_binding = escrow.seed.to_le_bytes();
+        let bump_binding = escrow.bump;
+        let escrow_seeds = [
+            Seed::from(b"bundle"),
+            Seed::from(self.accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+        let signers = [Signer::from(&escrow_seeds)];
+
+        for (leg, accounts) in legs_a.iter().zip(self.accounts.legs.chunks_exact(REFUND_BUNDLE_LEG_ACCOUNTS)) {
+            let [mint_a, token_program, vault, maker_ata_a] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            if leg.mint != *mint_a.key() {
+                return Err(crate::errors::PinocchioError::InvalidMint.into());
+            }
+            TokenProgram::check(token_program, mint_a)?;
+            AssociatedTokenAccount::check(vault, self.accounts.escrow, mint_a, token_program)?;
+            AssociatedTokenAccount::init_if_needed(
+                maker_ata_a,
+                mint_a,
+                self.accounts.maker,
+                self.accounts.maker,
+                self.accounts.system_program,
+                token_program,
+            )?;
+
+            TransferChecked {
+                from: vault,
+                mint: mint_a,
+                to: maker_ata_a,
+                authority: self.accounts.escrow,
+                amount: TokenAccount::get_amount(vault)?,
+                decimals: MintAccount::get_decimals(mint_a)?,
+                token_program: token_program.key(),
+            }
+            .invoke_signed(&signers)?;
+
+            CloseAccount {
+                account: vault,
+                destination: self.accounts.maker,
+                authority: self.accounts.escrow,
+                token_program: token_program.key(),
+            }
+            .invoke_signed(&signers)?;
+        }
+
+        drop(data);
+        ProgramAccount::close(self.accounts.escrow, self.accounts.maker)?;
+
+        Ok(())
+    }
+}
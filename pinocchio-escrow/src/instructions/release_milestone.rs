@@ -0,0 +1,151 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, ReleaseAccounts, TokenAccount};
+use crate::state::{ArbiterEscrow, MAX_MILESTONES};
+
+pub struct ReleaseMilestoneInstructionData {
+    pub index: usize,
+}
+
+impl<'a> TryFrom<&'a [u8]> for ReleaseMilestoneInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let [index] = data else {
+            return Err(ProgramError::InvalidInstructionData);
+        };
+        if *index as usize >= MAX_MILESTONES {
+            return Err(crate::errors::PinocchioError::InvalidMilestone.into());
+        }
+
+        Ok(Self {
+            index: *index as usize,
+        })
+    }
+}
+
+pub struct ReleaseMilestone<'a> {
+    pub accounts: ReleaseAccounts<'a>,
+    pub instruction_data: ReleaseMilestoneInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for ReleaseMilestone<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = ReleaseAccounts::try_from(accounts)?;
+        let instruction_data = ReleaseMilestoneInstructionData::try_from(data)?;
+        AssociatedTokenAccount::init_if_needed(
+            accounts.beneficiary_ata_a,
+            accounts.mint_a,
+            accounts.authority,
+            accounts.beneficiary,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> ReleaseMilestone<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &12;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let mut data = self.accounts.escrow.try_borrow_mut_data()?;
+        let escrow = ArbiterEscrow::load_mut(data.as_mut())?;
+
+        // Same authority as a full release: the arbiter, or the maker confirming delivery
+        let authority = self.accounts.authority.key();
+        if escrow.arbiter != *authority && escrow.maker != *authority {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+
+        // Verify the maker and beneficiary match the stored parties
+        if escrow.maker != *self.accounts.maker.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+        if escrow.beneficiary != *self.accounts.beneficiary.key() {
+            return Err(crate::errors::PinocchioError::InvalidTaker.into());
+        }
+
+        // Verify the mint matches the stored mint
+        if escrow.mint_a != *self.accounts.mint_a.key() {
+            return Err(crate::errors::PinocchioError::InvalidMint.into());
+        }
+
+        let index = self.instruction_data.index;
+        if index >= escrow.milestone_count() || escrow.is_milestone_released(index) {
+            return Err(crate::errors::PinocchioError::InvalidMilestone.into());
+        }
+
+        let escrow_key = create_program_address(
+            &[
+                b"arbiter",
+                self.accounts.maker.key(),
+                &escrow.seed.to_le_bytes(),
+                &escrow.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &escrow_key != self.accounts.escrow.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        escrow.set_milestone_released(index);
+        let is_final = escrow.is_fully_released();
+
+        // The last tranche sweeps the vault, absorbing anything a transfer fee withheld
+        let amount = if is_final {
+            TokenAccount::get_amount(self.accounts.vault)?
+        } else {
+            escrow.milestones[index]
+        };
+
+        let seed_binding = escrow.seed.to_le_bytes();
+        let bump_binding = escrow.bump;
+        let escrow_seeds = [
+            Seed::from(b"arbiter"),
+            Seed::from(self.accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+        let signers = [Signer::from(&escrow_seeds)];
+
+        TransferChecked {
+            from: self.accounts.vault,
+            mint: self.accounts.mint_a,
+            to: self.accounts.beneficiary_ata_a,
+            authority: self.accounts.escrow,
+            amount,
+            decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        if is_final {
+            CloseAccount {
+                account: self.accounts.vault,
+                destination: self.accounts.maker,
+                authority: self.accounts.escrow,
+                token_program: self.accounts.token_program.key(),
+            }
+            .invoke_signed(&signers)?;
+
+            drop(data);
+            ProgramAccount::close(self.accounts.escrow, self.accounts.maker)?;
+        }
+
+        Ok(())
+    }
+}
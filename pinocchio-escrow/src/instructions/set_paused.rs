@@ -0,0 +1,75 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
+
+use crate::{ConfigAccount, SignerAccount};
+use crate::state::Config;
+
+pub struct SetPausedAccounts<'a> {
+    pub admin: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for SetPausedAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [admin, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(admin)?;
+        ConfigAccount::check(config)?;
+
+        Ok(Self { admin, config })
+    }
+}
+
+pub struct SetPausedInstructionData {
+    pub paused: bool,
+}
+
+impl<'a> TryFrom<&'a [u8]> for SetPausedInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        match data {
+            [0] => Ok(Self { paused: false }),
+            [1] => Ok(Self { paused: true }),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+
+pub struct SetPaused<'a> {
+    pub accounts: SetPausedAccounts<'a>,
+    pub instruction_data: SetPausedInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for SetPaused<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = SetPausedAccounts::try_from(accounts)?;
+        let instruction_data = SetPausedInstructionData::try_from(data)?;
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> SetPaused<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &7;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let mut data = self.accounts.config.try_borrow_mut_data()?;
+        let config = Config::load_mut(data.as_mut())?;
+
+        if config.admin != *self.accounts.admin.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+
+        config.set_paused(self.instruction_data.paused);
+
+        Ok(())
+    }
+}
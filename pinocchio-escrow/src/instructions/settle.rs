@@ -0,0 +1,252 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenAccount, TokenProgram};
+use crate::state::{AuctionEscrow, Bid};
+
+pub struct SettleAccounts<'a> {
+    pub cranker: &'a AccountInfo,
+    pub maker: &'a AccountInfo,
+    pub auction: &'a AccountInfo,
+    pub mint_a: &'a AccountInfo,
+    pub mint_b: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    /// The leading bidder, or the maker when nobody met the reserve
+    pub winner: &'a AccountInfo,
+    pub winner_ata_a: &'a AccountInfo,
+    /// Unused when there is no winning bid
+    pub bid: &'a AccountInfo,
+    pub bid_vault: &'a AccountInfo,
+    pub maker_ata_b: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    /// Token program owning mint A, which the lot vault lives under
+    pub token_program_a: &'a AccountInfo,
+    /// Token program owning mint B, which the bid vaults live under
+    pub token_program_b: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for SettleAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [cranker, maker, auction, mint_a, mint_b, vault, winner, winner_ata_a, bid, bid_vault, maker_ata_b, system_program, token_program_a, token_program_b, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(cranker)?;
+        ProgramAccount::check_len(auction, AuctionEscrow::LEN)?;
+        MintAccount::check(mint_a)?;
+        MintAccount::check(mint_b)?;
+        TokenProgram::check(token_program_a, mint_a)?;
+        TokenProgram::check(token_program_b, mint_b)?;
+        AssociatedTokenAccount::check(vault, auction, mint_a, token_program_a)?;
+
+        Ok(Self {
+            cranker,
+            maker,
+            auction,
+            mint_a,
+            mint_b,
+            vault,
+            winner,
+            winner_ata_a,
+            bid,
+            bid_vault,
+            maker_ata_b,
+            system_program,
+            token_program_a,
+            token_program_b,
+        })
+    }
+}
+
+pub struct Settle<'a> {
+    pub accounts: SettleAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for Settle<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = SettleAccounts::try_from(accounts)?;
+
+        AssociatedTokenAccount::init_if_needed(
+            accounts.winner_ata_a,
+            accounts.mint_a,
+            accounts.cranker,
+            accounts.winner,
+            accounts.system_program,
+            accounts.token_program_a,
+        )?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> Settle<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &21;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let data = self.accounts.auction.try_borrow_data()?;
+        let auction = AuctionEscrow::load(&data)?;
+
+        // Bids stay open until the end time, after which anyone may crank settlement
+        let clock = Clock::get()?;
+        if !auction.is_ended(clock.unix_timestamp) {
+            return Err(crate::errors::PinocchioError::EscrowNotExpired.into());
+        }
+
+        // Verify the maker matches the stored maker
+        if auction.maker != *self.accounts.maker.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+
+        // Verify the mints match the stored mints
+        if auction.mint_a != *self.accounts.mint_a.key() {
+            return Err(crate::errors::PinocchioError::InvalidMint.into());
+        }
+        if auction.mint_b != *self.accounts.mint_b.key() {
+            return Err(crate::errors::PinocchioError::InvalidMint.into());
+        }
+
+        let auction_key = create_program_address(
+            &[
+                b"auction",
+                self.accounts.maker.key(),
+                &auction.seed.to_le_bytes(),
+                &auction.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &auction_key != self.accounts.auction.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        // Without a bid at or above the reserve the lot goes back to the maker
+        let winner = if auction.has_bid() {
+            auction.highest_bidder
+        } else {
+            auction.maker
+        };
+        if winner != *self.accounts.winner.key() {
+            return Err(crate::errors::PinocchioError::InvalidTaker.into());
+        }
+
+        let seed_binding = auction.seed.to_le_bytes();
+        let bump_binding = auction.bump;
+        let auction_seeds = [
+            Seed::from(b"auction"),
+            Seed::from(self.accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+        let signers = [Signer::from(&auction_seeds)];
+
+        TransferChecked {
+            from: self.accounts.vault,
+            mint: self.accounts.mint_a,
+            to: self.accounts.winner_ata_a,
+            authority: self.accounts.auction,
+            amount: TokenAccount::get_amount(self.accounts.vault)?,
+            decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+            token_program: self.accounts.token_program_a.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        CloseAccount {
+            account: self.accounts.vault,
+            destination: self.accounts.maker,
+            authority: self.accounts.auction,
+            token_program: self.accounts.token_program_a.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        if auction.has_bid() {
+            self.pay_winning_bid()?;
+        }
+
+        drop(data);
+        ProgramAccount::close(self.accounts.auction, self.accounts.maker)?;
+
+        Ok(())
+    }
+
+    /// Sweeps the winning bid vault to the maker and returns its rent to the winner.
+    fn pay_winning_bid(&self) -> ProgramResult {
+        ProgramAccount::check_len(self.accounts.bid, Bid::LEN)?;
+        AssociatedTokenAccount::check(
+            self.accounts.bid_vault,
+            self.accounts.bid,
+            self.accounts.mint_b,
+            self.accounts.token_program_b,
+        )?;
+
+        let data = self.accounts.bid.try_borrow_data()?;
+        let bid = Bid::load(&data)?;
+        if bid.auction != *self.accounts.auction.key() || bid.bidder != *self.accounts.winner.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        let bid_key = create_program_address(
+            &[
+                b"bid",
+                self.accounts.auction.key(),
+                self.accounts.winner.key(),
+                &bid.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &bid_key != self.accounts.bid.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        AssociatedTokenAccount::init_if_needed(
+            self.accounts.maker_ata_b,
+            self.accounts.mint_b,
+            self.accounts.cranker,
+            self.accounts.maker,
+            self.accounts.system_program,
+            self.accounts.token_program_b,
+        )?;
+
+        let bump_binding = bid.bump;
+        let bid_seeds = [
+            Seed::from(b"bid"),
+            Seed::from(self.accounts.auction.key().as_ref()),
+            Seed::from(self.accounts.winner.key().as_ref()),
+            Seed::from(&bump_binding),
+        ];
+        let signers = [Signer::from(&bid_seeds)];
+
+        TransferChecked {
+            from: self.accounts.bid_vault,
+            mint: self.accounts.mint_b,
+            to: self.accounts.maker_ata_b,
+            authority: self.accounts.bid,
+            amount: TokenAccount::get_amount(self.accounts.bid_vault)?,
+            decimals: MintAccount::get_decimals(self.accounts.mint_b)?,
+            token_program: self.accounts.token_program_b.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        CloseAccount {
+            account: self.accounts.bid_vault,
+            destination: self.accounts.winner,
+            authority: self.accounts.bid,
+            token_program: self.accounts.token_program_b.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        drop(data);
+        ProgramAccount::close(self.accounts.bid, self.accounts.winner)
+    }
+}
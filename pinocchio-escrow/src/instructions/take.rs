@@ -2,17 +2,18 @@ use pinocchio::{
     account_info::AccountInfo,
     instruction::{Seed, Signer},
     program_error::ProgramError,
-    pubkey::create_program_address,
+    pubkey::{create_program_address, Pubkey},
     sysvars::{clock::Clock, Sysvar},
     ProgramResult,
 };
-use pinocchio_token::{
-    instructions::{CloseAccount, Transfer},
-    state::TokenAccount,
-};
+use std::mem::size_of;
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
 
-use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount};
-use crate::state::Escrow;
+use crate::{AssociatedTokenAccount, ConfigAccount, MintAccount, NativeAccount, ProgramAccount, SignerAccount, TakerPayment, TokenAccount, TokenProgram};
+use crate::hash::{verify_merkle_proof, HASH_BYTES};
+use crate::metadata::{Metadata, TOKEN_METADATA_ID};
+use crate::oracle::{PriceFeed, PYTH_RECEIVER_ID};
+use crate::state::{remaining_after_fill, Config, Escrow};
 
 pub struct TakeAccounts<'a> {
     pub taker: &'a AccountInfo,
@@ -21,18 +22,31 @@ pub struct TakeAccounts<'a> {
     pub mint_a: &'a AccountInfo,
     pub mint_b: &'a AccountInfo,
     pub vault: &'a AccountInfo,
+    /// The taker's mint A account, or the temporary unwrap account when mint A is native SOL
     pub taker_ata_a: &'a AccountInfo,
     pub taker_ata_b: &'a AccountInfo,
     pub maker_ata_b: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub treasury: &'a AccountInfo,
+    pub treasury_ata_b: &'a AccountInfo,
     pub system_program: &'a AccountInfo,
-    pub token_program: &'a AccountInfo,
+    /// Token program owning mint A, which the vault and the taker's mint A account live under
+    pub token_program_a: &'a AccountInfo,
+    /// Token program owning mint B, which every mint B payment goes through
+    pub token_program_b: &'a AccountInfo,
+    /// Price feed of an oracle escrow, passed ahead of any referrer
+    pub price_feed: Option<&'a AccountInfo>,
+    /// Metadata of the NFT offered to a collection bid, passed ahead of any referrer
+    pub metadata: Option<&'a AccountInfo>,
+    /// Optional referrer's mint B token account, or their wallet when mint B is native SOL
+    pub referrer: Option<&'a AccountInfo>,
 }
 
 impl<'a> TryFrom<&'a [AccountInfo]> for TakeAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [taker, maker, escrow, mint_a, mint_b, vault, taker_ata_a, taker_ata_b, maker_ata_b, system_program, token_program, _] =
+        let [taker, maker, escrow, mint_a, mint_b, vault, taker_ata_a, taker_ata_b, maker_ata_b, config, treasury, treasury_ata_b, system_program, token_program_a, token_program_b, _, remaining @ ..] =
             accounts
         else {
             return Err(ProgramError::NotEnoughAccountKeys);
@@ -42,8 +56,36 @@ impl<'a> TryFrom<&'a [AccountInfo]> for TakeAccounts<'a> {
         ProgramAccount::check(escrow)?;
         MintAccount::check(mint_a)?;
         MintAccount::check(mint_b)?;
-        AssociatedTokenAccount::check(taker_ata_b, taker, mint_b, token_program)?;
-        AssociatedTokenAccount::check(vault, escrow, mint_a, token_program)?;
+        TokenProgram::check(token_program_a, mint_a)?;
+        TokenProgram::check(token_program_b, mint_b)?;
+        // Native SOL is paid straight from the taker's lamports, so no ATA is needed
+        if !MintAccount::is_native(mint_b) {
+            AssociatedTokenAccount::check(taker_ata_b, taker, mint_b, token_program_b)?;
+        }
+        AssociatedTokenAccount::check(vault, escrow, mint_a, token_program_a)?;
+        ConfigAccount::check(config)?;
+
+        // Only the Pyth receiver owns price feeds and only Token Metadata owns metadata,
+        // which tells them apart from a referrer
+        let (price_feed, remaining) = match remaining.split_first() {
+            Some((price_feed, rest)) if price_feed.is_owned_by(&PYTH_RECEIVER_ID) => (Some(price_feed), rest),
+            _ => (None, remaining),
+        };
+        let (metadata, remaining) = match remaining.split_first() {
+            Some((metadata, rest)) if metadata.is_owned_by(&TOKEN_METADATA_ID) => (Some(metadata), rest),
+            _ => (None, remaining),
+        };
+        let referrer = remaining.first();
+        if let Some(referrer) = referrer {
+            if !MintAccount::is_native(mint_b) {
+                if !referrer.is_owned_by(token_program_b.key()) {
+                    return Err(crate::errors::PinocchioError::InvalidOwner.into());
+                }
+                if TokenAccount::get_mint(referrer)? != *mint_b.key() {
+                    return Err(crate::errors::PinocchioError::InvalidMint.into());
+                }
+            }
+        }
 
         Ok(Self {
             taker,
@@ -54,47 +96,125 @@ impl<'a> TryFrom<&'a [AccountInfo]> for TakeAccounts<'a> {
             taker_ata_a,
             taker_ata_b,
             maker_ata_b,
+            config,
+            treasury,
+            treasury_ata_b,
             vault,
             system_program,
-            token_program,
+            token_program_a,
+            token_program_b,
+            price_feed,
+            metadata,
+            referrer,
+        })
+    }
+}
+
+pub struct TakeInstructionData<'a> {
+    pub amount: u64,
+    /// Share of the protocol fee routed to the referrer, in basis points
+    pub referral_bps: u16,
+    /// Most mint B the taker will send, transfer fee gross-ups included
+    pub max_pay: u64,
+    /// Least mint A the taker will accept, net of any transfer fee
+    pub min_receive: u64,
+    /// Concatenated sibling hashes proving the taker is on the escrow allowlist
+    pub proof: &'a [u8],
+}
+
+impl<'a> TryFrom<&'a [u8]> for TakeInstructionData<'a> {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        const HEADER_LEN: usize = size_of::<u64>() * 3 + size_of::<u16>();
+        if data.len() < HEADER_LEN || (data.len() - HEADER_LEN) % HASH_BYTES != 0 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let amount = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let referral_bps = u16::from_le_bytes(data[8..10].try_into().unwrap());
+        let max_pay = u64::from_le_bytes(data[10..18].try_into().unwrap());
+        let min_receive = u64::from_le_bytes(data[18..26].try_into().unwrap());
+        let proof = &data[HEADER_LEN..];
+
+        if amount == 0 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            amount,
+            referral_bps,
+            max_pay,
+            min_receive,
+            proof,
         })
     }
 }
 
 pub struct Take<'a> {
     pub accounts: TakeAccounts<'a>,
+    pub instruction_data: TakeInstructionData<'a>,
 }
 
-impl<'a> TryFrom<&'a [AccountInfo]> for Take<'a> {
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Take<'a> {
     type Error = ProgramError;
 
-    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
         let accounts = TakeAccounts::try_from(accounts)?;
+        let instruction_data = TakeInstructionData::try_from(data)?;
 
-        AssociatedTokenAccount::init_if_needed(
-            accounts.taker_ata_a,
-            accounts.mint_a,
-            accounts.taker,
-            accounts.taker,
-            accounts.system_program,
-            accounts.token_program,
-        )?;
+        // Native SOL is received into a temporary account that is unwrapped right after
+        if MintAccount::is_native(accounts.mint_a) {
+            NativeAccount::init(
+                accounts.taker_ata_a,
+                accounts.mint_a,
+                accounts.taker,
+                accounts.token_program_a,
+            )?;
+        } else {
+            AssociatedTokenAccount::init_if_needed(
+                accounts.taker_ata_a,
+                accounts.mint_a,
+                accounts.taker,
+                accounts.taker,
+                accounts.system_program,
+                accounts.token_program_a,
+            )?;
+        }
 
-        AssociatedTokenAccount::init_if_needed(
-            accounts.maker_ata_b,
-            accounts.mint_b,
-            accounts.taker,
-            accounts.maker,
-            accounts.system_program,
-            accounts.token_program,
-        )?;
+        if !MintAccount::is_native(accounts.mint_b) {
+            AssociatedTokenAccount::init_if_needed(
+                accounts.maker_ata_b,
+                accounts.mint_b,
+                accounts.taker,
+                accounts.maker,
+                accounts.system_program,
+                accounts.token_program_b,
+            )?;
 
-        Ok(Self { accounts })
+            let fee_bps = Config::load(&accounts.config.try_borrow_data()?)?.fee_bps();
+            if fee_bps > 0 {
+                AssociatedTokenAccount::init_if_needed(
+                    accounts.treasury_ata_b,
+                    accounts.mint_b,
+                    accounts.taker,
+                    accounts.treasury,
+                    accounts.system_program,
+                    accounts.token_program_b,
+                )?;
+            }
+        }
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
     }
 }
 
 impl<'a> Take<'a> {
     pub const DISCRIMINATOR: &'a u8 = &1;
+    pub const CONFIG_INDEX: usize = 9;
 
     pub fn process(&mut self) -> ProgramResult {
         let data = self.accounts.escrow.try_borrow_data()?;
@@ -106,6 +226,22 @@ impl<'a> Take<'a> {
             return Err(crate::errors::PinocchioError::EscrowExpired.into());
         }
 
+        // Private escrows can only be taken by their designated taker
+        if !escrow.is_taker_allowed(self.accounts.taker.key()) {
+            return Err(crate::errors::PinocchioError::InvalidTaker.into());
+        }
+
+        // Allowlisted escrows require a merkle proof of the taker's key
+        if escrow.has_allowlist()
+            && !verify_merkle_proof(
+                self.instruction_data.proof,
+                &escrow.merkle_root,
+                self.accounts.taker.key(),
+            )
+        {
+            return Err(crate::errors::PinocchioError::InvalidProof.into());
+        }
+
         // Verify the maker account matches the stored maker
         if escrow.maker != *self.accounts.maker.key() {
             return Err(crate::errors::PinocchioError::InvalidOwner.into());
@@ -115,7 +251,9 @@ impl<'a> Take<'a> {
         if escrow.mint_a != *self.accounts.mint_a.key() {
             return Err(crate::errors::PinocchioError::InvalidMint.into());
         }
-        if escrow.mint_b != *self.accounts.mint_b.key() {
+        if escrow.has_collection() {
+            self.check_collection_nft(&escrow.collection)?;
+        } else if escrow.mint_b != *self.accounts.mint_b.key() {
             return Err(crate::errors::PinocchioError::InvalidMint.into());
         }
 
@@ -132,6 +270,39 @@ impl<'a> Take<'a> {
             return Err(crate::errors::PinocchioError::InvalidAddress.into());
         }
 
+        // The taker can fill at most the remaining price; auction and oracle prices move while
+        // a take is in flight, so an amount quoted above it fills in full at the current price
+        let price = self.price(escrow, clock.unix_timestamp)?;
+        let receive = if escrow.has_auction() || escrow.has_oracle() {
+            self.instruction_data.amount.min(price)
+        } else {
+            self.instruction_data.amount
+        };
+        if receive > price {
+            return Err(crate::errors::PinocchioError::InvalidAmount.into());
+        }
+        let is_final_fill = receive == price;
+
+        // The protocol fee comes out of the maker's mint B proceeds, and the referral out of the fee
+        let config_data = self.accounts.config.try_borrow_data()?;
+        let config = Config::load(&config_data)?;
+        if config.fee_recipient != *self.accounts.treasury.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+        let protocol_fee = config.fee_for(receive);
+        let referral_fee = match self.accounts.referrer {
+            Some(_) => config
+                .referral_fee_for(protocol_fee, self.instruction_data.referral_bps)
+                .ok_or(crate::errors::PinocchioError::InvalidFee)?,
+            None if self.instruction_data.referral_bps > 0 => {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            }
+            None => 0,
+        };
+        let fee = protocol_fee - referral_fee;
+        let maker_receive = receive - protocol_fee;
+        drop(config_data);
+
         let seed_binding = escrow.seed.to_le_bytes();
         let bump_binding = escrow.bump;
         let escrow_seeds = [
@@ -140,41 +311,172 @@ impl<'a> Take<'a> {
             Seed::from(&seed_binding),
             Seed::from(&bump_binding),
         ];
-        let signer = Signer::from(&escrow_seeds);
+        let signers = [Signer::from(&escrow_seeds)];
+
+        // The final fill sweeps whatever is left in the vault so it can be closed
+        let amount = if is_final_fill {
+            TokenAccount::get_amount(self.accounts.vault)?
+        } else {
+            escrow.deposit_at_price(receive, price)
+        };
 
-        let amount = TokenAccount::from_account_info(self.accounts.vault)?.amount();
-        
         // Validate amount is greater than 0
         if amount == 0 {
             return Err(crate::errors::PinocchioError::InvalidAmount.into());
         }
 
-        Transfer {
+        // Guard the taker against a reprice landing first and against transfer fees on either leg
+        let payment = self.payment();
+        let paid = payment
+            .gross(maker_receive, clock.epoch)?
+            .saturating_add(payment.gross(fee, clock.epoch)?)
+            .saturating_add(payment.gross(referral_fee, clock.epoch)?);
+        let received = MintAccount::amount_after_fee(self.accounts.mint_a, clock.epoch, amount)?;
+        if paid > self.instruction_data.max_pay || received < self.instruction_data.min_receive {
+            return Err(crate::errors::PinocchioError::SlippageExceeded.into());
+        }
+
+        TransferChecked {
             from: self.accounts.vault,
+            mint: self.accounts.mint_a,
             to: self.accounts.taker_ata_a,
             authority: self.accounts.escrow,
             amount,
+            decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+            token_program: self.accounts.token_program_a.key(),
         }
-        .invoke_signed(&[signer.clone()])?;
+        .invoke_signed(&signers)?;
 
-        CloseAccount {
-            account: self.accounts.vault,
-            destination: self.accounts.maker,
-            authority: self.accounts.escrow,
+        // Unwrap native SOL by closing the temporary account, so only this fill is paid out
+        if MintAccount::is_native(self.accounts.mint_a) {
+            NativeAccount::close(
+                self.accounts.taker_ata_a,
+                self.accounts.taker,
+                self.accounts.token_program_a,
+            )?;
         }
-        .invoke_signed(&[signer.clone()])?;
 
-        Transfer {
-            from: self.accounts.taker_ata_b,
-            to: self.accounts.maker_ata_b,
-            authority: self.accounts.taker,
-            amount: escrow.receive,
+        if is_final_fill {
+            CloseAccount {
+                account: self.accounts.vault,
+                destination: self.accounts.maker,
+                authority: self.accounts.escrow,
+                token_program: self.accounts.token_program_a.key(),
+            }
+            .invoke_signed(&signers)?;
         }
-        .invoke()?;
+
+        payment.pay(
+            self.accounts.maker,
+            self.accounts.maker_ata_b,
+            maker_receive,
+            clock.epoch,
+        )?;
+        if fee > 0 {
+            payment.pay(
+                self.accounts.treasury,
+                self.accounts.treasury_ata_b,
+                fee,
+                clock.epoch,
+            )?;
+        }
+        if let Some(referrer) = self.accounts.referrer.filter(|_| referral_fee > 0) {
+            payment.pay(referrer, referrer, referral_fee, clock.epoch)?;
+        }
+
+        let remaining_receive = remaining_after_fill(escrow.receive, receive, price);
+        let remaining_start_receive = remaining_after_fill(escrow.start_receive, receive, price);
+        let remaining_deposit = escrow.deposit.saturating_sub(amount);
+        let (auction_start, auction_end) = (escrow.auction_start, escrow.auction_end);
+        let bounty = escrow.bounty;
 
         drop(data);
-        ProgramAccount::close(self.accounts.escrow, self.accounts.taker)?;
 
+        if is_final_fill {
+            Self::close_filled(
+                self.accounts.escrow,
+                self.accounts.maker,
+                self.accounts.taker,
+                bounty,
+            )?;
+        } else {
+            let mut data = self.accounts.escrow.try_borrow_mut_data()?;
+            let escrow = Escrow::load_mut(data.as_mut())?;
+            escrow.set_receive(remaining_receive);
+            escrow.set_auction(remaining_start_receive, auction_start, auction_end);
+            escrow.set_deposit(remaining_deposit);
+        }
+
+        Ok(())
+    }
+
+    /// Closes a fully taken escrow. The crank bounty is the maker's, so only the escrow's
+    /// rent goes to the taker.
+    pub fn close_filled(
+        escrow: &AccountInfo,
+        maker: &AccountInfo,
+        taker: &AccountInfo,
+        bounty: u64,
+    ) -> ProgramResult {
+        if bounty > 0 {
+            let mut escrow_lamports = escrow.try_borrow_mut_lamports()?;
+            *escrow_lamports = escrow_lamports
+                .checked_sub(bounty)
+                .ok_or(ProgramError::InsufficientFunds)?;
+            *maker.try_borrow_mut_lamports()? += bounty;
+        }
+        ProgramAccount::close(escrow, taker)
+    }
+
+    /// Collection bids accept any NFT whose metadata carries the verified collection.
+    fn check_collection_nft(&self, collection: &Pubkey) -> ProgramResult {
+        if !MintAccount::is_nft(self.accounts.mint_b)? {
+            return Err(crate::errors::PinocchioError::InvalidNft.into());
+        }
+        let metadata = self
+            .accounts
+            .metadata
+            .ok_or(crate::errors::PinocchioError::InvalidNft)?;
+        if !Metadata::load(metadata, self.accounts.mint_b.key())?.is_verified_member_of(collection) {
+            return Err(crate::errors::PinocchioError::InvalidCollection.into());
+        }
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Price in mint B for the whole remaining deposit, read from the price feed for
+    /// oracle escrows and never below the maker's `receive` floor.
+    fn price(&self, escrow: &Escrow, current_timestamp: i64) -> Result<u64, ProgramError> {
+        if !escrow.has_oracle() {
+            return Ok(escrow.receive_at(current_timestamp));
+        }
+        let price_feed = self
+            .accounts
+            .price_feed
+            .filter(|price_feed| *price_feed.key() == escrow.price_feed)
+            .ok_or(crate::errors::PinocchioError::InvalidPriceFeed)?;
+        let feed = PriceFeed::load(price_feed)?;
+        feed.check_feed_id(&escrow.feed_id)?;
+        feed.check_fresh(current_timestamp, escrow.max_staleness)?;
+        feed.check_confidence(escrow.max_conf_bps())?;
+
+        let quote = feed
+            .quote(
+                escrow.deposit,
+                MintAccount::get_decimals(self.accounts.mint_a)?,
+                MintAccount::get_decimals(self.accounts.mint_b)?,
+                escrow.spread_bps(),
+            )
+            .ok_or(crate::errors::PinocchioError::InvalidPriceFeed)?;
+        Ok(quote.max(escrow.receive))
+    }
+
+    /// The taker's mint B payments to the maker, treasury and referrer.
+    fn payment(&self) -> TakerPayment<'a> {
+        TakerPayment {
+            taker: self.accounts.taker,
+            taker_ata_b: self.accounts.taker_ata_b,
+            mint_b: self.accounts.mint_b,
+            token_program_b: self.accounts.token_program_b,
+        }
+    }
+}
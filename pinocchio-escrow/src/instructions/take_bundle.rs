@@ -0,0 +1,225 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{AssociatedTokenAccount, ConfigAccount, MintAccount, ProgramAccount, SignerAccount, TokenAccount, TokenProgram};
+use crate::state::{BundleEscrow, Config};
+
+/// Accounts passed per mint A leg: `[mint_a, token_program, vault, taker_ata_a]`, with the
+/// token program owning that leg's mint.
+pub const TAKE_BUNDLE_LEG_A_ACCOUNTS: usize = 4;
+
+/// Accounts passed per mint B leg: `[mint_b, token_program, taker_ata_b, maker_ata_b,
+/// treasury_ata_b]`, with the token program owning that leg's mint.
+pub const TAKE_BUNDLE_LEG_B_ACCOUNTS: usize = 5;
+
+pub struct TakeBundleAccounts<'a> {
+    pub taker: &'a AccountInfo,
+    pub maker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub treasury: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    /// Every mint A leg then every mint B leg, in the order the escrow stores them
+    pub legs: &'a [AccountInfo],
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for TakeBundleAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [taker, maker, escrow, config, treasury, system_program, _, legs @ ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(taker)?;
+        ProgramAccount::check_len(escrow, BundleEscrow::LEN)?;
+        ConfigAccount::check(config)?;
+
+        Ok(Self {
+            taker,
+            maker,
+            escrow,
+            config,
+            treasury,
+            system_program,
+            legs,
+        })
+    }
+}
+
+pub struct TakeBundle<'a> {
+    pub accounts: TakeBundleAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for TakeBundle<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = TakeBundleAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> TakeBundle<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &23;
+    pub const CONFIG_INDEX: usize = 3;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let data = self.accounts.escrow.try_borrow_data()?;
+        let escrow = BundleEscrow::load(&data)?;
+
+        // Verify the maker account matches the stored maker
+        if escrow.maker != *self.accounts.maker.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+
+        let escrow_key = create_program_address(
+            &[
+                b"bundle",
+                self.accounts.maker.key(),
+                &escrow.seed.to_le_bytes(),
+                &escrow.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &escrow_key != self.accounts.escrow.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        // Every leg must be settled, so a missing leg fails the whole take
+        let legs_a = escrow.legs_a();
+        let legs_b = escrow.legs_b();
+        if self.accounts.legs.len()
+            != legs_a.len() * TAKE_BUNDLE_LEG_A_ACCOUNTS + legs_b.len() * TAKE_BUNDLE_LEG_B_ACCOUNTS
+        {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let (accounts_a, accounts_b) = self.accounts.legs.split_at(legs_a.len() * TAKE_BUNDLE_LEG_A_ACCOUNTS);
+
+        // The protocol fee comes out of each mint B leg, as it would on a take
+        let config_data = self.accounts.config.try_borrow_data()?;
+        let config = Config::load(&config_data)?;
+        if config.fee_recipient != *self.accounts.treasury.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        // The taker pays every mint B leg, grossed up so the maker and treasury receive their full share
+        let clock = Clock::get()?;
+        for (leg, accounts) in legs_b.iter().zip(accounts_b.chunks_exact(TAKE_BUNDLE_LEG_B_ACCOUNTS)) {
+            let [mint_b, token_program, taker_ata_b, maker_ata_b, treasury_ata_b] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            if leg.mint != *mint_b.key() {
+                return Err(crate::errors::PinocchioError::InvalidMint.into());
+            }
+            MintAccount::check(mint_b)?;
+            TokenProgram::check(token_program, mint_b)?;
+            AssociatedTokenAccount::check(taker_ata_b, self.accounts.taker, mint_b, token_program)?;
+            AssociatedTokenAccount::init_if_needed(
+                maker_ata_b,
+                mint_b,
+                self.accounts.taker,
+                self.accounts.maker,
+                self.accounts.system_program,
+                token_program,
+            )?;
+
+            let fee = config.fee_for(leg.amount);
+            let decimals = MintAccount::get_decimals(mint_b)?;
+            TransferChecked {
+                from: taker_ata_b,
+                mint: mint_b,
+                to: maker_ata_b,
+                authority: self.accounts.taker,
+                amount: MintAccount::amount_before_fee(mint_b, clock.epoch, leg.amount - fee)?,
+                decimals,
+                token_program: token_program.key(),
+            }
+            .invoke()?;
+
+            if fee > 0 {
+                AssociatedTokenAccount::init_if_needed(
+                    treasury_ata_b,
+                    mint_b,
+                    self.accounts.taker,
+                    self.accounts.treasury,
+                    self.accounts.system_program,
+                    token_program,
+                )?;
+                TransferChecked {
+                    from: taker_ata_b,
+                    mint: mint_b,
+                    to: treasury_ata_b,
+                    authority: self.accounts.taker,
+                    amount: MintAccount::amount_before_fee(mint_b, clock.epoch, fee)?,
+                    decimals,
+                    token_program: token_program.key(),
+                }
+                .invoke()?;
+            }
+        }
+        drop(config_data);
+
+        let seed_binding = escrow.seed.to_le_bytes();
+        let bump_binding = escrow.bump;
+        let escrow_seeds = [
+            Seed::from(b"bundle"),
+            Seed::from(self.accounts.maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+        let signers = [Signer::from(&escrow_seeds)];
+
+        // Then every mint A vault is swept to the taker and closed
+        for (leg, accounts) in legs_a.iter().zip(accounts_a.chunks_exact(TAKE_BUNDLE_LEG_A_ACCOUNTS)) {
+            let [mint_a, token_program, vault, taker_ata_a] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            if leg.mint != *mint_a.key() {
+                return Err(crate::errors::PinocchioError::InvalidMint.into());
+            }
+            TokenProgram::check(token_program, mint_a)?;
+            AssociatedTokenAccount::check(vault, self.accounts.escrow, mint_a, token_program)?;
+            AssociatedTokenAccount::init_if_needed(
+                taker_ata_a,
+                mint_a,
+                self.accounts.taker,
+                self.accounts.taker,
+                self.accounts.system_program,
+                token_program,
+            )?;
+
+            TransferChecked {
+                from: vault,
+                mint: mint_a,
+                to: taker_ata_a,
+                authority: self.accounts.escrow,
+                amount: TokenAccount::get_amount(vault)?,
+                decimals: MintAccount::get_decimals(mint_a)?,
+                token_program: token_program.key(),
+            }
+            .invoke_signed(&signers)?;
+
+            CloseAccount {
+                account: vault,
+                destination: self.accounts.maker,
+                authority: self.accounts.escrow,
+                token_program: token_program.key(),
+            }
+            .invoke_signed(&signers)?;
+        }
+
+        drop(data);
+        ProgramAccount::close(self.accounts.escrow, self.accounts.maker)?;
+
+        Ok(())
+    }
+}
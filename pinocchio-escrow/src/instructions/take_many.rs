@@ -0,0 +1,391 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    cpi::{set_return_data, MAX_RETURN_DATA},
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use std::mem::size_of;
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{AssociatedTokenAccount, ConfigAccount, MintAccount, NativeAccount, ProgramAccount, SignerAccount, Take, TakerPayment, TokenAccount, TokenProgram};
+use crate::state::{remaining_after_fill, Config, Escrow};
+
+/// Accounts passed per escrow: `[maker, escrow, vault, maker_ata_b]`.
+pub const TAKE_MANY_GROUP_ACCOUNTS: usize = 4;
+
+/// Bytes reported per escrow, in the order given: mint B owed and mint A released, both
+/// zero for an escrow that was skipped or left untouched.
+pub const TAKE_MANY_FILL_LEN: usize = size_of::<u64>() * 2;
+
+/// Most escrows one `TakeMany` can sweep, bounded by the return data reporting their fills.
+pub const MAX_TAKE_MANY_ESCROWS: usize = MAX_RETURN_DATA / TAKE_MANY_FILL_LEN;
+
+pub struct TakeManyAccounts<'a> {
+    pub taker: &'a AccountInfo,
+    pub mint_a: &'a AccountInfo,
+    pub mint_b: &'a AccountInfo,
+    /// The taker's mint A account, or the temporary unwrap account when mint A is native SOL
+    pub taker_ata_a: &'a AccountInfo,
+    pub taker_ata_b: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub treasury: &'a AccountInfo,
+    pub treasury_ata_b: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    /// Token program owning mint A, which every vault and the taker's mint A account live under
+    pub token_program_a: &'a AccountInfo,
+    /// Token program owning mint B, which every mint B payment goes through
+    pub token_program_b: &'a AccountInfo,
+    /// `[maker, escrow, vault, maker_ata_b]` for each escrow, filled in order
+    pub groups: &'a [AccountInfo],
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for TakeManyAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [taker, mint_a, mint_b, taker_ata_a, taker_ata_b, config, treasury, treasury_ata_b, system_program, token_program_a, token_program_b, _, groups @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(taker)?;
+        MintAccount::check(mint_a)?;
+        MintAccount::check(mint_b)?;
+        TokenProgram::check(token_program_a, mint_a)?;
+        TokenProgram::check(token_program_b, mint_b)?;
+        // Native SOL is paid straight from the taker's lamports, so no ATA is needed
+        if !MintAccount::is_native(mint_b) {
+            AssociatedTokenAccount::check(taker_ata_b, taker, mint_b, token_program_b)?;
+        }
+        ConfigAccount::check(config)?;
+
+        if groups.is_empty()
+            || groups.len() % TAKE_MANY_GROUP_ACCOUNTS != 0
+            || groups.len() / TAKE_MANY_GROUP_ACCOUNTS > MAX_TAKE_MANY_ESCROWS
+        {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        Ok(Self {
+            taker,
+            mint_a,
+            mint_b,
+            taker_ata_a,
+            taker_ata_b,
+            config,
+            treasury,
+            treasury_ata_b,
+            system_program,
+            token_program_a,
+            token_program_b,
+            groups,
+        })
+    }
+}
+
+pub struct TakeManyInstructionData {
+    /// Most mint B the taker will send across every fill, fees and transfer fee gross-ups included
+    pub budget: u64,
+    /// Least mint A the taker will accept across every fill, net of any transfer fee
+    pub min_receive: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for TakeManyInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let budget = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let min_receive = u64::from_le_bytes(data[8..16].try_into().unwrap());
+
+        if budget == 0 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self { budget, min_receive })
+    }
+}
+
+/// Sweeps several escrows selling the same pair within one mint B budget.
+///
+/// Escrows whose state keeps this sweep from taking them are skipped and report a zero fill:
+/// closed, expired, reserved for another taker, allowlisted, oracle-priced or collection bids.
+/// Everything else is fixed at Make and visible before sending, so a group whose maker, mints,
+/// escrow address or vault don't match fails the whole sweep.
+pub struct TakeMany<'a> {
+    pub accounts: TakeManyAccounts<'a>,
+    pub instruction_data: TakeManyInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for TakeMany<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = TakeManyAccounts::try_from(accounts)?;
+        let instruction_data = TakeManyInstructionData::try_from(data)?;
+
+        // Native SOL is received into a temporary account that is unwrapped after the sweep
+        if MintAccount::is_native(accounts.mint_a) {
+            NativeAccount::init(
+                accounts.taker_ata_a,
+                accounts.mint_a,
+                accounts.taker,
+                accounts.token_program_a,
+            )?;
+        } else {
+            AssociatedTokenAccount::init_if_needed(
+                accounts.taker_ata_a,
+                accounts.mint_a,
+                accounts.taker,
+                accounts.taker,
+                accounts.system_program,
+                accounts.token_program_a,
+            )?;
+        }
+
+        if !MintAccount::is_native(accounts.mint_b) {
+            let fee_bps = Config::load(&accounts.config.try_borrow_data()?)?.fee_bps();
+            if fee_bps > 0 {
+                AssociatedTokenAccount::init_if_needed(
+                    accounts.treasury_ata_b,
+                    accounts.mint_b,
+                    accounts.taker,
+                    accounts.treasury,
+                    accounts.system_program,
+                    accounts.token_program_b,
+                )?;
+            }
+        }
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> TakeMany<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &28;
+    pub const CONFIG_INDEX: usize = 5;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let clock = Clock::get()?;
+
+        let config_data = self.accounts.config.try_borrow_data()?;
+        let config = Config::load(&config_data)?;
+        if config.fee_recipient != *self.accounts.treasury.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        // Escrows fill in the order given until the budget runs out; later ones are left untouched
+        let mut budget = self.instruction_data.budget;
+        let mut received = 0u64;
+        let mut fills = [0u8; MAX_RETURN_DATA];
+        let mut fills_len = 0;
+        for group in self.accounts.groups.chunks_exact(TAKE_MANY_GROUP_ACCOUNTS) {
+            let [maker, escrow, vault, maker_ata_b] = group else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+
+            let (receive, amount, paid) = if budget > 0 {
+                self.fill(config, maker, escrow, vault, maker_ata_b, budget, clock.epoch, clock.unix_timestamp)?
+            } else {
+                (0, 0, 0)
+            };
+            budget -= paid;
+            received = received.saturating_add(MintAccount::amount_after_fee(self.accounts.mint_a, clock.epoch, amount)?);
+
+            fills[fills_len..fills_len + 8].copy_from_slice(&receive.to_le_bytes());
+            fills[fills_len + 8..fills_len + TAKE_MANY_FILL_LEN].copy_from_slice(&amount.to_le_bytes());
+            fills_len += TAKE_MANY_FILL_LEN;
+        }
+
+        if received < self.instruction_data.min_receive {
+            return Err(crate::errors::PinocchioError::SlippageExceeded.into());
+        }
+
+        // Unwrap native SOL by closing the temporary account, so only this sweep is paid out
+        if MintAccount::is_native(self.accounts.mint_a) {
+            NativeAccount::close(
+                self.accounts.taker_ata_a,
+                self.accounts.taker,
+                self.accounts.token_program_a,
+            )?;
+        }
+
+        set_return_data(&fills[..fills_len]);
+
+        Ok(())
+    }
+
+    /// Fills one escrow as far as `budget` allows, returning the mint B owed, the mint A
+    /// released and the mint B actually sent, all zero when the escrow can't be taken.
+    #[allow(clippy::too_many_arguments)]
+    fn fill(
+        &self,
+        config: &Config,
+        maker: &AccountInfo,
+        escrow_account: &AccountInfo,
+        vault: &AccountInfo,
+        maker_ata_b: &AccountInfo,
+        budget: u64,
+        epoch: u64,
+        current_timestamp: i64,
+    ) -> Result<(u64, u64, u64), ProgramError> {
+        // Another taker may have filled or closed an escrow since the sweep was built, so one
+        // that can't be taken any more is skipped rather than failing the whole sweep
+        if ProgramAccount::check(escrow_account).is_err() {
+            return Ok((0, 0, 0));
+        }
+
+        let data = escrow_account.try_borrow_data()?;
+        let escrow = Escrow::load(&data)?;
+
+        // Allowlist proofs, price feeds and NFT metadata are per escrow, which a batch has no room for
+        if escrow.is_expired(current_timestamp)
+            || !escrow.is_taker_allowed(self.accounts.taker.key())
+            || escrow.has_allowlist()
+            || escrow.has_oracle()
+            || escrow.has_collection()
+        {
+            return Ok((0, 0, 0));
+        }
+
+        // Verify the maker account matches the stored maker
+        if escrow.maker != *maker.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+
+        // Every escrow must sell the same pair
+        if escrow.mint_a != *self.accounts.mint_a.key() || escrow.mint_b != *self.accounts.mint_b.key() {
+            return Err(crate::errors::PinocchioError::InvalidMint.into());
+        }
+
+        let escrow_key = create_program_address(
+            &[b"escrow", maker.key(), &escrow.seed.to_le_bytes(), &escrow.bump],
+            &crate::ID,
+        )?;
+        if &escrow_key != escrow_account.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+        AssociatedTokenAccount::check(vault, escrow_account, self.accounts.mint_a, self.accounts.token_program_a)?;
+
+        let price = escrow.receive_at(current_timestamp);
+        let (receive, paid) = self.receive_within(config, budget.min(price), budget, epoch)?;
+        let is_final_fill = receive == price;
+        let amount = if is_final_fill {
+            TokenAccount::get_amount(vault)?
+        } else {
+            escrow.deposit_at_price(receive, price)
+        };
+
+        // What's left of the budget can't buy a single unit of this escrow
+        if amount == 0 {
+            return Ok((0, 0, 0));
+        }
+
+        if !MintAccount::is_native(self.accounts.mint_b) {
+            AssociatedTokenAccount::init_if_needed(
+                maker_ata_b,
+                self.accounts.mint_b,
+                self.accounts.taker,
+                maker,
+                self.accounts.system_program,
+                self.accounts.token_program_b,
+            )?;
+        }
+
+        let seed_binding = escrow.seed.to_le_bytes();
+        let bump_binding = escrow.bump;
+        let escrow_seeds = [
+            Seed::from(b"escrow"),
+            Seed::from(maker.key().as_ref()),
+            Seed::from(&seed_binding),
+            Seed::from(&bump_binding),
+        ];
+        let signers = [Signer::from(&escrow_seeds)];
+
+        TransferChecked {
+            from: vault,
+            mint: self.accounts.mint_a,
+            to: self.accounts.taker_ata_a,
+            authority: escrow_account,
+            amount,
+            decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+            token_program: self.accounts.token_program_a.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        if is_final_fill {
+            CloseAccount {
+                account: vault,
+                destination: maker,
+                authority: escrow_account,
+                token_program: self.accounts.token_program_a.key(),
+            }
+            .invoke_signed(&signers)?;
+        }
+
+        // The protocol fee comes out of the maker's mint B proceeds, as on a single take
+        let fee = config.fee_for(receive);
+        let payment = self.payment();
+        payment.pay(maker, maker_ata_b, receive - fee, epoch)?;
+        if fee > 0 {
+            payment.pay(self.accounts.treasury, self.accounts.treasury_ata_b, fee, epoch)?;
+        }
+
+        let remaining_receive = remaining_after_fill(escrow.receive, receive, price);
+        let remaining_start_receive = remaining_after_fill(escrow.start_receive, receive, price);
+        let remaining_deposit = escrow.deposit.saturating_sub(amount);
+        let (auction_start, auction_end) = (escrow.auction_start, escrow.auction_end);
+        let bounty = escrow.bounty;
+
+        drop(data);
+
+        if is_final_fill {
+            Take::close_filled(escrow_account, maker, self.accounts.taker, bounty)?;
+        } else {
+            let mut data = escrow_account.try_borrow_mut_data()?;
+            let escrow = Escrow::load_mut(data.as_mut())?;
+            escrow.set_receive(remaining_receive);
+            escrow.set_auction(remaining_start_receive, auction_start, auction_end);
+            escrow.set_deposit(remaining_deposit);
+        }
+
+        Ok((receive, amount, paid))
+    }
+
+    /// Largest fill of at most `receive` whose mint B, protocol fee and transfer fee gross-ups
+    /// included, fits in `budget`, along with what it costs the taker.
+    fn receive_within(&self, config: &Config, mut receive: u64, budget: u64, epoch: u64) -> Result<(u64, u64), ProgramError> {
+        loop {
+            let fee = config.fee_for(receive);
+            let payment = self.payment();
+            let paid = payment
+                .gross(receive - fee, epoch)?
+                .saturating_add(payment.gross(fee, epoch)?);
+            if paid <= budget {
+                return Ok((receive, paid));
+            }
+            // Transfer fees pushed the cost over budget; shrink by the overshoot and retry
+            receive = receive.saturating_sub(paid - budget);
+        }
+    }
+
+    /// The taker's mint B payments to each maker and the treasury.
+    fn payment(&self) -> TakerPayment<'a> {
+        TakerPayment {
+            taker: self.accounts.taker,
+            taker_ata_b: self.accounts.taker_ata_b,
+            mint_b: self.accounts.mint_b,
+            token_program_b: self.accounts.token_program_b,
+        }
+    }
+}
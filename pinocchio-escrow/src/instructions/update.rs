@@ -0,0 +1,259 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+use std::mem::size_of;
+use pinocchio_system::instructions::Transfer as SystemTransfer;
+use pinocchio_token_2022::instructions::{SyncNative, TransferChecked};
+
+use crate::{AssociatedTokenAccount, MintAccount, NativeAccount, ProgramAccount, SignerAccount, TokenProgram};
+use crate::state::Escrow;
+
+pub struct UpdateAccounts<'a> {
+    pub maker: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    pub mint_a: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    /// The maker's mint A account, or the temporary unwrap account when withdrawing native SOL
+    pub maker_ata_a: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UpdateAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [maker, escrow, mint_a, vault, maker_ata_a, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(maker)?;
+        ProgramAccount::check(escrow)?;
+        MintAccount::check(mint_a)?;
+        TokenProgram::check(token_program, mint_a)?;
+        AssociatedTokenAccount::check(vault, escrow, mint_a, token_program)?;
+
+        Ok(Self {
+            maker,
+            escrow,
+            mint_a,
+            vault,
+            maker_ata_a,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct UpdateInstructionData {
+    pub receive: u64,
+    pub expiry: i64,
+    /// Amount of mint A to add to the vault
+    pub deposit: u64,
+    /// Amount of mint A to take back out of the vault
+    pub withdraw: u64,
+}
+
+impl<'a> TryFrom<&'a [u8]> for UpdateInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<u64>() * 3 + size_of::<i64>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let receive = u64::from_le_bytes(data[0..8].try_into().unwrap());
+        let expiry = i64::from_le_bytes(data[8..16].try_into().unwrap());
+        let deposit = u64::from_le_bytes(data[16..24].try_into().unwrap());
+        let withdraw = u64::from_le_bytes(data[24..32].try_into().unwrap());
+
+        // Depositing and withdrawing in the same update is ambiguous
+        if receive == 0 || (deposit > 0 && withdraw > 0) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            receive,
+            expiry,
+            deposit,
+            withdraw,
+        })
+    }
+}
+
+pub struct Update<'a> {
+    pub accounts: UpdateAccounts<'a>,
+    pub instruction_data: UpdateInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Update<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = UpdateAccounts::try_from(accounts)?;
+        let instruction_data = UpdateInstructionData::try_from(data)?;
+
+        // Native SOL is withdrawn into a temporary account that is unwrapped right after
+        if instruction_data.withdraw > 0 && MintAccount::is_native(accounts.mint_a) {
+            NativeAccount::init(
+                accounts.maker_ata_a,
+                accounts.mint_a,
+                accounts.maker,
+                accounts.token_program,
+            )?;
+        } else if instruction_data.withdraw > 0 {
+            AssociatedTokenAccount::init_if_needed(
+                accounts.maker_ata_a,
+                accounts.mint_a,
+                accounts.maker,
+                accounts.maker,
+                accounts.system_program,
+                accounts.token_program,
+            )?;
+        } else if instruction_data.deposit > 0 && !MintAccount::is_native(accounts.mint_a) {
+            AssociatedTokenAccount::check(
+                accounts.maker_ata_a,
+                accounts.maker,
+                accounts.mint_a,
+                accounts.token_program,
+            )?;
+        }
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> Update<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &4;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let data = self.accounts.escrow.try_borrow_data()?;
+        let escrow = Escrow::load(&data)?;
+
+        // Verify the maker account matches the stored maker
+        if escrow.maker != *self.accounts.maker.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+
+        // Verify the mint matches the stored mint
+        if escrow.mint_a != *self.accounts.mint_a.key() {
+            return Err(crate::errors::PinocchioError::InvalidMint.into());
+        }
+
+        let escrow_key = create_program_address(
+            &[
+                b"escrow",
+                self.accounts.maker.key(),
+                &escrow.seed.to_le_bytes(),
+                &escrow.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &escrow_key != self.accounts.escrow.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        // Mint A may withhold a transfer fee, so only credit what lands in the vault
+        let clock = Clock::get()?;
+        let credited = MintAccount::amount_after_fee(
+            self.accounts.mint_a,
+            clock.epoch,
+            self.instruction_data.deposit,
+        )?;
+
+        // The escrow must keep something to sell; a full withdrawal is a refund
+        let deposit = escrow
+            .deposit
+            .checked_add(credited)
+            .and_then(|deposit| deposit.checked_sub(self.instruction_data.withdraw))
+            .filter(|deposit| *deposit > 0)
+            .ok_or(crate::errors::PinocchioError::InvalidAmount)?;
+
+        let seed_binding = escrow.seed.to_le_bytes();
+        let bump_binding = escrow.bump;
+
+        drop(data);
+
+        if self.instruction_data.deposit > 0 {
+            self.deposit_tokens()?;
+        }
+
+        if self.instruction_data.withdraw > 0 {
+            let escrow_seeds = [
+                Seed::from(b"escrow"),
+                Seed::from(self.accounts.maker.key().as_ref()),
+                Seed::from(&seed_binding),
+                Seed::from(&bump_binding),
+            ];
+            let signers = [Signer::from(&escrow_seeds)];
+
+            TransferChecked {
+                from: self.accounts.vault,
+                mint: self.accounts.mint_a,
+                to: self.accounts.maker_ata_a,
+                authority: self.accounts.escrow,
+                amount: self.instruction_data.withdraw,
+                decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+                token_program: self.accounts.token_program.key(),
+            }
+            .invoke_signed(&signers)?;
+
+            // Unwrap native SOL by closing the temporary account, so only the withdrawal is paid out
+            if MintAccount::is_native(self.accounts.mint_a) {
+                NativeAccount::close(
+                    self.accounts.maker_ata_a,
+                    self.accounts.maker,
+                    self.accounts.token_program,
+                )?;
+            }
+        }
+
+        let mut data = self.accounts.escrow.try_borrow_mut_data()?;
+        let escrow = Escrow::load_mut(data.as_mut())?;
+        // Repricing replaces any auction schedule with the new fixed price
+        escrow.set_receive(self.instruction_data.receive);
+        escrow.set_auction(0, 0, 0);
+        escrow.set_expiry(self.instruction_data.expiry);
+        escrow.set_deposit(deposit);
+
+        Ok(())
+    }
+
+    fn deposit_tokens(&self) -> ProgramResult {
+        if MintAccount::is_native(self.accounts.mint_a) {
+            SystemTransfer {
+                from: self.accounts.maker,
+                to: self.accounts.vault,
+                lamports: self.instruction_data.deposit,
+            }
+            .invoke()?;
+
+            SyncNative {
+                native_token: self.accounts.vault,
+                token_program: self.accounts.token_program.key(),
+            }
+            .invoke()
+        } else {
+            TransferChecked {
+                from: self.accounts.maker_ata_a,
+                mint: self.accounts.mint_a,
+                to: self.accounts.vault,
+                authority: self.accounts.maker,
+                amount: self.instruction_data.deposit,
+                decimals: MintAccount::get_decimals(self.accounts.mint_a)?,
+                token_program: self.accounts.token_program.key(),
+            }
+            .invoke()
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use pinocchio::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult,
+};
+use std::mem::size_of;
+
+use crate::{ConfigAccount, SignerAccount};
+use crate::state::{Config, MAX_BASIS_POINTS};
+
+pub struct UpdateConfigAccounts<'a> {
+    pub admin: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UpdateConfigAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [admin, config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        SignerAccount::check(admin)?;
+        ConfigAccount::check(config)?;
+
+        Ok(Self { admin, config })
+    }
+}
+
+pub struct UpdateConfigInstructionData {
+    pub admin: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub fee_bps: u16,
+    pub max_referral_bps: u16,
+}
+
+impl<'a> TryFrom<&'a [u8]> for UpdateConfigInstructionData {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != size_of::<Pubkey>() * 2 + size_of::<u16>() * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let admin: Pubkey = data[0..32].try_into().unwrap();
+        let fee_recipient: Pubkey = data[32..64].try_into().unwrap();
+        let fee_bps = u16::from_le_bytes(data[64..66].try_into().unwrap());
+        let max_referral_bps = u16::from_le_bytes(data[66..68].try_into().unwrap());
+
+        // The referral is a share of the protocol fee, which is a share of the mint B leg
+        if fee_bps as u64 > MAX_BASIS_POINTS || max_referral_bps as u64 > MAX_BASIS_POINTS {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            admin,
+            fee_recipient,
+            fee_bps,
+            max_referral_bps,
+        })
+    }
+}
+
+pub struct UpdateConfig<'a> {
+    pub accounts: UpdateConfigAccounts<'a>,
+    pub instruction_data: UpdateConfigInstructionData,
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for UpdateConfig<'a> {
+    type Error = ProgramError;
+
+    fn try_from((data, accounts): (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let accounts = UpdateConfigAccounts::try_from(accounts)?;
+        let instruction_data = UpdateConfigInstructionData::try_from(data)?;
+
+        Ok(Self {
+            accounts,
+            instruction_data,
+        })
+    }
+}
+
+impl<'a> UpdateConfig<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &6;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let mut data = self.accounts.config.try_borrow_mut_data()?;
+        let config = Config::load_mut(data.as_mut())?;
+
+        // Only the current admin may change the config, including handing it over
+        if config.admin != *self.accounts.admin.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+
+        config.set_admin(self.instruction_data.admin);
+        config.set_fee_recipient(self.instruction_data.fee_recipient);
+        config.set_fee_bps(self.instruction_data.fee_bps);
+        config.set_max_referral_bps(self.instruction_data.max_referral_bps);
+
+        Ok(())
+    }
+}
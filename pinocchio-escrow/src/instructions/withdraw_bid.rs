@@ -0,0 +1,147 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenAccount, TokenProgram};
+use crate::state::{AuctionEscrow, Bid};
+
+pub struct WithdrawBidAccounts<'a> {
+    pub bidder: &'a AccountInfo,
+    /// Already closed once the auction has been settled
+    pub auction: &'a AccountInfo,
+    pub bid: &'a AccountInfo,
+    pub mint_b: &'a AccountInfo,
+    pub bid_vault: &'a AccountInfo,
+    pub bidder_ata_b: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawBidAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [bidder, auction, bid, mint_b, bid_vault, bidder_ata_b, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(bidder)?;
+        ProgramAccount::check_len(bid, Bid::LEN)?;
+        MintAccount::check(mint_b)?;
+        TokenProgram::check(token_program, mint_b)?;
+        AssociatedTokenAccount::check(bid_vault, bid, mint_b, token_program)?;
+
+        Ok(Self {
+            bidder,
+            auction,
+            bid,
+            mint_b,
+            bid_vault,
+            bidder_ata_b,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct WithdrawBid<'a> {
+    pub accounts: WithdrawBidAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawBid<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = WithdrawBidAccounts::try_from(accounts)?;
+
+        AssociatedTokenAccount::init_if_needed(
+            accounts.bidder_ata_b,
+            accounts.mint_b,
+            accounts.bidder,
+            accounts.bidder,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> WithdrawBid<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &20;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let data = self.accounts.bid.try_borrow_data()?;
+        let bid = Bid::load(&data)?;
+
+        // Verify the bidder and auction match the stored ones
+        if bid.bidder != *self.accounts.bidder.key() {
+            return Err(crate::errors::PinocchioError::InvalidOwner.into());
+        }
+        if bid.auction != *self.accounts.auction.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        let bid_key = create_program_address(
+            &[
+                b"bid",
+                self.accounts.auction.key(),
+                self.accounts.bidder.key(),
+                &bid.bump,
+            ],
+            &crate::ID,
+        )?;
+        if &bid_key != self.accounts.bid.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        // The leading bid stays locked until settlement consumes it; once the auction
+        // is settled and closed every remaining bid was outbid
+        if ProgramAccount::check_len(self.accounts.auction, AuctionEscrow::LEN).is_ok() {
+            let auction_data = self.accounts.auction.try_borrow_data()?;
+            if AuctionEscrow::load(&auction_data)?.highest_bidder == *self.accounts.bidder.key() {
+                return Err(crate::errors::PinocchioError::LeadingBid.into());
+            }
+        }
+
+        let bump_binding = bid.bump;
+        let bid_seeds = [
+            Seed::from(b"bid"),
+            Seed::from(self.accounts.auction.key().as_ref()),
+            Seed::from(self.accounts.bidder.key().as_ref()),
+            Seed::from(&bump_binding),
+        ];
+        let signers = [Signer::from(&bid_seeds)];
+
+        TransferChecked {
+            from: self.accounts.bid_vault,
+            mint: self.accounts.mint_b,
+            to: self.accounts.bidder_ata_b,
+            authority: self.accounts.bid,
+            amount: TokenAccount::get_amount(self.accounts.bid_vault)?,
+            decimals: MintAccount::get_decimals(self.accounts.mint_b)?,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        CloseAccount {
+            account: self.accounts.bid_vault,
+            destination: self.accounts.bidder,
+            authority: self.accounts.bid,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        drop(data);
+        ProgramAccount::close(self.accounts.bid, self.accounts.bidder)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::create_program_address,
+    ProgramResult,
+};
+use pinocchio_token_2022::instructions::{CloseAccount, TransferChecked};
+
+use crate::{AssociatedTokenAccount, MintAccount, ProgramAccount, SignerAccount, TokenAccount, TokenProgram};
+use crate::state::Offer;
+
+pub struct WithdrawCounterAccounts<'a> {
+    pub taker: &'a AccountInfo,
+    pub offer: &'a AccountInfo,
+    pub mint_b: &'a AccountInfo,
+    pub offer_vault: &'a AccountInfo,
+    pub taker_ata_b: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawCounterAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [taker, offer, mint_b, offer_vault, taker_ata_b, system_program, token_program, _] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(taker)?;
+        ProgramAccount::check_len(offer, Offer::LEN)?;
+        MintAccount::check(mint_b)?;
+        TokenProgram::check(token_program, mint_b)?;
+        AssociatedTokenAccount::check(offer_vault, offer, mint_b, token_program)?;
+
+        Ok(Self {
+            taker,
+            offer,
+            mint_b,
+            offer_vault,
+            taker_ata_b,
+            system_program,
+            token_program,
+        })
+    }
+}
+
+pub struct WithdrawCounter<'a> {
+    pub accounts: WithdrawCounterAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawCounter<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let accounts = WithdrawCounterAccounts::try_from(accounts)?;
+        AssociatedTokenAccount::init_if_needed(
+            accounts.taker_ata_b,
+            accounts.mint_b,
+            accounts.taker,
+            accounts.taker,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> WithdrawCounter<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &27;
+
+    pub fn process(&mut self) -> ProgramResult {
+        let data = self.accounts.offer.try_borrow_data()?;
+        let offer = Offer::load(&data)?;
+
+        // Only the taker who made the offer can pull it, at any time and even once the
+        // escrow itself is gone
+        if offer.taker != *self.accounts.taker.key() {
+            return Err(crate::errors::PinocchioError::InvalidTaker.into());
+        }
+
+        let offer_key = create_program_address(
+            &[b"offer", &offer.escrow, self.accounts.taker.key(), &offer.bump],
+            &crate::ID,
+        )?;
+        if &offer_key != self.accounts.offer.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        let bump_binding = offer.bump;
+        let offer_seeds = [
+            Seed::from(b"offer"),
+            Seed::from(offer.escrow.as_ref()),
+            Seed::from(self.accounts.taker.key().as_ref()),
+            Seed::from(&bump_binding),
+        ];
+        let signers = [Signer::from(&offer_seeds)];
+
+        TransferChecked {
+            from: self.accounts.offer_vault,
+            mint: self.accounts.mint_b,
+            to: self.accounts.taker_ata_b,
+            authority: self.accounts.offer,
+            amount: TokenAccount::get_amount(self.accounts.offer_vault)?,
+            decimals: MintAccount::get_decimals(self.accounts.mint_b)?,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        CloseAccount {
+            account: self.accounts.offer_vault,
+            destination: self.accounts.taker,
+            authority: self.accounts.offer,
+            token_program: self.accounts.token_program.key(),
+        }
+        .invoke_signed(&signers)?;
+
+        drop(data);
+        ProgramAccount::close(self.accounts.offer, self.accounts.taker)?;
+
+        Ok(())
+    }
+}
@@ -13,6 +13,9 @@ pub use instructions::*;
 
 pub mod state;
 pub mod errors;
+pub mod hash;
+pub mod metadata;
+pub mod oracle;
 
 // #[cfg(target_os="solana")]
 // nostd_panic_handler!();
@@ -30,9 +33,91 @@ fn process_instruction(
     instruction_data: &[u8],
 ) -> ProgramResult {
     match instruction_data.split_first() {
-        Some((Make::DISCRIMINATOR, data)) => Make::try_from((data, accounts))?.process(),
-        Some((Take::DISCRIMINATOR, _)) => Take::try_from(accounts)?.process(),
+        Some((Make::DISCRIMINATOR, data)) => {
+            check_not_paused(accounts, Make::CONFIG_INDEX)?;
+            Make::try_from((data, accounts))?.process()
+        }
+        Some((Take::DISCRIMINATOR, data)) => {
+            check_not_paused(accounts, Take::CONFIG_INDEX)?;
+            Take::try_from((data, accounts))?.process()
+        }
         Some((Refund::DISCRIMINATOR, _)) => Refund::try_from(accounts)?.process(),
+        Some((ExpireRefund::DISCRIMINATOR, _)) => ExpireRefund::try_from(accounts)?.process(),
+        Some((Update::DISCRIMINATOR, data)) => Update::try_from((data, accounts))?.process(),
+        Some((InitializeConfig::DISCRIMINATOR, data)) => {
+            InitializeConfig::try_from((data, accounts))?.process()
+        }
+        Some((UpdateConfig::DISCRIMINATOR, data)) => UpdateConfig::try_from((data, accounts))?.process(),
+        Some((SetPaused::DISCRIMINATOR, data)) => SetPaused::try_from((data, accounts))?.process(),
+        Some((MakeArbiterEscrow::DISCRIMINATOR, data)) => {
+            check_not_paused(accounts, MakeArbiterEscrow::CONFIG_INDEX)?;
+            MakeArbiterEscrow::try_from((data, accounts))?.process()
+        }
+        Some((Release::DISCRIMINATOR, _)) => Release::try_from(accounts)?.process(),
+        Some((ArbiterRefund::DISCRIMINATOR, _)) => ArbiterRefund::try_from(accounts)?.process(),
+        Some((Dispute::DISCRIMINATOR, _)) => Dispute::try_from(accounts)?.process(),
+        Some((ReleaseMilestone::DISCRIMINATOR, data)) => {
+            ReleaseMilestone::try_from((data, accounts))?.process()
+        }
+        Some((MakeVestingEscrow::DISCRIMINATOR, data)) => {
+            check_not_paused(accounts, MakeVestingEscrow::CONFIG_INDEX)?;
+            MakeVestingEscrow::try_from((data, accounts))?.process()
+        }
+        Some((Claim::DISCRIMINATOR, _)) => Claim::try_from(accounts)?.process(),
+        Some((MakeHtlc::DISCRIMINATOR, data)) => {
+            check_not_paused(accounts, MakeHtlc::CONFIG_INDEX)?;
+            MakeHtlc::try_from((data, accounts))?.process()
+        }
+        Some((ClaimHtlc::DISCRIMINATOR, data)) => ClaimHtlc::try_from((data, accounts))?.process(),
+        Some((RefundHtlc::DISCRIMINATOR, _)) => RefundHtlc::try_from(accounts)?.process(),
+        Some((MakeAuction::DISCRIMINATOR, data)) => {
+            check_not_paused(accounts, MakeAuction::CONFIG_INDEX)?;
+            MakeAuction::try_from((data, accounts))?.process()
+        }
+        Some((PlaceBid::DISCRIMINATOR, data)) => {
+            check_not_paused(accounts, PlaceBid::CONFIG_INDEX)?;
+            PlaceBid::try_from((data, accounts))?.process()
+        }
+        Some((WithdrawBid::DISCRIMINATOR, _)) => WithdrawBid::try_from(accounts)?.process(),
+        Some((Settle::DISCRIMINATOR, _)) => Settle::try_from(accounts)?.process(),
+        Some((MakeBundle::DISCRIMINATOR, data)) => {
+            check_not_paused(accounts, MakeBundle::CONFIG_INDEX)?;
+            MakeBundle::try_from((data, accounts))?.process()
+        }
+        Some((TakeBundle::DISCRIMINATOR, _)) => {
+            check_not_paused(accounts, TakeBundle::CONFIG_INDEX)?;
+            TakeBundle::try_from(accounts)?.process()
+        }
+        Some((RefundBundle::DISCRIMINATOR, _)) => RefundBundle::try_from(accounts)?.process(),
+        Some((CounterOffer::DISCRIMINATOR, data)) => {
+            check_not_paused(accounts, CounterOffer::CONFIG_INDEX)?;
+            CounterOffer::try_from((data, accounts))?.process()
+        }
+        Some((AcceptCounter::DISCRIMINATOR, _)) => {
+            check_not_paused(accounts, AcceptCounter::CONFIG_INDEX)?;
+            AcceptCounter::try_from(accounts)?.process()
+        }
+        Some((WithdrawCounter::DISCRIMINATOR, _)) => WithdrawCounter::try_from(accounts)?.process(),
+        Some((TakeMany::DISCRIMINATOR, data)) => {
+            check_not_paused(accounts, TakeMany::CONFIG_INDEX)?;
+            TakeMany::try_from((data, accounts))?.process()
+        }
         _ => Err(ProgramError::InvalidInstructionData)
     }
-}
\ No newline at end of file
+}
+
+/// New trades are halted while the admin has paused the program; refunds never are.
+/// Each instruction that opens or fills a trade names the position of its config account
+/// in `CONFIG_INDEX`, so the pause is checked before anything else is parsed.
+fn check_not_paused(accounts: &[AccountInfo], config_index: usize) -> ProgramResult {
+    let config = accounts
+        .get(config_index)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    ConfigAccount::check(config)?;
+
+    if state::Config::load(&config.try_borrow_data()?)?.is_paused() {
+        return Err(errors::PinocchioError::ProgramPaused.into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::{find_program_address, Pubkey},
+};
+
+/// Metaplex Token Metadata program, owner of every NFT metadata account.
+pub const TOKEN_METADATA_ID: Pubkey = [
+    0x0b, 0x70, 0x65, 0xb1, 0xe3, 0xd1, 0x7c, 0x45,
+    0x38, 0x9d, 0x52, 0x7f, 0x6b, 0x04, 0xc3, 0xcd,
+    0x58, 0xb8, 0x6c, 0x73, 0x1a, 0xa0, 0xfd, 0xb5,
+    0x49, 0xb6, 0xd1, 0xbc, 0x03, 0xf8, 0x29, 0x46,
+]; // metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s
+
+/// `Key::MetadataV1`, the first byte of every metadata account.
+pub const KEY_METADATA_V1: u8 = 4;
+
+/// Size of a serialized `Creator`: address, verified flag and share.
+const CREATOR_LEN: usize = 32 + 1 + 1;
+
+/// The collection an NFT claims, trusted only once its authority has verified it.
+pub struct Collection {
+    pub verified: bool,
+    pub key: Pubkey,
+}
+
+/// The fields of a Metaplex `Metadata` account needed to check collection membership.
+pub struct Metadata {
+    pub mint: Pubkey,
+    pub collection: Option<Collection>,
+}
+
+impl Metadata {
+    /// Reads the canonical metadata account of `mint`.
+    pub fn load(account: &AccountInfo, mint: &Pubkey) -> Result<Self, ProgramError> {
+        if !account.is_owned_by(&TOKEN_METADATA_ID) {
+            return Err(crate::errors::PinocchioError::InvalidNft.into());
+        }
+
+        let (metadata_key, _) = find_program_address(
+            &[b"metadata", &TOKEN_METADATA_ID, mint],
+            &TOKEN_METADATA_ID,
+        );
+        if &metadata_key != account.key() {
+            return Err(crate::errors::PinocchioError::InvalidAddress.into());
+        }
+
+        let metadata = Self::parse(&account.try_borrow_data()?)
+            .ok_or(crate::errors::PinocchioError::InvalidNft)?;
+        if metadata.mint != *mint {
+            return Err(crate::errors::PinocchioError::InvalidNft.into());
+        }
+
+        Ok(metadata)
+    }
+
+    /// Walks the Borsh layout up to the collection field, skipping the variable-length
+    /// name, symbol, uri and creators along the way.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader { data, offset: 0 };
+
+        if reader.u8()? != KEY_METADATA_V1 {
+            return None;
+        }
+        reader.take(32)?; // update authority
+        let mint: Pubkey = reader.take(32)?.try_into().ok()?;
+        reader.string()?; // name
+        reader.string()?; // symbol
+        reader.string()?; // uri
+        reader.take(2)?; // seller fee basis points
+        if reader.option()? {
+            let creators = reader.u32()? as usize;
+            reader.take(creators.checked_mul(CREATOR_LEN)?)?;
+        }
+        reader.take(2)?; // primary sale happened, is mutable
+        if reader.option()? {
+            reader.take(1)?; // edition nonce
+        }
+        if reader.option()? {
+            reader.take(1)?; // token standard
+        }
+        let collection = if reader.option()? {
+            Some(Collection {
+                verified: reader.u8()? != 0,
+                key: reader.take(32)?.try_into().ok()?,
+            })
+        } else {
+            None
+        };
+
+        Some(Self { mint, collection })
+    }
+
+    /// Unverified collections can be claimed by anyone, so they never count.
+    #[inline(always)]
+    pub fn is_verified_member_of(&self, collection: &Pubkey) -> bool {
+        self.collection
+            .as_ref()
+            .is_some_and(|claimed| claimed.verified && claimed.key == *collection)
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.offset..self.offset.checked_add(len)?)?;
+        self.offset += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn string(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn option(&mut self) -> Option<bool> {
+        match self.u8()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::state::MAX_BASIS_POINTS;
+
+/// Pyth Solana receiver program, owner of every posted price update account.
+pub const PYTH_RECEIVER_ID: Pubkey = [
+    0x0c, 0xb7, 0xfa, 0xbb, 0x52, 0xf7, 0xa6, 0x48,
+    0xbb, 0x5b, 0x31, 0x7d, 0x9a, 0x01, 0x8b, 0x90,
+    0x57, 0xcb, 0x02, 0x47, 0x74, 0xfa, 0xfe, 0x01,
+    0xe6, 0xc4, 0xdf, 0x98, 0xcc, 0x38, 0x58, 0x81,
+]; // rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ
+
+/// Anchor discriminator of the receiver's `PriceUpdateV2` account.
+pub const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// `VerificationLevel::Full`; partially verified updates are rejected.
+pub const VERIFICATION_LEVEL_FULL: u8 = 1;
+
+/// Offset of the price message in a fully verified update, after the
+/// discriminator, write authority and one-byte verification level.
+const MESSAGE_OFFSET: usize = 8 + 32 + 1;
+
+/// Price of the feed's base asset in its quote asset, as `price * 10^exponent`.
+pub struct PriceFeed {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+}
+
+impl PriceFeed {
+    /// Reads a fully verified `PriceUpdateV2` account posted by the Pyth receiver.
+    pub fn load(account: &AccountInfo) -> Result<Self, ProgramError> {
+        if !account.is_owned_by(&PYTH_RECEIVER_ID) {
+            return Err(crate::errors::PinocchioError::InvalidPriceFeed.into());
+        }
+        Self::parse(&account.try_borrow_data()?)
+    }
+
+    /// Parses `PriceUpdateV2` account data, rejecting partial verification and non-positive prices.
+    pub fn parse(data: &[u8]) -> Result<Self, ProgramError> {
+        // feed_id(32) price(8) conf(8) exponent(4) publish_time(8)
+        let message = data
+            .get(MESSAGE_OFFSET..MESSAGE_OFFSET + 60)
+            .filter(|_| data[..8] == PRICE_UPDATE_V2_DISCRIMINATOR)
+            .filter(|_| data[40] == VERIFICATION_LEVEL_FULL)
+            .ok_or(crate::errors::PinocchioError::InvalidPriceFeed)?;
+
+        let feed = Self {
+            feed_id: message[0..32].try_into().unwrap(),
+            price: i64::from_le_bytes(message[32..40].try_into().unwrap()),
+            conf: u64::from_le_bytes(message[40..48].try_into().unwrap()),
+            exponent: i32::from_le_bytes(message[48..52].try_into().unwrap()),
+            publish_time: i64::from_le_bytes(message[52..60].try_into().unwrap()),
+        };
+        if feed.price <= 0 {
+            return Err(crate::errors::PinocchioError::InvalidPriceFeed.into());
+        }
+        Ok(feed)
+    }
+
+    /// Rejects updates for any feed other than `feed_id`.
+    pub fn check_feed_id(&self, feed_id: &[u8; 32]) -> Result<(), ProgramError> {
+        if self.feed_id != *feed_id {
+            return Err(crate::errors::PinocchioError::InvalidPriceFeed.into());
+        }
+        Ok(())
+    }
+
+    /// Rejects prices published more than `max_staleness` seconds before `current_timestamp`.
+    pub fn check_fresh(&self, current_timestamp: i64, max_staleness: u64) -> Result<(), ProgramError> {
+        if current_timestamp.saturating_sub(self.publish_time) as i128 > max_staleness as i128 {
+            return Err(crate::errors::PinocchioError::StalePrice.into());
+        }
+        Ok(())
+    }
+
+    /// Rejects prices whose confidence interval is wider than `max_conf_bps` of the price.
+    pub fn check_confidence(&self, max_conf_bps: u16) -> Result<(), ProgramError> {
+        if self.conf as u128 * MAX_BASIS_POINTS as u128 > self.price as u128 * max_conf_bps as u128 {
+            return Err(crate::errors::PinocchioError::PriceTooUncertain.into());
+        }
+        Ok(())
+    }
+
+    /// Mint B owed for `amount` of mint A at this price adjusted by `spread_bps`,
+    /// converted between the mints' decimals and rounded up in favor of the maker.
+    pub fn quote(&self, amount: u64, decimals_a: u8, decimals_b: u8, spread_bps: i16) -> Option<u64> {
+        let scale = self.exponent + decimals_b as i32 - decimals_a as i32;
+        let value = amount as u128 * self.price as u128;
+        let value = if scale >= 0 {
+            value.checked_mul(10u128.checked_pow(scale as u32)?)?
+        } else {
+            value.div_ceil(10u128.checked_pow(scale.unsigned_abs())?)
+        };
+        let adjusted = (MAX_BASIS_POINTS as i64 + spread_bps as i64) as u128;
+        let quote = value.checked_mul(adjusted)?.div_ceil(MAX_BASIS_POINTS as u128);
+        u64::try_from(quote).ok()
+    }
+}
@@ -11,7 +11,24 @@ pub struct Escrow {
     pub mint_a: Pubkey,
     pub mint_b: Pubkey,
     pub receive: u64,
+    pub deposit: u64,
     pub expiry: i64,
+    pub bounty: u64,
+    pub taker: Pubkey,
+    pub merkle_root: [u8; 32],
+    /// Dutch auction opening price for the remaining deposit; zero for a fixed price
+    pub start_receive: u64,
+    pub auction_start: i64,
+    pub auction_end: i64,
+    /// Price feed quoting mint A in mint B; zeroed for a fixed price
+    pub price_feed: Pubkey,
+    /// Pyth feed the price feed account must carry, since its write authority can post any feed
+    pub feed_id: [u8; 32],
+    pub max_staleness: u64,
+    pub max_conf_bps: [u8; 2],
+    pub spread_bps: [u8; 2],
+    /// Collection whose verified NFTs are accepted as mint B; zeroed for a fixed mint B
+    pub collection: Pubkey,
     pub bump: [u8; 1],
 }
 
@@ -22,7 +39,20 @@ impl Escrow {
         + size_of::<Pubkey>()
         + size_of::<Pubkey>()
         + size_of::<u64>()
+        + size_of::<u64>()
+        + size_of::<i64>()
+        + size_of::<u64>()
+        + size_of::<Pubkey>()
+        + size_of::<[u8; 32]>()
+        + size_of::<u64>()
+        + size_of::<i64>()
         + size_of::<i64>()
+        + size_of::<Pubkey>()
+        + size_of::<[u8; 32]>()
+        + size_of::<u64>()
+        + size_of::<[u8; 2]>()
+        + size_of::<[u8; 2]>()
+        + size_of::<Pubkey>()
         + size_of::<[u8; 1]>();
 
     #[inline(always)]
@@ -80,11 +110,63 @@ impl Escrow {
         self.receive = receive;
     }
 
+    #[inline(always)]
+    pub fn set_deposit(&mut self, deposit: u64) {
+        self.deposit = deposit;
+    }
+
     #[inline(always)]
     pub fn set_expiry(&mut self, expiry: i64) {
         self.expiry = expiry;
     }
 
+    #[inline(always)]
+    pub fn set_bounty(&mut self, bounty: u64) {
+        self.bounty = bounty;
+    }
+
+    #[inline(always)]
+    pub fn set_taker(&mut self, taker: Pubkey) {
+        self.taker = taker;
+    }
+
+    #[inline(always)]
+    pub fn set_merkle_root(&mut self, merkle_root: [u8; 32]) {
+        self.merkle_root = merkle_root;
+    }
+
+    #[inline(always)]
+    pub fn set_auction(&mut self, start_receive: u64, auction_start: i64, auction_end: i64) {
+        self.start_receive = start_receive;
+        self.auction_start = auction_start;
+        self.auction_end = auction_end;
+    }
+
+    #[inline(always)]
+    pub fn set_oracle(
+        &mut self,
+        price_feed: Pubkey,
+        feed_id: [u8; 32],
+        max_staleness: u64,
+        max_conf_bps: u16,
+        spread_bps: i16,
+    ) {
+        self.price_feed = price_feed;
+        self.feed_id = feed_id;
+        self.max_staleness = max_staleness;
+        self.max_conf_bps = max_conf_bps.to_le_bytes();
+        self.spread_bps = spread_bps.to_le_bytes();
+    }
+
+    /// Turns the escrow into a bid for any verified NFT of `collection`, so no single mint B is stored.
+    #[inline(always)]
+    pub fn set_collection(&mut self, collection: Pubkey) {
+        self.collection = collection;
+        if collection != [0; 32] {
+            self.mint_b = [0; 32];
+        }
+    }
+
     #[inline(always)]
     pub fn set_bump(&mut self, bump: [u8; 1]) {
         self.bump = bump;
@@ -96,6 +178,7 @@ impl Escrow {
     }
 
     #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
     pub fn set_inner(
         &mut self,
         seed: u64,
@@ -103,7 +186,11 @@ impl Escrow {
         mint_a: Pubkey,
         mint_b: Pubkey,
         receive: u64,
+        deposit: u64,
         expiry: i64,
+        bounty: u64,
+        taker: Pubkey,
+        merkle_root: [u8; 32],
         bump: [u8; 1],
     ) {
         self.discriminator = ESCROW_DISCRIMINATOR;
@@ -112,7 +199,20 @@ impl Escrow {
         self.mint_a = mint_a;
         self.mint_b = mint_b;
         self.receive = receive;
+        self.deposit = deposit;
         self.expiry = expiry;
+        self.bounty = bounty;
+        self.taker = taker;
+        self.merkle_root = merkle_root;
+        self.start_receive = 0;
+        self.auction_start = 0;
+        self.auction_end = 0;
+        self.price_feed = [0; 32];
+        self.feed_id = [0; 32];
+        self.max_staleness = 0;
+        self.max_conf_bps = [0; 2];
+        self.spread_bps = [0; 2];
+        self.collection = [0; 32];
         self.bump = bump;
     }
 
@@ -120,4 +220,911 @@ impl Escrow {
     pub fn is_expired(&self, current_timestamp: i64) -> bool {
         self.expiry > 0 && current_timestamp > self.expiry
     }
-}
\ No newline at end of file
+
+    /// A zeroed taker keeps the escrow public; otherwise only that signer may take it.
+    #[inline(always)]
+    pub fn is_taker_allowed(&self, taker: &Pubkey) -> bool {
+        self.taker == [0; 32] || self.taker == *taker
+    }
+
+    /// A zeroed merkle root means the escrow has no taker allowlist.
+    #[inline(always)]
+    pub fn has_allowlist(&self) -> bool {
+        self.merkle_root != [0; 32]
+    }
+
+    /// A non-zero opening price means `receive` is the floor of a Dutch auction.
+    #[inline(always)]
+    pub fn has_auction(&self) -> bool {
+        self.start_receive != 0
+    }
+
+    /// A non-zero price feed means `receive` is the floor under an oracle price.
+    #[inline(always)]
+    pub fn has_oracle(&self) -> bool {
+        self.price_feed != [0; 32]
+    }
+
+    /// A non-zero collection means any verified NFT from it is accepted as mint B.
+    #[inline(always)]
+    pub fn has_collection(&self) -> bool {
+        self.collection != [0; 32]
+    }
+
+    #[inline(always)]
+    pub fn max_conf_bps(&self) -> u16 {
+        u16::from_le_bytes(self.max_conf_bps)
+    }
+
+    /// Offset from the oracle price in basis points; negative sells below market.
+    #[inline(always)]
+    pub fn spread_bps(&self) -> i16 {
+        i16::from_le_bytes(self.spread_bps)
+    }
+
+    /// Price in mint B for the whole remaining deposit at `current_timestamp`.
+    ///
+    /// Auctions fall linearly from `start_receive` to `receive` between
+    /// `auction_start` and `auction_end`, rounded up in favor of the maker.
+    #[inline(always)]
+    pub fn receive_at(&self, current_timestamp: i64) -> u64 {
+        if !self.has_auction() || current_timestamp >= self.auction_end {
+            return self.receive;
+        }
+        if current_timestamp <= self.auction_start {
+            return self.start_receive;
+        }
+        let decline = self.start_receive.saturating_sub(self.receive) as u128;
+        let elapsed = (current_timestamp - self.auction_start) as u128;
+        let duration = (self.auction_end - self.auction_start) as u128;
+        self.start_receive - (decline * elapsed / duration) as u64
+    }
+
+    /// Amount of mint A released for a fill paying `receive` of mint B,
+    /// proportional to the remaining deposit and rounded down in favor of the maker.
+    #[inline(always)]
+    pub fn deposit_for(&self, receive: u64) -> u64 {
+        self.deposit_at_price(receive, self.receive)
+    }
+
+    /// Like [`Escrow::deposit_for`], against a `price` for the whole remaining deposit.
+    #[inline(always)]
+    pub fn deposit_at_price(&self, receive: u64, price: u64) -> u64 {
+        if price == 0 {
+            return 0;
+        }
+        (self.deposit as u128 * receive as u128 / price as u128) as u64
+    }
+}
+
+/// What remains of `value` after a fill paying `receive` out of `price`, so a
+/// partially filled auction keeps its per-unit schedule.
+#[inline(always)]
+pub fn remaining_after_fill(value: u64, receive: u64, price: u64) -> u64 {
+    if price == 0 {
+        return value;
+    }
+    value - (value as u128 * receive as u128 / price as u128) as u64
+}
+
+pub const CONFIG_DISCRIMINATOR: [u8; 8] = [0x43, 0x4f, 0x4e, 0x46, 0x49, 0x47, 0x00, 0x01]; // "CONFIG\0\1"
+
+/// Basis points denominator for fees
+pub const MAX_BASIS_POINTS: u64 = 10_000;
+
+#[repr(C)]
+pub struct Config {
+    pub discriminator: [u8; 8],
+    pub admin: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub fee_bps: [u8; 2],
+    pub max_referral_bps: [u8; 2],
+    pub paused: [u8; 1],
+    pub bump: [u8; 1],
+}
+
+impl Config {
+    pub const LEN: usize = size_of::<[u8; 8]>()
+        + size_of::<Pubkey>()
+        + size_of::<Pubkey>()
+        + size_of::<[u8; 2]>()
+        + size_of::<[u8; 2]>()
+        + size_of::<[u8; 1]>()
+        + size_of::<[u8; 1]>();
+
+    #[inline(always)]
+    pub fn load_mut(bytes: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if bytes.len() != Config::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let config = unsafe { &mut *core::mem::transmute::<*mut u8, *mut Self>(bytes.as_mut_ptr()) };
+
+        // Validate discriminator for existing accounts (not for new initialization)
+        if config.discriminator != [0; 8] && config.discriminator != CONFIG_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(config)
+    }
+
+    #[inline(always)]
+    pub fn load(bytes: &[u8]) -> Result<&Self, ProgramError> {
+        if bytes.len() != Config::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let config = unsafe { &*core::mem::transmute::<*const u8, *const Self>(bytes.as_ptr()) };
+
+        // Validate discriminator
+        if config.discriminator != CONFIG_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(config)
+    }
+
+    #[inline(always)]
+    pub fn fee_bps(&self) -> u16 {
+        u16::from_le_bytes(self.fee_bps)
+    }
+
+    #[inline(always)]
+    pub fn max_referral_bps(&self) -> u16 {
+        u16::from_le_bytes(self.max_referral_bps)
+    }
+
+    /// Make and Take are halted while paused; refunds always stay open.
+    #[inline(always)]
+    pub fn is_paused(&self) -> bool {
+        self.paused != [0]
+    }
+
+    #[inline(always)]
+    pub fn set_admin(&mut self, admin: Pubkey) {
+        self.admin = admin;
+    }
+
+    #[inline(always)]
+    pub fn set_fee_recipient(&mut self, fee_recipient: Pubkey) {
+        self.fee_recipient = fee_recipient;
+    }
+
+    #[inline(always)]
+    pub fn set_fee_bps(&mut self, fee_bps: u16) {
+        self.fee_bps = fee_bps.to_le_bytes();
+    }
+
+    #[inline(always)]
+    pub fn set_max_referral_bps(&mut self, max_referral_bps: u16) {
+        self.max_referral_bps = max_referral_bps.to_le_bytes();
+    }
+
+    #[inline(always)]
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = [paused as u8];
+    }
+
+    #[inline(always)]
+    pub fn set_inner(
+        &mut self,
+        admin: Pubkey,
+        fee_recipient: Pubkey,
+        fee_bps: u16,
+        max_referral_bps: u16,
+        bump: [u8; 1],
+    ) {
+        self.discriminator = CONFIG_DISCRIMINATOR;
+        self.admin = admin;
+        self.fee_recipient = fee_recipient;
+        self.fee_bps = fee_bps.to_le_bytes();
+        self.max_referral_bps = max_referral_bps.to_le_bytes();
+        self.paused = [0];
+        self.bump = bump;
+    }
+
+    /// Protocol fee owed on `amount`, rounded down in favor of the trader.
+    #[inline(always)]
+    pub fn fee_for(&self, amount: u64) -> u64 {
+        bps_of(amount, self.fee_bps())
+    }
+
+    /// Referrer's share of the protocol `fee` at `referral_bps`, or `None` above the configured cap.
+    /// It comes out of the fee rather than the maker's proceeds, so self-referral costs the maker nothing.
+    #[inline(always)]
+    pub fn referral_fee_for(&self, fee: u64, referral_bps: u16) -> Option<u64> {
+        (referral_bps <= self.max_referral_bps()).then(|| bps_of(fee, referral_bps))
+    }
+}
+
+/// `bps` basis points of `amount`, rounded down.
+#[inline(always)]
+fn bps_of(amount: u64, bps: u16) -> u64 {
+    (amount as u128 * bps as u128 / MAX_BASIS_POINTS as u128) as u64
+}
+
+pub const ARBITER_ESCROW_DISCRIMINATOR: [u8; 8] = [0x41, 0x52, 0x42, 0x49, 0x54, 0x45, 0x52, 0x01]; // "ARBITER\1"
+
+/// Number of tranches an arbiter escrow can be split into; unused slots hold zero.
+pub const MAX_MILESTONES: usize = 4;
+
+/// Escrow releasing mint A to a named beneficiary on an arbiter's (or the maker's) say-so,
+/// for deals whose other side is settled off-chain.
+#[repr(C)]
+pub struct ArbiterEscrow {
+    pub discriminator: [u8; 8],
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub beneficiary: Pubkey,
+    pub arbiter: Pubkey,
+    pub mint_a: Pubkey,
+    pub expiry: i64,
+    pub milestones: [u64; MAX_MILESTONES],
+    /// Bit `i` is set once milestone `i` has been paid out.
+    pub released: [u8; 1],
+    pub disputed: [u8; 1],
+    pub bump: [u8; 1],
+}
+
+impl ArbiterEscrow {
+    pub const LEN: usize = size_of::<[u8; 8]>()
+        + size_of::<u64>()
+        + size_of::<Pubkey>()
+        + size_of::<Pubkey>()
+        + size_of::<Pubkey>()
+        + size_of::<Pubkey>()
+        + size_of::<i64>()
+        + size_of::<[u64; MAX_MILESTONES]>()
+        + size_of::<[u8; 1]>()
+        + size_of::<[u8; 1]>()
+        + size_of::<[u8; 1]>();
+
+    #[inline(always)]
+    pub fn load_mut(bytes: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if bytes.len() != ArbiterEscrow::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let escrow = unsafe { &mut *core::mem::transmute::<*mut u8, *mut Self>(bytes.as_mut_ptr()) };
+
+        // Validate discriminator for existing accounts (not for new initialization)
+        if escrow.discriminator != [0; 8] && escrow.discriminator != ARBITER_ESCROW_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(escrow)
+    }
+
+    #[inline(always)]
+    pub fn load(bytes: &[u8]) -> Result<&Self, ProgramError> {
+        if bytes.len() != ArbiterEscrow::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let escrow = unsafe { &*core::mem::transmute::<*const u8, *const Self>(bytes.as_ptr()) };
+
+        // Validate discriminator
+        if escrow.discriminator != ARBITER_ESCROW_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(escrow)
+    }
+
+    #[inline(always)]
+    pub fn set_disputed(&mut self, disputed: bool) {
+        self.disputed = [disputed as u8];
+    }
+
+    #[inline(always)]
+    pub fn set_milestone_released(&mut self, index: usize) {
+        self.released[0] |= 1 << index;
+    }
+
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_inner(
+        &mut self,
+        seed: u64,
+        maker: Pubkey,
+        beneficiary: Pubkey,
+        arbiter: Pubkey,
+        mint_a: Pubkey,
+        expiry: i64,
+        milestones: [u64; MAX_MILESTONES],
+        bump: [u8; 1],
+    ) {
+        self.discriminator = ARBITER_ESCROW_DISCRIMINATOR;
+        self.seed = seed;
+        self.maker = maker;
+        self.beneficiary = beneficiary;
+        self.arbiter = arbiter;
+        self.mint_a = mint_a;
+        self.expiry = expiry;
+        self.milestones = milestones;
+        self.released = [0];
+        self.disputed = [0];
+        self.bump = bump;
+    }
+
+    /// Milestones fill the array from the front, so the first zero slot ends the schedule.
+    #[inline(always)]
+    pub fn milestone_count(&self) -> usize {
+        self.milestones.iter().take_while(|&&amount| amount > 0).count()
+    }
+
+    #[inline(always)]
+    pub fn is_milestone_released(&self, index: usize) -> bool {
+        self.released[0] & (1 << index) != 0
+    }
+
+    /// Once every milestone is paid the vault and escrow can be closed.
+    #[inline(always)]
+    pub fn is_fully_released(&self) -> bool {
+        (0..self.milestone_count()).all(|index| self.is_milestone_released(index))
+    }
+
+    /// A dispute freezes expiry, leaving the outcome to the arbiter.
+    #[inline(always)]
+    pub fn is_disputed(&self) -> bool {
+        self.disputed != [0]
+    }
+
+    /// An expiry of zero means the escrow never expires; disputed escrows never do either.
+    #[inline(always)]
+    pub fn is_expired(&self, current_timestamp: i64) -> bool {
+        !self.is_disputed() && self.expiry > 0 && current_timestamp > self.expiry
+    }
+}
+
+pub const VESTING_ESCROW_DISCRIMINATOR: [u8; 8] = [0x56, 0x45, 0x53, 0x54, 0x49, 0x4e, 0x47, 0x01]; // "VESTING\1"
+
+/// Escrow unlocking mint A to a beneficiary linearly between `start` and `end`,
+/// with nothing claimable before `cliff`.
+#[repr(C)]
+pub struct VestingEscrow {
+    pub discriminator: [u8; 8],
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub beneficiary: Pubkey,
+    pub mint_a: Pubkey,
+    pub start: i64,
+    pub cliff: i64,
+    pub end: i64,
+    /// Amount that landed in the vault, net of any transfer fee
+    pub total: u64,
+    pub withdrawn: u64,
+    pub bump: [u8; 1],
+}
+
+impl VestingEscrow {
+    pub const LEN: usize = size_of::<[u8; 8]>()
+        + size_of::<u64>()
+        + size_of::<Pubkey>()
+        + size_of::<Pubkey>()
+        + size_of::<Pubkey>()
+        + size_of::<i64>()
+        + size_of::<i64>()
+        + size_of::<i64>()
+        + size_of::<u64>()
+        + size_of::<u64>()
+        + size_of::<[u8; 1]>();
+
+    #[inline(always)]
+    pub fn load_mut(bytes: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if bytes.len() != VestingEscrow::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let escrow = unsafe { &mut *core::mem::transmute::<*mut u8, *mut Self>(bytes.as_mut_ptr()) };
+
+        // Validate discriminator for existing accounts (not for new initialization)
+        if escrow.discriminator != [0; 8] && escrow.discriminator != VESTING_ESCROW_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(escrow)
+    }
+
+    #[inline(always)]
+    pub fn load(bytes: &[u8]) -> Result<&Self, ProgramError> {
+        if bytes.len() != VestingEscrow::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let escrow = unsafe { &*core::mem::transmute::<*const u8, *const Self>(bytes.as_ptr()) };
+
+        // Validate discriminator
+        if escrow.discriminator != VESTING_ESCROW_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(escrow)
+    }
+
+    #[inline(always)]
+    pub fn set_withdrawn(&mut self, withdrawn: u64) {
+        self.withdrawn = withdrawn;
+    }
+
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_inner(
+        &mut self,
+        seed: u64,
+        maker: Pubkey,
+        beneficiary: Pubkey,
+        mint_a: Pubkey,
+        start: i64,
+        cliff: i64,
+        end: i64,
+        total: u64,
+        bump: [u8; 1],
+    ) {
+        self.discriminator = VESTING_ESCROW_DISCRIMINATOR;
+        self.seed = seed;
+        self.maker = maker;
+        self.beneficiary = beneficiary;
+        self.mint_a = mint_a;
+        self.start = start;
+        self.cliff = cliff;
+        self.end = end;
+        self.total = total;
+        self.withdrawn = 0;
+        self.bump = bump;
+    }
+
+    /// Amount vested by `current_timestamp`, rounded down in favor of the maker.
+    #[inline(always)]
+    pub fn unlocked_at(&self, current_timestamp: i64) -> u64 {
+        if current_timestamp < self.cliff {
+            return 0;
+        }
+        if current_timestamp >= self.end {
+            return self.total;
+        }
+        let elapsed = (current_timestamp - self.start) as u128;
+        let duration = (self.end - self.start) as u128;
+        (self.total as u128 * elapsed / duration) as u64
+    }
+
+    /// Vested amount not yet withdrawn by the beneficiary.
+    #[inline(always)]
+    pub fn claimable_at(&self, current_timestamp: i64) -> u64 {
+        self.unlocked_at(current_timestamp).saturating_sub(self.withdrawn)
+    }
+}
+
+pub const HTLC_ESCROW_DISCRIMINATOR: [u8; 8] = [0x48, 0x54, 0x4c, 0x43, 0x00, 0x00, 0x00, 0x01]; // "HTLC\0\0\0\1"
+
+/// Hash time-locked escrow releasing mint A to `recipient` for the preimage of
+/// its hashlock, or back to the maker once the timelock in `expiry` passes.
+#[repr(C)]
+pub struct HtlcEscrow {
+    pub discriminator: [u8; 8],
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub recipient: Pubkey,
+    pub mint_a: Pubkey,
+    pub sha256_hash: [u8; 32],
+    /// All zeroes when only the SHA-256 lock is set
+    pub keccak_hash: [u8; 32],
+    pub expiry: i64,
+    pub bump: [u8; 1],
+}
+
+impl HtlcEscrow {
+    pub const LEN: usize = size_of::<[u8; 8]>()
+        + size_of::<u64>()
+        + size_of::<Pubkey>()
+        + size_of::<Pubkey>()
+        + size_of::<Pubkey>()
+        + size_of::<[u8; 32]>()
+        + size_of::<[u8; 32]>()
+        + size_of::<i64>()
+        + size_of::<[u8; 1]>();
+
+    #[inline(always)]
+    pub fn load_mut(bytes: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if bytes.len() != HtlcEscrow::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let escrow = unsafe { &mut *core::mem::transmute::<*mut u8, *mut Self>(bytes.as_mut_ptr()) };
+
+        // Validate discriminator for existing accounts (not for new initialization)
+        if escrow.discriminator != [0; 8] && escrow.discriminator != HTLC_ESCROW_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(escrow)
+    }
+
+    #[inline(always)]
+    pub fn load(bytes: &[u8]) -> Result<&Self, ProgramError> {
+        if bytes.len() != HtlcEscrow::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let escrow = unsafe { &*core::mem::transmute::<*const u8, *const Self>(bytes.as_ptr()) };
+
+        // Validate discriminator
+        if escrow.discriminator != HTLC_ESCROW_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(escrow)
+    }
+
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_inner(
+        &mut self,
+        seed: u64,
+        maker: Pubkey,
+        recipient: Pubkey,
+        mint_a: Pubkey,
+        sha256_hash: [u8; 32],
+        keccak_hash: [u8; 32],
+        expiry: i64,
+        bump: [u8; 1],
+    ) {
+        self.discriminator = HTLC_ESCROW_DISCRIMINATOR;
+        self.seed = seed;
+        self.maker = maker;
+        self.recipient = recipient;
+        self.mint_a = mint_a;
+        self.sha256_hash = sha256_hash;
+        self.keccak_hash = keccak_hash;
+        self.expiry = expiry;
+        self.bump = bump;
+    }
+
+    /// The timelock is mandatory, so unlike a swap escrow a zero expiry isn't "never".
+    #[inline(always)]
+    pub fn is_expired(&self, current_timestamp: i64) -> bool {
+        current_timestamp > self.expiry
+    }
+}
+
+pub const AUCTION_ESCROW_DISCRIMINATOR: [u8; 8] = [0x41, 0x55, 0x43, 0x54, 0x49, 0x4f, 0x4e, 0x01]; // "AUCTION\1"
+
+/// English auction selling the mint A lot in its vault to the highest mint B
+/// bid at or above `reserve` once `end` passes.
+#[repr(C)]
+pub struct AuctionEscrow {
+    pub discriminator: [u8; 8],
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub reserve: u64,
+    /// Amount each new leading bid must add on top of the current one
+    pub min_increment: u64,
+    pub end: i64,
+    pub highest_bid: u64,
+    /// All zeroes until the first bid lands
+    pub highest_bidder: Pubkey,
+    pub bump: [u8; 1],
+}
+
+impl AuctionEscrow {
+    pub const LEN: usize = size_of::<[u8; 8]>()
+        + size_of::<u64>()
+        + size_of::<Pubkey>()
+        + size_of::<Pubkey>()
+        + size_of::<Pubkey>()
+        + size_of::<u64>()
+        + size_of::<u64>()
+        + size_of::<i64>()
+        + size_of::<u64>()
+        + size_of::<Pubkey>()
+        + size_of::<[u8; 1]>();
+
+    #[inline(always)]
+    pub fn load_mut(bytes: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if bytes.len() != AuctionEscrow::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let auction = unsafe { &mut *core::mem::transmute::<*mut u8, *mut Self>(bytes.as_mut_ptr()) };
+
+        // Validate discriminator for existing accounts (not for new initialization)
+        if auction.discriminator != [0; 8] && auction.discriminator != AUCTION_ESCROW_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(auction)
+    }
+
+    #[inline(always)]
+    pub fn load(bytes: &[u8]) -> Result<&Self, ProgramError> {
+        if bytes.len() != AuctionEscrow::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let auction = unsafe { &*core::mem::transmute::<*const u8, *const Self>(bytes.as_ptr()) };
+
+        // Validate discriminator
+        if auction.discriminator != AUCTION_ESCROW_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(auction)
+    }
+
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_inner(
+        &mut self,
+        seed: u64,
+        maker: Pubkey,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        reserve: u64,
+        min_increment: u64,
+        end: i64,
+        bump: [u8; 1],
+    ) {
+        self.discriminator = AUCTION_ESCROW_DISCRIMINATOR;
+        self.seed = seed;
+        self.maker = maker;
+        self.mint_a = mint_a;
+        self.mint_b = mint_b;
+        self.reserve = reserve;
+        self.min_increment = min_increment;
+        self.end = end;
+        self.highest_bid = 0;
+        self.highest_bidder = [0; 32];
+        self.bump = bump;
+    }
+
+    #[inline(always)]
+    pub fn set_highest_bid(&mut self, bidder: Pubkey, amount: u64) {
+        self.highest_bidder = bidder;
+        self.highest_bid = amount;
+    }
+
+    #[inline(always)]
+    pub fn has_bid(&self) -> bool {
+        self.highest_bidder != [0; 32]
+    }
+
+    /// Bidding closes at `end`, after which anyone may settle.
+    #[inline(always)]
+    pub fn is_ended(&self, current_timestamp: i64) -> bool {
+        current_timestamp >= self.end
+    }
+
+    /// Smallest total bid that takes the lead: the reserve, then one increment over the leader.
+    #[inline(always)]
+    pub fn min_bid(&self) -> u64 {
+        if self.has_bid() {
+            self.highest_bid.saturating_add(self.min_increment)
+        } else {
+            self.reserve
+        }
+    }
+}
+
+pub const BID_DISCRIMINATOR: [u8; 8] = [0x42, 0x49, 0x44, 0x00, 0x00, 0x00, 0x00, 0x01]; // "BID\0\0\0\0\1"
+
+/// A bidder's mint B locked in the vault owned by this account, one per bidder and auction.
+#[repr(C)]
+pub struct Bid {
+    pub discriminator: [u8; 8],
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    /// Mint B credited to the bid vault, net of any transfer fee
+    pub amount: u64,
+    pub bump: [u8; 1],
+}
+
+impl Bid {
+    pub const LEN: usize = size_of::<[u8; 8]>()
+        + size_of::<Pubkey>()
+        + size_of::<Pubkey>()
+        + size_of::<u64>()
+        + size_of::<[u8; 1]>();
+
+    #[inline(always)]
+    pub fn load_mut(bytes: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if bytes.len() != Bid::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let bid = unsafe { &mut *core::mem::transmute::<*mut u8, *mut Self>(bytes.as_mut_ptr()) };
+
+        // Validate discriminator for existing accounts (not for new initialization)
+        if bid.discriminator != [0; 8] && bid.discriminator != BID_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(bid)
+    }
+
+    #[inline(always)]
+    pub fn load(bytes: &[u8]) -> Result<&Self, ProgramError> {
+        if bytes.len() != Bid::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let bid = unsafe { &*core::mem::transmute::<*const u8, *const Self>(bytes.as_ptr()) };
+
+        // Validate discriminator
+        if bid.discriminator != BID_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(bid)
+    }
+
+    #[inline(always)]
+    pub fn set_inner(&mut self, auction: Pubkey, bidder: Pubkey, bump: [u8; 1]) {
+        self.discriminator = BID_DISCRIMINATOR;
+        self.auction = auction;
+        self.bidder = bidder;
+        self.amount = 0;
+        self.bump = bump;
+    }
+
+    #[inline(always)]
+    pub fn set_amount(&mut self, amount: u64) {
+        self.amount = amount;
+    }
+}
+
+pub const BUNDLE_ESCROW_DISCRIMINATOR: [u8; 8] = [0x42, 0x55, 0x4e, 0x44, 0x4c, 0x45, 0x00, 0x01]; // "BUNDLE\0\1"
+
+/// Most mint/amount pairs a bundle holds on either side.
+pub const MAX_BUNDLE_LEGS: usize = 4;
+
+/// One mint/amount pair of a bundle.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct Leg {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Several mints traded at once: each mint A leg is locked in its own vault owned by
+/// this account, and every mint B leg is owed to the maker in a single atomic take.
+#[repr(C)]
+pub struct BundleEscrow {
+    pub discriminator: [u8; 8],
+    pub seed: u64,
+    pub maker: Pubkey,
+    /// Deposited amounts, net of any transfer fee; only the first `legs_a_len` are set
+    pub legs_a: [Leg; MAX_BUNDLE_LEGS],
+    /// Amounts the maker receives; only the first `legs_b_len` are set
+    pub legs_b: [Leg; MAX_BUNDLE_LEGS],
+    pub legs_a_len: [u8; 1],
+    pub legs_b_len: [u8; 1],
+    pub bump: [u8; 1],
+}
+
+impl BundleEscrow {
+    pub const LEN: usize = size_of::<[u8; 8]>()
+        + size_of::<u64>()
+        + size_of::<Pubkey>()
+        + size_of::<[Leg; MAX_BUNDLE_LEGS]>()
+        + size_of::<[Leg; MAX_BUNDLE_LEGS]>()
+        + size_of::<[u8; 1]>()
+        + size_of::<[u8; 1]>()
+        + size_of::<[u8; 1]>();
+
+    #[inline(always)]
+    pub fn load_mut(bytes: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if bytes.len() != BundleEscrow::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let escrow = unsafe { &mut *core::mem::transmute::<*mut u8, *mut Self>(bytes.as_mut_ptr()) };
+
+        // Validate discriminator for existing accounts (not for new initialization)
+        if escrow.discriminator != [0; 8] && escrow.discriminator != BUNDLE_ESCROW_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(escrow)
+    }
+
+    #[inline(always)]
+    pub fn load(bytes: &[u8]) -> Result<&Self, ProgramError> {
+        if bytes.len() != BundleEscrow::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let escrow = unsafe { &*core::mem::transmute::<*const u8, *const Self>(bytes.as_ptr()) };
+
+        // Validate discriminator
+        if escrow.discriminator != BUNDLE_ESCROW_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(escrow)
+    }
+
+    /// Stores up to [`MAX_BUNDLE_LEGS`] legs per side; callers validate the counts.
+    #[inline(always)]
+    pub fn set_inner(&mut self, seed: u64, maker: Pubkey, legs_a: &[Leg], legs_b: &[Leg], bump: [u8; 1]) {
+        self.discriminator = BUNDLE_ESCROW_DISCRIMINATOR;
+        self.seed = seed;
+        self.maker = maker;
+        self.legs_a = [Leg::default(); MAX_BUNDLE_LEGS];
+        self.legs_a[..legs_a.len()].copy_from_slice(legs_a);
+        self.legs_b = [Leg::default(); MAX_BUNDLE_LEGS];
+        self.legs_b[..legs_b.len()].copy_from_slice(legs_b);
+        self.legs_a_len = [legs_a.len() as u8];
+        self.legs_b_len = [legs_b.len() as u8];
+        self.bump = bump;
+    }
+
+    #[inline(always)]
+    pub fn legs_a(&self) -> &[Leg] {
+        &self.legs_a[..(self.legs_a_len[0] as usize).min(MAX_BUNDLE_LEGS)]
+    }
+
+    #[inline(always)]
+    pub fn legs_b(&self) -> &[Leg] {
+        &self.legs_b[..(self.legs_b_len[0] as usize).min(MAX_BUNDLE_LEGS)]
+    }
+}
+
+/// A mint repeated on one side would share a vault or an ATA with itself.
+pub fn has_duplicate_mints(legs: &[Leg]) -> bool {
+    legs.iter()
+        .enumerate()
+        .any(|(i, leg)| legs[..i].iter().any(|other| other.mint == leg.mint))
+}
+
+pub const OFFER_DISCRIMINATOR: [u8; 8] = [0x4f, 0x46, 0x46, 0x45, 0x52, 0x00, 0x00, 0x01]; // "OFFER\0\0\1"
+
+/// A taker's counter-offer on an escrow: their proposed price in mint B, locked in the
+/// vault owned by this account, one per escrow and taker.
+#[repr(C)]
+pub struct Offer {
+    pub discriminator: [u8; 8],
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    /// Mint B offered for the whole remaining deposit, net of any transfer fee
+    pub receive: u64,
+    /// Mint A the escrow held when the offer was made; accepting fails if it has shrunk since
+    pub deposit: u64,
+    pub bump: [u8; 1],
+}
+
+impl Offer {
+    pub const LEN: usize = size_of::<[u8; 8]>()
+        + size_of::<Pubkey>()
+        + size_of::<Pubkey>()
+        + size_of::<u64>()
+        + size_of::<u64>()
+        + size_of::<[u8; 1]>();
+
+    #[inline(always)]
+    pub fn load_mut(bytes: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if bytes.len() != Offer::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let offer = unsafe { &mut *core::mem::transmute::<*mut u8, *mut Self>(bytes.as_mut_ptr()) };
+
+        // Validate discriminator for existing accounts (not for new initialization)
+        if offer.discriminator != [0; 8] && offer.discriminator != OFFER_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(offer)
+    }
+
+    #[inline(always)]
+    pub fn load(bytes: &[u8]) -> Result<&Self, ProgramError> {
+        if bytes.len() != Offer::LEN {
+            return Err(crate::errors::PinocchioError::InvalidAccountData.into());
+        }
+        let offer = unsafe { &*core::mem::transmute::<*const u8, *const Self>(bytes.as_ptr()) };
+
+        // Validate discriminator
+        if offer.discriminator != OFFER_DISCRIMINATOR {
+            return Err(crate::errors::PinocchioError::InvalidDiscriminator.into());
+        }
+
+        Ok(offer)
+    }
+
+    #[inline(always)]
+    pub fn set_inner(&mut self, escrow: Pubkey, taker: Pubkey, receive: u64, deposit: u64, bump: [u8; 1]) {
+        self.discriminator = OFFER_DISCRIMINATOR;
+        self.escrow = escrow;
+        self.taker = taker;
+        self.receive = receive;
+        self.deposit = deposit;
+        self.bump = bump;
+    }
+}
@@ -1,16 +1,32 @@
-use litesvm::LiteSVM;
+use std::path::PathBuf;
+use litesvm::{types::TransactionResult, LiteSVM};
 use solana_sdk::{
-    instruction::{AccountMeta, Instruction},
+    account::Account,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction, InstructionError},
     native_token::LAMPORTS_PER_SOL,
     program_pack::Pack,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
     system_instruction,
     system_program,
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError},
 };
-use spl_associated_token_account;
 use spl_token::{self, state::Account as TokenAccount, state::Mint};
+use blueshift_pinocchio_escrow::{
+    errors::PinocchioError,
+    hash::{verify_hashlock, verify_merkle_proof},
+    metadata::{Metadata, KEY_METADATA_V1, TOKEN_METADATA_ID},
+    oracle::{PriceFeed, PRICE_UPDATE_V2_DISCRIMINATOR, PYTH_RECEIVER_ID, VERIFICATION_LEVEL_FULL},
+    state::{
+        has_duplicate_mints, remaining_after_fill, ArbiterEscrow, AuctionEscrow, Bid, BundleEscrow, Config,
+        Escrow, HtlcEscrow, Leg, Offer, VestingEscrow, ARBITER_ESCROW_DISCRIMINATOR,
+        AUCTION_ESCROW_DISCRIMINATOR, BID_DISCRIMINATOR, BUNDLE_ESCROW_DISCRIMINATOR, CONFIG_DISCRIMINATOR,
+        HTLC_ESCROW_DISCRIMINATOR, MAX_BUNDLE_LEGS, OFFER_DISCRIMINATOR, VESTING_ESCROW_DISCRIMINATOR,
+    },
+    CounterOfferInstructionData, MakeBundleInstructionData, TakeManyInstructionData, TransferFee,
+    BPF_LOADER_UPGRADEABLE_ID, MAX_TAKE_MANY_ESCROWS, NATIVE_MINT, TAKE_MANY_FILL_LEN,
+};
 
 /// The program ID for our Pinocchio escrow program
 const PROGRAM_ID: [u8; 32] = [
@@ -20,13 +36,45 @@ const PROGRAM_ID: [u8; 32] = [
     0x8e, 0xf8, 0xaf, 0x70, 0x47, 0xdc, 0x11, 0xf7,
 ];
 
+/// The Token-2022 program ID
+const TOKEN_2022_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
 /// Discriminator for escrow accounts
 const ESCROW_DISCRIMINATOR: [u8; 8] = [0x45, 0x53, 0x43, 0x52, 0x4f, 0x57, 0x00, 0x01];
 
+/// Size of the escrow account data
+const ESCROW_LEN: usize = 341;
+
 /// Instruction discriminators
 const MAKE_DISCRIMINATOR: u8 = 0;
 const TAKE_DISCRIMINATOR: u8 = 1;
 const REFUND_DISCRIMINATOR: u8 = 2;
+const EXPIRE_REFUND_DISCRIMINATOR: u8 = 3;
+const UPDATE_DISCRIMINATOR: u8 = 4;
+const INITIALIZE_CONFIG_DISCRIMINATOR: u8 = 5;
+const UPDATE_CONFIG_DISCRIMINATOR: u8 = 6;
+const SET_PAUSED_DISCRIMINATOR: u8 = 7;
+const MAKE_ARBITER_ESCROW_DISCRIMINATOR: u8 = 8;
+const RELEASE_DISCRIMINATOR: u8 = 9;
+const ARBITER_REFUND_DISCRIMINATOR: u8 = 10;
+const DISPUTE_DISCRIMINATOR: u8 = 11;
+const RELEASE_MILESTONE_DISCRIMINATOR: u8 = 12;
+const MAKE_VESTING_ESCROW_DISCRIMINATOR: u8 = 13;
+const CLAIM_DISCRIMINATOR: u8 = 14;
+const MAKE_HTLC_DISCRIMINATOR: u8 = 15;
+const CLAIM_HTLC_DISCRIMINATOR: u8 = 16;
+const REFUND_HTLC_DISCRIMINATOR: u8 = 17;
+const MAKE_AUCTION_DISCRIMINATOR: u8 = 18;
+const PLACE_BID_DISCRIMINATOR: u8 = 19;
+const WITHDRAW_BID_DISCRIMINATOR: u8 = 20;
+const SETTLE_DISCRIMINATOR: u8 = 21;
+const MAKE_BUNDLE_DISCRIMINATOR: u8 = 22;
+const TAKE_BUNDLE_DISCRIMINATOR: u8 = 23;
+const REFUND_BUNDLE_DISCRIMINATOR: u8 = 24;
+const COUNTER_OFFER_DISCRIMINATOR: u8 = 25;
+const ACCEPT_COUNTER_DISCRIMINATOR: u8 = 26;
+const WITHDRAW_COUNTER_DISCRIMINATOR: u8 = 27;
+const TAKE_MANY_DISCRIMINATOR: u8 = 28;
 
 /// Helper struct to manage test context
 struct TestContext {
@@ -37,6 +85,7 @@ struct TestContext {
     mint_authority: Keypair,
     mint_a: Keypair,
     mint_b: Keypair,
+    treasury: Keypair,
 }
 
 impl TestContext {
@@ -57,6 +106,7 @@ impl TestContext {
         let mint_authority = Keypair::new();
         let mint_a = Keypair::new();
         let mint_b = Keypair::new();
+        let treasury = Keypair::new();
         
         // Fund accounts with SOL
         svm.airdrop(&maker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
@@ -71,6 +121,7 @@ impl TestContext {
             mint_authority,
             mint_a,
             mint_b,
+            treasury,
         }
     }
     
@@ -220,33 +271,370 @@ impl TestContext {
         )
     }
     
-    #[allow(dead_code)]
+    fn get_config_pda(&self) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"config"], &self.program_id)
+    }
+    
     fn get_token_balance(&self, account: &Pubkey) -> u64 {
+        // Token-2022 accounts carry extensions past the base layout
         self.svm.get_account(account)
-            .and_then(|account| TokenAccount::unpack(&account.data).ok())
+            .and_then(|account| TokenAccount::unpack(account.data.get(..TokenAccount::LEN)?).ok())
             .map(|token_account| token_account.amount)
             .unwrap_or(0)
     }
     
-    fn serialize_make_instruction(seed: u64, receive: u64, amount: u64, expiry: i64) -> Vec<u8> {
+    /// Loads the program built by `cargo build-sbf`, from `SBF_OUT_DIR` when run through
+    /// `cargo test-sbf`. Returns false when there is no build, so the caller can skip.
+    fn deploy(&mut self) -> bool {
+        let program_path = std::env::var("SBF_OUT_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/deploy"))
+            .join("blueshift_pinocchio_escrow.so");
+        if !program_path.exists() {
+            println!("Skipping: {} not found, run `cargo build-sbf` first", program_path.display());
+            return false;
+        }
+        self.svm.add_program_from_file(self.program_id, program_path).unwrap();
+        true
+    }
+    
+    /// Writes the config directly, since InitializeConfig needs the program's ProgramData account
+    fn set_config(&mut self, fee_bps: u16) {
+        self.write_config(fee_bps, 0, false);
+    }
+    
+    /// Writes a config administered by the mint authority that pays fees to the treasury
+    fn write_config(&mut self, fee_bps: u16, max_referral_bps: u16, paused: bool) {
+        let (config, bump) = self.get_config_pda();
+        let mut data = Vec::with_capacity(Config::LEN);
+        data.extend_from_slice(&CONFIG_DISCRIMINATOR);
+        data.extend_from_slice(self.mint_authority.pubkey().as_ref());
+        data.extend_from_slice(self.treasury.pubkey().as_ref());
+        data.extend_from_slice(&fee_bps.to_le_bytes());
+        data.extend_from_slice(&max_referral_bps.to_le_bytes());
+        data.push(paused as u8);
+        data.push(bump);
+        
+        let account = Account {
+            lamports: self.svm.minimum_balance_for_rent_exemption(Config::LEN),
+            data,
+            owner: self.program_id,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.svm.set_account(config, account).unwrap();
+    }
+    
+    fn set_clock(&mut self, unix_timestamp: i64) {
+        let mut clock = self.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = unix_timestamp;
+        self.svm.set_sysvar(&clock);
+    }
+    
+    /// Writes the legacy wrapped SOL mint in place, since LiteSVM doesn't ship it
+    fn create_native_mint(&mut self) {
+        let mut data = vec![0; Mint::LEN];
+        Mint {
+            decimals: 9,
+            is_initialized: true,
+            ..Mint::default()
+        }
+        .pack_into_slice(&mut data);
+        
+        let account = Account {
+            lamports: self.svm.minimum_balance_for_rent_exemption(Mint::LEN),
+            data,
+            owner: spl_token::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.svm.set_account(spl_token::native_mint::id(), account).unwrap();
+    }
+    
+    /// Creates `owner`'s ATA for a legacy token `mint` if needed and mints `amount` into it
+    fn fund_token_account(&mut self, owner: &Pubkey, mint: &Pubkey, amount: u64) -> Pubkey {
+        let ata = self.get_associated_token_address(owner, mint);
+        let create_ata_ix = spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            &self.mint_authority.pubkey(),
+            owner,
+            mint,
+            &spl_token::ID,
+        );
+        let mint_to_ix = spl_token::instruction::mint_to(
+            &spl_token::ID,
+            mint,
+            &ata,
+            &self.mint_authority.pubkey(),
+            &[],
+            amount,
+        ).unwrap();
+        
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ata_ix, mint_to_ix],
+            Some(&self.mint_authority.pubkey()),
+            &[&self.mint_authority],
+            self.svm.latest_blockhash(),
+        );
+        
+        self.svm.send_transaction(tx).unwrap();
+        ata
+    }
+    
+    /// Make instruction for a `maker` escrow selling mint A for `mint_b` under the legacy token program
+    fn make_instruction(&self, maker: &Pubkey, seed: u64, mint_b: &Pubkey, data: Vec<u8>) -> Instruction {
+        let (escrow_pda, _) = self.get_escrow_pda(maker, seed);
+        let (config, _) = self.get_config_pda();
+        
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(*maker, true),
+                AccountMeta::new(escrow_pda, false),
+                AccountMeta::new_readonly(self.mint_a.pubkey(), false),
+                AccountMeta::new_readonly(*mint_b, false),
+                AccountMeta::new(self.get_associated_token_address(maker, &self.mint_a.pubkey()), false),
+                AccountMeta::new(self.get_associated_token_address(&escrow_pda, &self.mint_a.pubkey()), false),
+                AccountMeta::new_readonly(config, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+            ],
+            data,
+        }
+    }
+    
+    /// Take of the maker's mint A escrow `seed` by `taker`, with `remaining` appended after
+    /// the fixed accounts
+    fn take_instruction(&self, taker: &Pubkey, seed: u64, data: Vec<u8>, remaining: &[AccountMeta]) -> Instruction {
+        let maker = self.maker.pubkey();
+        let (escrow_pda, _) = self.get_escrow_pda(&maker, seed);
+        let (config, _) = self.get_config_pda();
+        let mut accounts = vec![
+            AccountMeta::new(*taker, true),
+            AccountMeta::new(maker, false),
+            AccountMeta::new(escrow_pda, false),
+            AccountMeta::new_readonly(self.mint_a.pubkey(), false),
+            AccountMeta::new_readonly(self.mint_b.pubkey(), false),
+            AccountMeta::new(self.get_associated_token_address(&escrow_pda, &self.mint_a.pubkey()), false),
+            AccountMeta::new(self.get_associated_token_address(taker, &self.mint_a.pubkey()), false),
+            AccountMeta::new(self.get_associated_token_address(taker, &self.mint_b.pubkey()), false),
+            AccountMeta::new(self.get_associated_token_address(&maker, &self.mint_b.pubkey()), false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(self.treasury.pubkey(), false),
+            AccountMeta::new(self.get_associated_token_address(&self.treasury.pubkey(), &self.mint_b.pubkey()), false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        ];
+        accounts.extend_from_slice(remaining);
+        
+        Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        }
+    }
+    
+    #[allow(clippy::too_many_arguments)]
+    fn serialize_make_instruction(
+        seed: u64,
+        receive: u64,
+        amount: u64,
+        expiry: i64,
+        bounty: u64,
+        taker: &Pubkey,
+        merkle_root: &[u8; 32],
+        (start_receive, auction_start, auction_end): (u64, i64, i64),
+        (price_feed, feed_id, max_staleness, max_conf_bps, spread_bps): (&Pubkey, &[u8; 32], u64, u16, i16),
+        collection: &Pubkey,
+    ) -> Vec<u8> {
         let mut data = vec![MAKE_DISCRIMINATOR];
         data.extend_from_slice(&seed.to_le_bytes());
         data.extend_from_slice(&receive.to_le_bytes());
         data.extend_from_slice(&amount.to_le_bytes());
         data.extend_from_slice(&expiry.to_le_bytes());
+        data.extend_from_slice(&bounty.to_le_bytes());
+        data.extend_from_slice(taker.as_ref());
+        data.extend_from_slice(merkle_root);
+        data.extend_from_slice(&start_receive.to_le_bytes());
+        data.extend_from_slice(&auction_start.to_le_bytes());
+        data.extend_from_slice(&auction_end.to_le_bytes());
+        data.extend_from_slice(price_feed.as_ref());
+        data.extend_from_slice(feed_id);
+        data.extend_from_slice(&max_staleness.to_le_bytes());
+        data.extend_from_slice(&max_conf_bps.to_le_bytes());
+        data.extend_from_slice(&spread_bps.to_le_bytes());
+        data.extend_from_slice(collection.as_ref());
         data
     }
     
-    fn serialize_take_instruction() -> Vec<u8> {
-        vec![TAKE_DISCRIMINATOR]
+    fn serialize_take_instruction(
+        amount: u64,
+        referral_bps: u16,
+        max_pay: u64,
+        min_receive: u64,
+        proof: &[[u8; 32]],
+    ) -> Vec<u8> {
+        let mut data = vec![TAKE_DISCRIMINATOR];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend_from_slice(&referral_bps.to_le_bytes());
+        data.extend_from_slice(&max_pay.to_le_bytes());
+        data.extend_from_slice(&min_receive.to_le_bytes());
+        for node in proof {
+            data.extend_from_slice(node);
+        }
+        data
     }
     
     fn serialize_refund_instruction() -> Vec<u8> {
         vec![REFUND_DISCRIMINATOR]
     }
     
+    fn serialize_expire_refund_instruction() -> Vec<u8> {
+        vec![EXPIRE_REFUND_DISCRIMINATOR]
+    }
+    
+    fn serialize_update_instruction(receive: u64, expiry: i64, deposit: u64, withdraw: u64) -> Vec<u8> {
+        let mut data = vec![UPDATE_DISCRIMINATOR];
+        data.extend_from_slice(&receive.to_le_bytes());
+        data.extend_from_slice(&expiry.to_le_bytes());
+        data.extend_from_slice(&deposit.to_le_bytes());
+        data.extend_from_slice(&withdraw.to_le_bytes());
+        data
+    }
+    
+    fn serialize_initialize_config_instruction(fee_recipient: &Pubkey, fee_bps: u16, max_referral_bps: u16) -> Vec<u8> {
+        let mut data = vec![INITIALIZE_CONFIG_DISCRIMINATOR];
+        data.extend_from_slice(fee_recipient.as_ref());
+        data.extend_from_slice(&fee_bps.to_le_bytes());
+        data.extend_from_slice(&max_referral_bps.to_le_bytes());
+        data
+    }
+    
+    fn serialize_update_config_instruction(admin: &Pubkey, fee_recipient: &Pubkey, fee_bps: u16, max_referral_bps: u16) -> Vec<u8> {
+        let mut data = vec![UPDATE_CONFIG_DISCRIMINATOR];
+        data.extend_from_slice(admin.as_ref());
+        data.extend_from_slice(fee_recipient.as_ref());
+        data.extend_from_slice(&fee_bps.to_le_bytes());
+        data.extend_from_slice(&max_referral_bps.to_le_bytes());
+        data
+    }
+    
+    fn serialize_set_paused_instruction(paused: bool) -> Vec<u8> {
+        vec![SET_PAUSED_DISCRIMINATOR, paused as u8]
+    }
+    
+    fn serialize_make_arbiter_escrow_instruction(
+        seed: u64,
+        expiry: i64,
+        beneficiary: &Pubkey,
+        arbiter: &Pubkey,
+        milestones: &[u64],
+    ) -> Vec<u8> {
+        let mut data = vec![MAKE_ARBITER_ESCROW_DISCRIMINATOR];
+        data.extend_from_slice(&seed.to_le_bytes());
+        data.extend_from_slice(&expiry.to_le_bytes());
+        data.extend_from_slice(beneficiary.as_ref());
+        data.extend_from_slice(arbiter.as_ref());
+        for milestone in milestones {
+            data.extend_from_slice(&milestone.to_le_bytes());
+        }
+        data
+    }
+    
+    fn serialize_release_milestone_instruction(index: u8) -> Vec<u8> {
+        vec![RELEASE_MILESTONE_DISCRIMINATOR, index]
+    }
+    
+    fn serialize_make_vesting_escrow_instruction(
+        seed: u64,
+        amount: u64,
+        start: i64,
+        cliff: i64,
+        end: i64,
+        beneficiary: &Pubkey,
+    ) -> Vec<u8> {
+        let mut data = vec![MAKE_VESTING_ESCROW_DISCRIMINATOR];
+        data.extend_from_slice(&seed.to_le_bytes());
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend_from_slice(&start.to_le_bytes());
+        data.extend_from_slice(&cliff.to_le_bytes());
+        data.extend_from_slice(&end.to_le_bytes());
+        data.extend_from_slice(beneficiary.as_ref());
+        data
+    }
+    
+    fn serialize_make_htlc_instruction(
+        seed: u64,
+        amount: u64,
+        expiry: i64,
+        recipient: &Pubkey,
+        sha256_hash: &[u8; 32],
+        keccak_hash: &[u8; 32],
+    ) -> Vec<u8> {
+        let mut data = vec![MAKE_HTLC_DISCRIMINATOR];
+        data.extend_from_slice(&seed.to_le_bytes());
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend_from_slice(&expiry.to_le_bytes());
+        data.extend_from_slice(recipient.as_ref());
+        data.extend_from_slice(sha256_hash);
+        data.extend_from_slice(keccak_hash);
+        data
+    }
+    
+    fn serialize_make_auction_instruction(seed: u64, amount: u64, reserve: u64, min_increment: u64, end: i64) -> Vec<u8> {
+        let mut data = vec![MAKE_AUCTION_DISCRIMINATOR];
+        data.extend_from_slice(&seed.to_le_bytes());
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend_from_slice(&reserve.to_le_bytes());
+        data.extend_from_slice(&min_increment.to_le_bytes());
+        data.extend_from_slice(&end.to_le_bytes());
+        data
+    }
+    
+    fn serialize_make_bundle_instruction(seed: u64, amounts_a: &[u64], legs_b: &[(Pubkey, u64)]) -> Vec<u8> {
+        let mut data = vec![MAKE_BUNDLE_DISCRIMINATOR];
+        data.extend_from_slice(&seed.to_le_bytes());
+        data.push(amounts_a.len() as u8);
+        data.push(legs_b.len() as u8);
+        for amount in amounts_a {
+            data.extend_from_slice(&amount.to_le_bytes());
+        }
+        for (mint, amount) in legs_b {
+            data.extend_from_slice(mint.as_ref());
+            data.extend_from_slice(&amount.to_le_bytes());
+        }
+        data
+    }
+    
+    fn serialize_counter_offer_instruction(receive: u64) -> Vec<u8> {
+        let mut data = vec![COUNTER_OFFER_DISCRIMINATOR];
+        data.extend_from_slice(&receive.to_le_bytes());
+        data
+    }
+    
+    fn serialize_take_many_instruction(budget: u64, min_receive: u64) -> Vec<u8> {
+        let mut data = vec![TAKE_MANY_DISCRIMINATOR];
+        data.extend_from_slice(&budget.to_le_bytes());
+        data.extend_from_slice(&min_receive.to_le_bytes());
+        data
+    }
+    
+    fn serialize_place_bid_instruction(amount: u64) -> Vec<u8> {
+        let mut data = vec![PLACE_BID_DISCRIMINATOR];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data
+    }
+    
+    fn serialize_claim_htlc_instruction(preimage: &[u8]) -> Vec<u8> {
+        let mut data = vec![CLAIM_HTLC_DISCRIMINATOR];
+        data.extend_from_slice(preimage);
+        data
+    }
+    
     fn parse_escrow_account(&self, account_data: &[u8]) -> Result<EscrowData, String> {
-        if account_data.len() != 130 {
+        if account_data.len() != ESCROW_LEN {
             return Err(format!("Invalid escrow account size: {}", account_data.len()));
         }
         
@@ -256,8 +644,21 @@ impl TestContext {
         let mint_a = Pubkey::new_from_array(account_data[48..80].try_into().unwrap());
         let mint_b = Pubkey::new_from_array(account_data[80..112].try_into().unwrap());
         let receive = u64::from_le_bytes(account_data[112..120].try_into().unwrap());
-        let expiry = i64::from_le_bytes(account_data[120..128].try_into().unwrap());
-        let bump = account_data[128];
+        let deposit = u64::from_le_bytes(account_data[120..128].try_into().unwrap());
+        let expiry = i64::from_le_bytes(account_data[128..136].try_into().unwrap());
+        let bounty = u64::from_le_bytes(account_data[136..144].try_into().unwrap());
+        let taker = Pubkey::new_from_array(account_data[144..176].try_into().unwrap());
+        let merkle_root = account_data[176..208].try_into().unwrap();
+        let start_receive = u64::from_le_bytes(account_data[208..216].try_into().unwrap());
+        let auction_start = i64::from_le_bytes(account_data[216..224].try_into().unwrap());
+        let auction_end = i64::from_le_bytes(account_data[224..232].try_into().unwrap());
+        let price_feed = Pubkey::new_from_array(account_data[232..264].try_into().unwrap());
+        let feed_id = account_data[264..296].try_into().unwrap();
+        let max_staleness = u64::from_le_bytes(account_data[296..304].try_into().unwrap());
+        let max_conf_bps = u16::from_le_bytes(account_data[304..306].try_into().unwrap());
+        let spread_bps = i16::from_le_bytes(account_data[306..308].try_into().unwrap());
+        let collection = Pubkey::new_from_array(account_data[308..340].try_into().unwrap());
+        let bump = account_data[340];
         
         Ok(EscrowData {
             discriminator,
@@ -266,12 +667,36 @@ impl TestContext {
             mint_a,
             mint_b,
             receive,
+            deposit,
             expiry,
+            bounty,
+            taker,
+            merkle_root,
+            start_receive,
+            auction_start,
+            auction_end,
+            price_feed,
+            feed_id,
+            max_staleness,
+            max_conf_bps,
+            spread_bps,
+            collection,
             bump,
         })
     }
 }
 
+/// Asserts that a transaction failed with the given program error
+fn assert_program_error(result: TransactionResult, error: PinocchioError) {
+    let err = result.expect_err("transaction should have failed").err;
+    assert!(
+        matches!(err, TransactionError::InstructionError(_, InstructionError::Custom(code)) if code == error.clone() as u32),
+        "expected {:?}, got {:?}",
+        error,
+        err,
+    );
+}
+
 #[derive(Debug)]
 #[allow(dead_code)]
 struct EscrowData {
@@ -281,10 +706,80 @@ struct EscrowData {
     mint_a: Pubkey,
     mint_b: Pubkey,
     receive: u64,
+    deposit: u64,
     expiry: i64,
+    bounty: u64,
+    taker: Pubkey,
+    merkle_root: [u8; 32],
+    start_receive: u64,
+    auction_start: i64,
+    auction_end: i64,
+    price_feed: Pubkey,
+    feed_id: [u8; 32],
+    max_staleness: u64,
+    max_conf_bps: u16,
+    spread_bps: i16,
+    collection: Pubkey,
     bump: u8,
 }
 
+/// Hashes a pair of merkle nodes in sorted order, matching the on-chain verifier
+fn hash_merkle_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    solana_sdk::hash::hashv(&[left, right]).to_bytes()
+}
+
+/// Builds a merkle tree over the given wallets and returns the root with the proof for `index`
+fn merkle_root_and_proof(wallets: &[Pubkey], index: usize) -> ([u8; 32], Vec<[u8; 32]>) {
+    let mut level: Vec<[u8; 32]> = wallets
+        .iter()
+        .map(|wallet| solana_sdk::hash::hash(wallet.as_ref()).to_bytes())
+        .collect();
+    let mut index = index;
+    let mut proof = Vec::new();
+    
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            level.push(*level.last().unwrap());
+        }
+        proof.push(level[index ^ 1]);
+        level = level
+            .chunks(2)
+            .map(|pair| hash_merkle_pair(&pair[0], &pair[1]))
+            .collect();
+        index /= 2;
+    }
+    
+    (level[0], proof)
+}
+
+/// Builds an open escrow selling 500_000_000 of mint A for 800_000_000 of mint B
+fn new_escrow() -> Escrow {
+    Escrow {
+        discriminator: ESCROW_DISCRIMINATOR,
+        seed: 42,
+        maker: [1; 32],
+        mint_a: [2; 32],
+        mint_b: [3; 32],
+        receive: 800_000_000,
+        deposit: 500_000_000,
+        expiry: 0,
+        bounty: 0,
+        taker: [0; 32],
+        merkle_root: [0; 32],
+        start_receive: 0,
+        auction_start: 0,
+        auction_end: 0,
+        price_feed: [0; 32],
+        feed_id: [0; 32],
+        max_staleness: 0,
+        max_conf_bps: [0; 2],
+        spread_bps: [0; 2],
+        collection: [0; 32],
+        bump: [255],
+    }
+}
+
 #[test]
 fn test_make_escrow() {
     let mut ctx = TestContext::new();
@@ -301,7 +796,9 @@ fn test_make_escrow() {
     let vault = ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey());
     let maker_ata_a = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_a.pubkey());
     
-    let instruction_data = TestContext::serialize_make_instruction(seed, receive, amount, expiry);
+    let (config, _) = ctx.get_config_pda();
+    
+    let instruction_data = TestContext::serialize_make_instruction(seed, receive, amount, expiry, 0, &Pubkey::default(), &[0; 32], (0, 0, 0), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
     
     let accounts = vec![
         AccountMeta::new(ctx.maker.pubkey(), true),
@@ -310,6 +807,7 @@ fn test_make_escrow() {
         AccountMeta::new_readonly(ctx.mint_b.pubkey(), false),
         AccountMeta::new(maker_ata_a, false),
         AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(config, false),
         AccountMeta::new_readonly(system_program::ID, false),
         AccountMeta::new_readonly(spl_token::ID, false),
         AccountMeta::new_readonly(spl_associated_token_account::ID, false),
@@ -340,41 +838,101 @@ fn test_make_escrow() {
 }
 
 #[test]
-fn test_take_escrow() {
+fn test_make_escrow_token_2022() {
     let mut ctx = TestContext::new();
-    ctx.create_mints();
-    ctx.create_token_accounts();
-    ctx.mint_tokens(1_000_000_000, 2_000_000_000);
+    if !ctx.deploy() {
+        return;
+    }
+    ctx.set_config(0);
     
-    let seed = 42u64;
-    let _receive = 800_000_000u64;
-    let _amount = 500_000_000u64;
-    let _expiry = 0i64;
+    let seed = 7u64;
+    let receive = 800_000_000u64;
+    let amount = 500_000_000u64;
+    let expiry = 0i64;
     
-    // First, create the escrow (would need program deployed)
-    // ... make instruction here ...
+    // Token-2022 shares the legacy instruction layout, so the spl-token builders are
+    // pointed at it (they reject any other program ID up front)
+    let to_token_2022 = |mut ix: Instruction| {
+        ix.program_id = TOKEN_2022_PROGRAM_ID;
+        ix
+    };
+    let rent = ctx.svm.minimum_balance_for_rent_exemption(Mint::LEN);
+    for mint in [&ctx.mint_a, &ctx.mint_b] {
+        let create_mint_ix = system_instruction::create_account(
+            &ctx.mint_authority.pubkey(),
+            &mint.pubkey(),
+            rent,
+            Mint::LEN as u64,
+            &TOKEN_2022_PROGRAM_ID,
+        );
+        let init_mint_ix = to_token_2022(spl_token::instruction::initialize_mint(
+            &spl_token::ID,
+            &mint.pubkey(),
+            &ctx.mint_authority.pubkey(),
+            None,
+            9,
+        ).unwrap());
+        
+        let tx = Transaction::new_signed_with_payer(
+            &[create_mint_ix, init_mint_ix],
+            Some(&ctx.mint_authority.pubkey()),
+            &[&ctx.mint_authority, mint],
+            ctx.svm.latest_blockhash(),
+        );
+        ctx.svm.send_transaction(tx).unwrap();
+    }
     
-    // Then test take instruction
+    // The vault and the maker's ATA are derived with the Token-2022 program
     let (escrow_pda, _bump) = ctx.get_escrow_pda(&ctx.maker.pubkey(), seed);
-    let vault = ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey());
-    let taker_ata_a = ctx.get_associated_token_address(&ctx.taker.pubkey(), &ctx.mint_a.pubkey());
-    let taker_ata_b = ctx.get_associated_token_address(&ctx.taker.pubkey(), &ctx.mint_b.pubkey());
-    let maker_ata_b = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_b.pubkey());
+    let vault = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &escrow_pda,
+        &ctx.mint_a.pubkey(),
+        &TOKEN_2022_PROGRAM_ID,
+    );
+    let maker_ata_a = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &ctx.maker.pubkey(),
+        &ctx.mint_a.pubkey(),
+        &TOKEN_2022_PROGRAM_ID,
+    );
+    assert_ne!(vault, ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey()));
+    assert_ne!(maker_ata_a, ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_a.pubkey()));
+    
+    let create_maker_ata_a_ix = spl_associated_token_account::instruction::create_associated_token_account(
+        &ctx.maker.pubkey(),
+        &ctx.maker.pubkey(),
+        &ctx.mint_a.pubkey(),
+        &TOKEN_2022_PROGRAM_ID,
+    );
+    let mint_to_maker_ix = to_token_2022(spl_token::instruction::mint_to(
+        &spl_token::ID,
+        &ctx.mint_a.pubkey(),
+        &maker_ata_a,
+        &ctx.mint_authority.pubkey(),
+        &[],
+        1_000_000_000,
+    ).unwrap());
+    let tx = Transaction::new_signed_with_payer(
+        &[create_maker_ata_a_ix, mint_to_maker_ix],
+        Some(&ctx.maker.pubkey()),
+        &[&ctx.maker, &ctx.mint_authority],
+        ctx.svm.latest_blockhash(),
+    );
+    ctx.svm.send_transaction(tx).unwrap();
     
-    let instruction_data = TestContext::serialize_take_instruction();
+    let (config, _) = ctx.get_config_pda();
+    
+    let instruction_data = TestContext::serialize_make_instruction(seed, receive, amount, expiry, 0, &Pubkey::default(), &[0; 32], (0, 0, 0), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
     
     let accounts = vec![
-        AccountMeta::new(ctx.taker.pubkey(), true),
-        AccountMeta::new(ctx.maker.pubkey(), false),
+        AccountMeta::new(ctx.maker.pubkey(), true),
         AccountMeta::new(escrow_pda, false),
         AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
         AccountMeta::new_readonly(ctx.mint_b.pubkey(), false),
+        AccountMeta::new(maker_ata_a, false),
         AccountMeta::new(vault, false),
-        AccountMeta::new(taker_ata_a, false),
-        AccountMeta::new(taker_ata_b, false),
-        AccountMeta::new(maker_ata_b, false),
+        AccountMeta::new_readonly(config, false),
         AccountMeta::new_readonly(system_program::ID, false),
-        AccountMeta::new_readonly(spl_token::ID, false),
+        AccountMeta::new_readonly(TOKEN_2022_PROGRAM_ID, false),
         AccountMeta::new_readonly(spl_associated_token_account::ID, false),
     ];
     
@@ -386,47 +944,65 @@ fn test_take_escrow() {
     
     let tx = Transaction::new_signed_with_payer(
         &[ix],
-        Some(&ctx.taker.pubkey()),
-        &[&ctx.taker],
+        Some(&ctx.maker.pubkey()),
+        &[&ctx.maker],
         ctx.svm.latest_blockhash(),
     );
     
-    let _result = ctx.svm.send_transaction(tx);
+    ctx.svm.send_transaction(tx).unwrap();
     
-    // Note: Without the actual program deployed and escrow created, this will fail
-    // In a real test, we would verify the token swap completed
+    // The vault was created by the Token-2022 program and holds the deposit
+    let vault_account = ctx.svm.get_account(&vault).unwrap();
+    assert_eq!(vault_account.owner, TOKEN_2022_PROGRAM_ID);
+    assert_eq!(ctx.get_token_balance(&vault), amount);
+    assert_eq!(ctx.get_token_balance(&maker_ata_a), 1_000_000_000 - amount);
     
-    println!("Take escrow test completed (program not deployed)");
+    let escrow_account = ctx.svm.get_account(&escrow_pda).unwrap();
+    let escrow = ctx.parse_escrow_account(&escrow_account.data).unwrap();
+    assert_eq!(escrow.deposit, amount);
+    assert_eq!(escrow.receive, receive);
+    
+    println!("Make escrow with Token-2022 test passed!");
 }
 
 #[test]
-fn test_refund_escrow() {
+fn test_make_escrow_native_sol() {
     let mut ctx = TestContext::new();
+    
+    // The program's native mint is the legacy wrapped SOL mint
+    let native_mint = Pubkey::new_from_array(NATIVE_MINT);
+    assert_eq!(native_mint, spl_token::native_mint::id());
+    
+    if !ctx.deploy() {
+        return;
+    }
+    ctx.set_config(0);
     ctx.create_mints();
     ctx.create_token_accounts();
     ctx.mint_tokens(1_000_000_000, 2_000_000_000);
     
-    let seed = 42u64;
-    let _receive = 800_000_000u64;
-    let _amount = 500_000_000u64;
-    let _expiry = 0i64;
+    ctx.create_native_mint();
     
-    // First, create the escrow (would need program deployed)
-    // ... make instruction here ...
+    let seed = 9u64;
+    let receive = 800_000_000u64;
+    let amount = LAMPORTS_PER_SOL;
     
-    // Then test refund instruction
+    // The vault holds wSOL; the maker funds it from lamports, so maker_ata_a is never touched
     let (escrow_pda, _bump) = ctx.get_escrow_pda(&ctx.maker.pubkey(), seed);
-    let vault = ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey());
-    let maker_ata_a = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_a.pubkey());
+    let vault = ctx.get_associated_token_address(&escrow_pda, &native_mint);
+    let maker_ata_a = ctx.get_associated_token_address(&ctx.maker.pubkey(), &native_mint);
+    let (config, _) = ctx.get_config_pda();
     
-    let instruction_data = TestContext::serialize_refund_instruction();
+    let instruction_data = TestContext::serialize_make_instruction(seed, receive, amount, 0, 0, &Pubkey::default(), &[0; 32], (0, 0, 0), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
     
     let accounts = vec![
         AccountMeta::new(ctx.maker.pubkey(), true),
         AccountMeta::new(escrow_pda, false),
-        AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
-        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(native_mint, false),
+        AccountMeta::new_readonly(ctx.mint_b.pubkey(), false),
         AccountMeta::new(maker_ata_a, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(config, false),
         AccountMeta::new_readonly(system_program::ID, false),
         AccountMeta::new_readonly(spl_token::ID, false),
         AccountMeta::new_readonly(spl_associated_token_account::ID, false),
@@ -445,41 +1021,99 @@ fn test_refund_escrow() {
         ctx.svm.latest_blockhash(),
     );
     
-    let _result = ctx.svm.send_transaction(tx);
+    ctx.svm.send_transaction(tx).unwrap();
     
-    // Note: Without the actual program deployed and escrow created, this will fail
-    // In a real test, we would verify the refund completed
+    // SyncNative credited the wrapped lamports to the vault's token balance
+    let vault_rent = ctx.svm.minimum_balance_for_rent_exemption(TokenAccount::LEN);
+    assert_eq!(ctx.get_token_balance(&vault), amount);
+    assert_eq!(ctx.svm.get_account(&vault).unwrap().lamports, vault_rent + amount);
+    assert!(ctx.svm.get_account(&maker_ata_a).is_none());
     
-    println!("Refund escrow test completed (program not deployed)");
+    // The taker receives wSOL into the ["unwrap", taker] account, which is closed to them
+    // within the take. The maker's mint B ATA already exists and the mint authority pays the
+    // fees, so the taker's lamports move by the fill alone
+    ctx.fund_token_account(&ctx.maker.pubkey(), &ctx.mint_b.pubkey(), 0);
+    let (unwrap_account, _) = Pubkey::find_program_address(&[b"unwrap", ctx.taker.pubkey().as_ref()], &ctx.program_id);
+    let taker_ata_b = ctx.get_associated_token_address(&ctx.taker.pubkey(), &ctx.mint_b.pubkey());
+    let maker_ata_b = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_b.pubkey());
+    let treasury_ata_b = ctx.get_associated_token_address(&ctx.treasury.pubkey(), &ctx.mint_b.pubkey());
+    let take_ix = Instruction {
+        program_id: ctx.program_id,
+        accounts: vec![
+            AccountMeta::new(ctx.taker.pubkey(), true),
+            AccountMeta::new(ctx.maker.pubkey(), false),
+            AccountMeta::new(escrow_pda, false),
+            AccountMeta::new_readonly(native_mint, false),
+            AccountMeta::new_readonly(ctx.mint_b.pubkey(), false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(unwrap_account, false),
+            AccountMeta::new(taker_ata_b, false),
+            AccountMeta::new(maker_ata_b, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(ctx.treasury.pubkey(), false),
+            AccountMeta::new(treasury_ata_b, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        ],
+        data: TestContext::serialize_take_instruction(400_000_000, 0, 400_000_000, amount / 2, &[]),
+    };
+    let taker_lamports = ctx.svm.get_account(&ctx.taker.pubkey()).unwrap().lamports;
+    let tx = Transaction::new_signed_with_payer(&[take_ix], Some(&ctx.mint_authority.pubkey()), &[&ctx.mint_authority, &ctx.taker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    assert_eq!(ctx.svm.get_account(&ctx.taker.pubkey()).unwrap().lamports, taker_lamports + amount / 2);
+    assert!(ctx.svm.get_account(&unwrap_account).is_none_or(|account| account.lamports == 0));
+    assert_eq!(ctx.get_token_balance(&vault), amount / 2);
+    assert_eq!(ctx.svm.get_account(&vault).unwrap().lamports, vault_rent + amount / 2);
+    assert_eq!(ctx.get_token_balance(&maker_ata_b), 400_000_000);
+    
+    println!("Make escrow with native SOL test passed!");
 }
 
 #[test]
-fn test_escrow_with_expiry() {
+fn test_take_escrow() {
     let mut ctx = TestContext::new();
     ctx.create_mints();
     ctx.create_token_accounts();
     ctx.mint_tokens(1_000_000_000, 2_000_000_000);
     
-    let seed = 100u64;
-    let receive = 750_000_000u64;
-    let amount = 400_000_000u64;
-    let expiry = 1735689600i64; // Some future timestamp
+    let seed = 42u64;
+    let _receive = 800_000_000u64;
+    let _amount = 500_000_000u64;
+    let _expiry = 0i64;
+    
+    // First, create the escrow (would need program deployed)
+    // ... make instruction here ...
     
+    // Then test take instruction
     let (escrow_pda, _bump) = ctx.get_escrow_pda(&ctx.maker.pubkey(), seed);
     let vault = ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey());
-    let maker_ata_a = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_a.pubkey());
+    let taker_ata_a = ctx.get_associated_token_address(&ctx.taker.pubkey(), &ctx.mint_a.pubkey());
+    let taker_ata_b = ctx.get_associated_token_address(&ctx.taker.pubkey(), &ctx.mint_b.pubkey());
+    let maker_ata_b = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_b.pubkey());
+    let (config, _) = ctx.get_config_pda();
+    let treasury_ata_b = ctx.get_associated_token_address(&ctx.treasury.pubkey(), &ctx.mint_b.pubkey());
     
-    let instruction_data = TestContext::serialize_make_instruction(seed, receive, amount, expiry);
+    let instruction_data = TestContext::serialize_take_instruction(800_000_000, 0, 800_000_000, 500_000_000, &[]);
     
     let accounts = vec![
-        AccountMeta::new(ctx.maker.pubkey(), true),
+        AccountMeta::new(ctx.taker.pubkey(), true),
+        AccountMeta::new(ctx.maker.pubkey(), false),
         AccountMeta::new(escrow_pda, false),
         AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
         AccountMeta::new_readonly(ctx.mint_b.pubkey(), false),
-        AccountMeta::new(maker_ata_a, false),
         AccountMeta::new(vault, false),
+        AccountMeta::new(taker_ata_a, false),
+        AccountMeta::new(taker_ata_b, false),
+        AccountMeta::new(maker_ata_b, false),
+        AccountMeta::new_readonly(config, false),
+        AccountMeta::new(ctx.treasury.pubkey(), false),
+        AccountMeta::new(treasury_ata_b, false),
         AccountMeta::new_readonly(system_program::ID, false),
         AccountMeta::new_readonly(spl_token::ID, false),
+        AccountMeta::new_readonly(spl_token::ID, false),
         AccountMeta::new_readonly(spl_associated_token_account::ID, false),
     ];
     
@@ -491,44 +1125,1987 @@ fn test_escrow_with_expiry() {
     
     let tx = Transaction::new_signed_with_payer(
         &[ix],
-        Some(&ctx.maker.pubkey()),
-        &[&ctx.maker],
+        Some(&ctx.taker.pubkey()),
+        &[&ctx.taker],
         ctx.svm.latest_blockhash(),
     );
     
     let _result = ctx.svm.send_transaction(tx);
     
-    // Note: With the program deployed, we could test expiry logic
-    // by warping the clock and attempting a take after expiry
+    // Note: Without the actual program deployed and escrow created, this will fail
+    // In a real test, we would verify the token swap completed
     
-    println!("Escrow with expiry test completed (program not deployed)");
+    println!("Take escrow test completed (program not deployed)");
 }
 
 #[test]
-fn test_invalid_amount() {
+fn test_partial_take_escrow() {
     let mut ctx = TestContext::new();
+    if !ctx.deploy() {
+        return;
+    }
+    // A 1% protocol fee, of which referrers may claim up to half
+    ctx.write_config(100, 5_000, false);
     ctx.create_mints();
     ctx.create_token_accounts();
     ctx.mint_tokens(1_000_000_000, 2_000_000_000);
     
     let seed = 42u64;
-    let receive = 800_000_000u64;
-    let amount = 0u64; // Invalid: zero amount
-    let expiry = 0i64;
+    let make_data = TestContext::serialize_make_instruction(seed, 800_000_000, 500_000_000, 0, 0, &Pubkey::default(), &[0; 32], (0, 0, 0), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
+    let make_ix = ctx.make_instruction(&ctx.maker.pubkey(), seed, &ctx.mint_b.pubkey(), make_data);
+    let tx = Transaction::new_signed_with_payer(&[make_ix], Some(&ctx.maker.pubkey()), &[&ctx.maker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    // An aggregator routed this taker and claims 25 bps of the fee
+    let referrer = Keypair::new();
+    let referrer_ata_b = ctx.fund_token_account(&referrer.pubkey(), &ctx.mint_b.pubkey(), 0);
+    
+    // Fill a quarter of the escrow
+    let take_data = TestContext::serialize_take_instruction(200_000_000, 25, 200_000_000, 125_000_000, &[]);
+    let take_ix = ctx.take_instruction(&ctx.taker.pubkey(), seed, take_data, &[AccountMeta::new(referrer_ata_b, false)]);
+    let tx = Transaction::new_signed_with_payer(&[take_ix], Some(&ctx.taker.pubkey()), &[&ctx.taker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
     
     let (escrow_pda, _bump) = ctx.get_escrow_pda(&ctx.maker.pubkey(), seed);
     let vault = ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey());
-    let maker_ata_a = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_a.pubkey());
+    let taker_ata_a = ctx.get_associated_token_address(&ctx.taker.pubkey(), &ctx.mint_a.pubkey());
+    let taker_ata_b = ctx.get_associated_token_address(&ctx.taker.pubkey(), &ctx.mint_b.pubkey());
+    let maker_ata_b = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_b.pubkey());
+    let treasury_ata_b = ctx.get_associated_token_address(&ctx.treasury.pubkey(), &ctx.mint_b.pubkey());
+    assert_eq!(ctx.get_token_balance(&taker_ata_a), 125_000_000);
+    assert_eq!(ctx.get_token_balance(&vault), 375_000_000);
+    assert_eq!(ctx.get_token_balance(&taker_ata_b), 1_800_000_000);
+    assert_eq!(ctx.get_token_balance(&maker_ata_b), 198_000_000);
+    assert_eq!(ctx.get_token_balance(&treasury_ata_b), 1_995_000);
+    assert_eq!(ctx.get_token_balance(&referrer_ata_b), 5_000);
+    
+    // The escrow stays open for the rest at the same price
+    let escrow = ctx.parse_escrow_account(&ctx.svm.get_account(&escrow_pda).unwrap().data).unwrap();
+    assert_eq!(escrow.receive, 600_000_000);
+    assert_eq!(escrow.deposit, 375_000_000);
+    
+    println!("Partial take escrow test passed!");
+}
+
+#[test]
+fn test_partial_fill_amounts() {
+    let mut escrow = new_escrow();
     
-    let instruction_data = TestContext::serialize_make_instruction(seed, receive, amount, expiry);
+    // A quarter of the receive releases a quarter of the deposit
+    assert_eq!(escrow.deposit_for(200_000_000), 125_000_000);
     
-    let accounts = vec![
+    // Filling the whole receive releases the whole deposit
+    assert_eq!(escrow.deposit_for(800_000_000), 500_000_000);
+    
+    // Rounding favors the maker
+    escrow.receive = 3;
+    escrow.deposit = 2;
+    assert_eq!(escrow.deposit_for(1), 0);
+    assert_eq!(escrow.deposit_for(2), 1);
+    
+    println!("Partial fill amounts test passed!");
+}
+
+#[test]
+fn test_designated_taker() {
+    let mut escrow = new_escrow();
+    
+    // A zeroed taker keeps the escrow public
+    assert!(escrow.is_taker_allowed(&[4; 32]));
+    assert!(escrow.is_taker_allowed(&[5; 32]));
+    
+    // A designated taker locks out everyone else
+    escrow.taker = [4; 32];
+    assert!(escrow.is_taker_allowed(&[4; 32]));
+    assert!(!escrow.is_taker_allowed(&[5; 32]));
+    
+    println!("Designated taker test passed!");
+}
+
+#[test]
+fn test_allowlisted_take_escrow() {
+    let mut ctx = TestContext::new();
+    
+    // The maker allowlists three KYC'd wallets, including the taker
+    let wallets = [Pubkey::new_unique(), ctx.taker.pubkey(), Pubkey::new_unique()];
+    let (merkle_root, proof) = merkle_root_and_proof(&wallets, 1);
+    assert_eq!(proof.len(), 2);
+    
+    // The proof folds back to the root
+    let leaf = solana_sdk::hash::hash(ctx.taker.pubkey().as_ref()).to_bytes();
+    let root = proof.iter().fold(leaf, |node, sibling| hash_merkle_pair(&node, sibling));
+    assert_eq!(root, merkle_root);
+    
+    // The proof is appended after the slippage bounds
+    let take_data = TestContext::serialize_take_instruction(800_000_000, 0, u64::MAX, 0, &proof);
+    assert_eq!(take_data.len(), 1 + 8 + 2 + 8 + 8 + 2 * 32);
+    assert_eq!(&take_data[27..59], &proof[0]);
+    assert_eq!(&take_data[59..91], &proof[1]);
+    
+    if !ctx.deploy() {
+        return;
+    }
+    ctx.set_config(0);
+    ctx.create_mints();
+    ctx.create_token_accounts();
+    ctx.mint_tokens(1_000_000_000, 2_000_000_000);
+    
+    let seed = 42u64;
+    let make_data = TestContext::serialize_make_instruction(seed, 800_000_000, 500_000_000, 0, 0, &Pubkey::default(), &merkle_root, (0, 0, 0), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
+    let make_ix = ctx.make_instruction(&ctx.maker.pubkey(), seed, &ctx.mint_b.pubkey(), make_data);
+    let tx = Transaction::new_signed_with_payer(&[make_ix], Some(&ctx.maker.pubkey()), &[&ctx.maker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    // Another wallet's proof doesn't prove the taker's key
+    let (_, wrong_proof) = merkle_root_and_proof(&wallets, 0);
+    let wrong_data = TestContext::serialize_take_instruction(800_000_000, 0, u64::MAX, 0, &wrong_proof);
+    let take_ix = ctx.take_instruction(&ctx.taker.pubkey(), seed, wrong_data, &[]);
+    let tx = Transaction::new_signed_with_payer(&[take_ix], Some(&ctx.taker.pubkey()), &[&ctx.taker], ctx.svm.latest_blockhash());
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::InvalidProof);
+    
+    // The taker's own proof fills the escrow
+    let take_ix = ctx.take_instruction(&ctx.taker.pubkey(), seed, take_data, &[]);
+    let tx = Transaction::new_signed_with_payer(&[take_ix], Some(&ctx.taker.pubkey()), &[&ctx.taker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    let taker_ata_a = ctx.get_associated_token_address(&ctx.taker.pubkey(), &ctx.mint_a.pubkey());
+    let maker_ata_b = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_b.pubkey());
+    assert_eq!(ctx.get_token_balance(&taker_ata_a), 500_000_000);
+    assert_eq!(ctx.get_token_balance(&maker_ata_b), 800_000_000);
+    
+    println!("Allowlisted take escrow test passed!");
+}
+
+#[test]
+fn test_merkle_proof_verification() {
+    let taker = Pubkey::new_unique();
+    let wallets = [Pubkey::new_unique(), taker, Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let (merkle_root, proof) = merkle_root_and_proof(&wallets, 1);
+    assert_eq!(proof.len(), 3);
+    
+    // The on-chain verifier folds the concatenated siblings back to the root
+    let proof_bytes = proof.concat();
+    assert!(verify_merkle_proof(&proof_bytes, &merkle_root, &taker.to_bytes()));
+    
+    // A wallet outside the tree can't reuse someone else's proof
+    assert!(!verify_merkle_proof(&proof_bytes, &merkle_root, &Pubkey::new_unique().to_bytes()));
+    
+    // Flipping a single bit of any sibling breaks the proof
+    let mut tampered = proof_bytes.clone();
+    tampered[40] ^= 1;
+    assert!(!verify_merkle_proof(&tampered, &merkle_root, &taker.to_bytes()));
+    
+    // A truncated proof no longer reaches the root
+    assert!(!verify_merkle_proof(&proof_bytes[..64], &merkle_root, &taker.to_bytes()));
+    
+    // Proofs that aren't a whole number of hashes are rejected outright
+    assert!(!verify_merkle_proof(&proof_bytes[..95], &merkle_root, &taker.to_bytes()));
+    let mut padded = proof_bytes.clone();
+    padded.push(0);
+    assert!(!verify_merkle_proof(&padded, &merkle_root, &taker.to_bytes()));
+    
+    // A one-wallet allowlist has the leaf as its root and an empty proof
+    let (single_root, single_proof) = merkle_root_and_proof(&[taker], 0);
+    assert!(single_proof.is_empty());
+    assert!(verify_merkle_proof(&[], &single_root, &taker.to_bytes()));
+    
+    println!("Merkle proof verification test passed!");
+}
+
+#[test]
+fn test_dutch_auction_amounts() {
+    // Opens at 1_200_000_000 of mint B and falls to the 800_000_000 floor over 1_000 seconds
+    let mut escrow = new_escrow();
+    escrow.set_auction(1_200_000_000, 1_000, 2_000);
+    assert!(escrow.has_auction());
+    assert!(!new_escrow().has_auction());
+    assert_eq!(Escrow::LEN, 341);
+    
+    assert_eq!(escrow.receive_at(0), 1_200_000_000);
+    assert_eq!(escrow.receive_at(1_000), 1_200_000_000);
+    assert_eq!(escrow.receive_at(1_250), 1_100_000_000);
+    assert_eq!(escrow.receive_at(1_500), 1_000_000_000);
+    assert_eq!(escrow.receive_at(2_000), 800_000_000);
+    assert_eq!(escrow.receive_at(i64::MAX), 800_000_000);
+    
+    // A fill for half the current price releases half the deposit
+    let price = escrow.receive_at(1_500);
+    assert_eq!(escrow.deposit_at_price(500_000_000, price), 250_000_000);
+    
+    // Both ends of the schedule shrink with the deposit, keeping the per-unit price curve
+    assert_eq!(remaining_after_fill(escrow.start_receive, 500_000_000, price), 600_000_000);
+    assert_eq!(remaining_after_fill(escrow.receive, 500_000_000, price), 400_000_000);
+    
+    // Fixed-price fills still just subtract from the remaining receive amount
+    let fixed = new_escrow();
+    assert_eq!(remaining_after_fill(fixed.receive, 200_000_000, fixed.receive), 600_000_000);
+    
+    println!("Dutch auction amounts test passed!");
+}
+
+/// Lays out a fully verified Pyth `PriceUpdateV2` account
+fn mock_price_update(feed_id: &[u8; 32], price: i64, conf: u64, exponent: i32, publish_time: i64) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&PRICE_UPDATE_V2_DISCRIMINATOR);
+    data.extend_from_slice(&[9; 32]); // write authority
+    data.push(VERIFICATION_LEVEL_FULL);
+    data.extend_from_slice(feed_id);
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(&conf.to_le_bytes());
+    data.extend_from_slice(&exponent.to_le_bytes());
+    data.extend_from_slice(&publish_time.to_le_bytes());
+    data.extend_from_slice(&(publish_time - 1).to_le_bytes()); // prev publish time
+    data.extend_from_slice(&price.to_le_bytes()); // ema price
+    data.extend_from_slice(&conf.to_le_bytes()); // ema conf
+    data.extend_from_slice(&1u64.to_le_bytes()); // posted slot
+    data
+}
+
+#[test]
+fn test_oracle_price_feed() {
+    let mut ctx = TestContext::new();
+    
+    // SOL/USD at $150.00 +/- $0.15, published at 1_000
+    let price_feed = Pubkey::new_unique();
+    ctx.svm
+        .set_account(
+            price_feed,
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                data: mock_price_update(&[4; 32], 15_000_000_000, 15_000_000, -8, 1_000),
+                owner: Pubkey::new_from_array(PYTH_RECEIVER_ID),
+                executable: false,
+                rent_epoch: 0,
+            },
+        )
+        .unwrap();
+    let account = ctx.svm.get_account(&price_feed).unwrap();
+    assert_eq!(account.owner.to_bytes(), PYTH_RECEIVER_ID);
+    
+    let feed = PriceFeed::parse(&account.data).unwrap();
+    assert_eq!(feed.price, 15_000_000_000);
+    assert_eq!(feed.exponent, -8);
+    
+    // Only the feed the escrow names is accepted
+    assert!(feed.check_feed_id(&[4; 32]).is_ok());
+    assert!(feed.check_feed_id(&[5; 32]).is_err());
+    
+    // Staleness is bounded in seconds since publication
+    assert!(feed.check_fresh(1_060, 60).is_ok());
+    assert!(feed.check_fresh(1_061, 60).is_err());
+    
+    // The confidence interval is 10 bps of the price
+    assert!(feed.check_confidence(10).is_ok());
+    assert!(feed.check_confidence(9).is_err());
+    
+    // 2 SOL (9 decimals) at market minus 1% costs 297 USDC (6 decimals)
+    assert_eq!(feed.quote(2_000_000_000, 9, 6, -100), Some(297_000_000));
+    assert_eq!(feed.quote(2_000_000_000, 9, 6, 0), Some(300_000_000));
+    assert_eq!(feed.quote(2_000_000_000, 9, 6, 50), Some(301_500_000));
+    
+    // Quotes round up in favor of the maker
+    assert_eq!(feed.quote(1, 9, 6, 0), Some(1));
+    
+    // The escrow's receive amount stays a floor under the oracle price
+    let mut escrow = new_escrow();
+    escrow.set_oracle(price_feed.to_bytes(), [4; 32], 60, 10, -100);
+    assert!(escrow.has_oracle());
+    assert!(!new_escrow().has_oracle());
+    assert_eq!(escrow.max_conf_bps(), 10);
+    assert_eq!(escrow.spread_bps(), -100);
+    
+    // Partially verified updates and non-positive prices are rejected
+    let mut partial = mock_price_update(&[4; 32], 15_000_000_000, 15_000_000, -8, 1_000);
+    partial[40] = 0;
+    assert!(PriceFeed::parse(&partial).is_err());
+    assert!(PriceFeed::parse(&mock_price_update(&[4; 32], 0, 0, -8, 1_000)).is_err());
+    assert!(PriceFeed::parse(&account.data[..100]).is_err());
+    
+    println!("Oracle price feed test passed!");
+}
+
+#[test]
+fn test_oracle_priced_take() {
+    let mut ctx = TestContext::new();
+    if !ctx.deploy() {
+        return;
+    }
+    let now = 1_700_000_000i64;
+    ctx.set_config(0);
+    ctx.set_clock(now);
+    ctx.create_mints();
+    ctx.create_token_accounts();
+    ctx.mint_tokens(1_000_000_000, 2_000_000_000);
+    
+    // The maker sells 500 of mint A at the feed price, accepting prices up to a minute
+    // old and 50 bps wide, and never for less than 700 of mint B
+    let seed = 42u64;
+    let price_feed = Pubkey::new_unique();
+    let make_data = TestContext::serialize_make_instruction(seed, 700_000_000, 500_000_000, 0, 0, &Pubkey::default(), &[0; 32], (0, 0, 0), (&price_feed, &[4; 32], 60, 50, 0), &Pubkey::default());
+    let make_ix = ctx.make_instruction(&ctx.maker.pubkey(), seed, &ctx.mint_b.pubkey(), make_data);
+    let tx = Transaction::new_signed_with_payer(&[make_ix], Some(&ctx.maker.pubkey()), &[&ctx.maker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    let (escrow_pda, _bump) = ctx.get_escrow_pda(&ctx.maker.pubkey(), seed);
+    let vault = ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey());
+    let taker_ata_a = ctx.get_associated_token_address(&ctx.taker.pubkey(), &ctx.mint_a.pubkey());
+    let taker_ata_b = ctx.get_associated_token_address(&ctx.taker.pubkey(), &ctx.mint_b.pubkey());
+    let maker_ata_b = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_b.pubkey());
+    let (config, _) = ctx.get_config_pda();
+    let treasury_ata_b = ctx.get_associated_token_address(&ctx.treasury.pubkey(), &ctx.mint_b.pubkey());
+    
+    // The feed is passed after the fixed accounts, where a referrer would otherwise go
+    let take_ix = Instruction {
+        program_id: ctx.program_id,
+        accounts: vec![
+            AccountMeta::new(ctx.taker.pubkey(), true),
+            AccountMeta::new(ctx.maker.pubkey(), false),
+            AccountMeta::new(escrow_pda, false),
+            AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
+            AccountMeta::new_readonly(ctx.mint_b.pubkey(), false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(taker_ata_a, false),
+            AccountMeta::new(taker_ata_b, false),
+            AccountMeta::new(maker_ata_b, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(ctx.treasury.pubkey(), false),
+            AccountMeta::new(treasury_ata_b, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+            AccountMeta::new_readonly(price_feed, false),
+        ],
+        data: TestContext::serialize_take_instruction(u64::MAX, 0, 800_000_000, 500_000_000, &[]),
+    };
+    
+    // 1.6 mint B per mint A, quoting the whole deposit at 800 of mint B
+    let take_with_feed = |ctx: &mut TestContext, feed_id: &[u8; 32], conf: u64, publish_time: i64| -> Transaction {
+        let feed = Account {
+            lamports: LAMPORTS_PER_SOL,
+            data: mock_price_update(feed_id, 160_000_000, conf, -8, publish_time),
+            owner: Pubkey::new_from_array(PYTH_RECEIVER_ID),
+            executable: false,
+            rent_epoch: 0,
+        };
+        ctx.svm.set_account(price_feed, feed).unwrap();
+        ctx.svm.expire_blockhash();
+        Transaction::new_signed_with_payer(std::slice::from_ref(&take_ix), Some(&ctx.taker.pubkey()), &[&ctx.taker], ctx.svm.latest_blockhash())
+    };
+    
+    // The feed's write authority posting another feed's price to the account doesn't fool the escrow
+    let tx = take_with_feed(&mut ctx, &[5; 32], 160_000, now);
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::InvalidPriceFeed);
+    
+    // A price published 61 seconds ago is too old
+    let tx = take_with_feed(&mut ctx, &[4; 32], 160_000, now - 61);
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::StalePrice);
+    
+    // A confidence interval of 100 bps is wider than the maker accepts
+    let tx = take_with_feed(&mut ctx, &[4; 32], 1_600_000, now);
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::PriceTooUncertain);
+    assert_eq!(ctx.get_token_balance(&vault), 500_000_000);
+    
+    // A fresh 10 bps price fills the whole deposit at the quote rather than the floor
+    let tx = take_with_feed(&mut ctx, &[4; 32], 160_000, now - 60);
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(ctx.get_token_balance(&taker_ata_a), 500_000_000);
+    assert_eq!(ctx.get_token_balance(&taker_ata_b), 1_200_000_000);
+    assert_eq!(ctx.get_token_balance(&maker_ata_b), 800_000_000);
+    assert!(ctx.svm.get_account(&escrow_pda).is_none_or(|account| account.lamports == 0));
+    
+    println!("Oracle priced take test passed!");
+}
+
+/// Lays out a Metaplex `Metadata` account for `mint`, with one creator and an optional collection
+fn mock_metadata(mint: &Pubkey, collection: Option<(bool, Pubkey)>) -> Vec<u8> {
+    let mut data = vec![KEY_METADATA_V1];
+    data.extend_from_slice(&[8; 32]); // update authority
+    data.extend_from_slice(mint.as_ref());
+    for field in [&b"Escrow Ape #1"[..], b"EAPE", b"https://example.com/1.json"] {
+        data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        data.extend_from_slice(field);
+    }
+    data.extend_from_slice(&500u16.to_le_bytes()); // seller fee basis points
+    data.push(1); // creators
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&[8; 32]);
+    data.extend_from_slice(&[1, 100]); // verified, share
+    data.extend_from_slice(&[1, 1]); // primary sale happened, is mutable
+    data.extend_from_slice(&[1, 255]); // edition nonce
+    data.extend_from_slice(&[1, 0]); // token standard
+    match collection {
+        Some((verified, key)) => {
+            data.extend_from_slice(&[1, verified as u8]);
+            data.extend_from_slice(key.as_ref());
+        }
+        None => data.push(0),
+    }
+    data.push(0); // uses
+    data
+}
+
+#[test]
+fn test_collection_nft_metadata() {
+    let mut ctx = TestContext::new();
+    let collection = Pubkey::new_unique();
+    let nft = Pubkey::new_unique();
+    
+    // Metadata lives at the canonical PDA of the NFT mint
+    let metadata_program = Pubkey::new_from_array(TOKEN_METADATA_ID);
+    let (metadata_key, _) = Pubkey::find_program_address(
+        &[b"metadata", metadata_program.as_ref(), nft.as_ref()],
+        &metadata_program,
+    );
+    ctx.svm
+        .set_account(
+            metadata_key,
+            Account {
+                lamports: LAMPORTS_PER_SOL,
+                data: mock_metadata(&nft, Some((true, collection))),
+                owner: metadata_program,
+                executable: false,
+                rent_epoch: 0,
+            },
+        )
+        .unwrap();
+    let account = ctx.svm.get_account(&metadata_key).unwrap();
+    
+    let metadata = Metadata::parse(&account.data).unwrap();
+    assert_eq!(metadata.mint, nft.to_bytes());
+    assert!(metadata.is_verified_member_of(&collection.to_bytes()));
+    assert!(!metadata.is_verified_member_of(&Pubkey::new_unique().to_bytes()));
+    
+    // Anyone can claim a collection, so only verified ones count
+    let unverified = Metadata::parse(&mock_metadata(&nft, Some((false, collection)))).unwrap();
+    assert!(!unverified.is_verified_member_of(&collection.to_bytes()));
+    let uncollected = Metadata::parse(&mock_metadata(&nft, None)).unwrap();
+    assert!(!uncollected.is_verified_member_of(&collection.to_bytes()));
+    
+    // Truncated or foreign accounts don't parse
+    assert!(Metadata::parse(&account.data[..100]).is_none());
+    let mut edition = account.data.clone();
+    edition[0] = 6;
+    assert!(Metadata::parse(&edition).is_none());
+    
+    // A collection bid stores the collection instead of a single mint B
+    let mut escrow = new_escrow();
+    assert!(!escrow.has_collection());
+    escrow.set_collection(collection.to_bytes());
+    assert!(escrow.has_collection());
+    assert_eq!(escrow.mint_b, [0; 32]);
+    
+    // Each take hands over one NFT, releasing a proportional share of the deposit
+    escrow.set_receive(5);
+    assert_eq!(escrow.deposit_for(1), 100_000_000);
+    
+    println!("Collection NFT metadata test passed!");
+}
+
+#[test]
+fn test_transfer_fee_amounts() {
+    // 1% fee capped at 5_000_000
+    let transfer_fee = TransferFee {
+        maximum_fee: 5_000_000,
+        transfer_fee_basis_points: 100,
+    };
+    
+    // The maker deposits 100_000_000 and only 99_000_000 lands in the vault
+    assert_eq!(transfer_fee.calculate_fee(100_000_000), 1_000_000);
+    
+    // Fees round up
+    assert_eq!(transfer_fee.calculate_fee(150), 2);
+    
+    // The taker pays enough for the maker to receive exactly the fill amount
+    let gross = transfer_fee.calculate_pre_fee_amount(99_000_000).unwrap();
+    assert_eq!(gross, 100_000_000);
+    assert_eq!(gross - transfer_fee.calculate_fee(gross), 99_000_000);
+    
+    // Large transfers hit the maximum fee
+    assert_eq!(transfer_fee.calculate_fee(1_000_000_000), 5_000_000);
+    assert_eq!(transfer_fee.calculate_pre_fee_amount(800_000_000), Some(805_000_000));
+    
+    // No fee, no gross up
+    let no_fee = TransferFee {
+        maximum_fee: 0,
+        transfer_fee_basis_points: 0,
+    };
+    assert_eq!(no_fee.calculate_fee(800_000_000), 0);
+    assert_eq!(no_fee.calculate_pre_fee_amount(800_000_000), Some(800_000_000));
+    
+    println!("Transfer fee amounts test passed!");
+}
+
+#[test]
+fn test_refund_escrow() {
+    let mut ctx = TestContext::new();
+    ctx.create_mints();
+    ctx.create_token_accounts();
+    ctx.mint_tokens(1_000_000_000, 2_000_000_000);
+    
+    let seed = 42u64;
+    let _receive = 800_000_000u64;
+    let _amount = 500_000_000u64;
+    let _expiry = 0i64;
+    
+    // First, create the escrow (would need program deployed)
+    // ... make instruction here ...
+    
+    // Then test refund instruction
+    let (escrow_pda, _bump) = ctx.get_escrow_pda(&ctx.maker.pubkey(), seed);
+    let vault = ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey());
+    let maker_ata_a = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_a.pubkey());
+    
+    let instruction_data = TestContext::serialize_refund_instruction();
+    
+    let accounts = vec![
+        AccountMeta::new(ctx.maker.pubkey(), true),
+        AccountMeta::new(escrow_pda, false),
+        AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(maker_ata_a, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(spl_token::ID, false),
+        AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+    ];
+    
+    let ix = Instruction {
+        program_id: ctx.program_id,
+        accounts,
+        data: instruction_data,
+    };
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&ctx.maker.pubkey()),
+        &[&ctx.maker],
+        ctx.svm.latest_blockhash(),
+    );
+    
+    let _result = ctx.svm.send_transaction(tx);
+    
+    // Note: Without the actual program deployed and escrow created, this will fail
+    // In a real test, we would verify the refund completed
+    
+    println!("Refund escrow test completed (program not deployed)");
+}
+
+#[test]
+fn test_update_escrow() {
+    let mut ctx = TestContext::new();
+    if !ctx.deploy() {
+        return;
+    }
+    ctx.set_config(0);
+    ctx.create_mints();
+    ctx.create_native_mint();
+    ctx.create_token_accounts();
+    ctx.mint_tokens(1_000_000_000, 2_000_000_000);
+    
+    let seed = 42u64;
+    let expiry = 1_735_689_600i64;
+    let make_data = TestContext::serialize_make_instruction(seed, 800_000_000, 500_000_000, 0, 0, &Pubkey::default(), &[0; 32], (0, 0, 0), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
+    let make_ix = ctx.make_instruction(&ctx.maker.pubkey(), seed, &ctx.mint_b.pubkey(), make_data);
+    let tx = Transaction::new_signed_with_payer(&[make_ix], Some(&ctx.maker.pubkey()), &[&ctx.maker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    // Updates keep the same escrow address
+    let program_id = ctx.program_id;
+    let maker = ctx.maker.pubkey();
+    let update_ix = |seed: u64, mint_a: &Pubkey, maker_ata_a: &Pubkey, data: Vec<u8>| {
+        let (escrow_pda, _) = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &seed.to_le_bytes()], &program_id);
+        Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(maker, true),
+                AccountMeta::new(escrow_pda, false),
+                AccountMeta::new_readonly(*mint_a, false),
+                AccountMeta::new(spl_associated_token_account::get_associated_token_address(&escrow_pda, mint_a), false),
+                AccountMeta::new(*maker_ata_a, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+            ],
+            data,
+        }
+    };
+    let (escrow_pda, _bump) = ctx.get_escrow_pda(&maker, seed);
+    let vault = ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey());
+    let mint_a = ctx.mint_a.pubkey();
+    let maker_ata_a = ctx.get_associated_token_address(&maker, &mint_a);
+    let escrow = |ctx: &TestContext| ctx.parse_escrow_account(&ctx.svm.get_account(&escrow_pda).unwrap().data).unwrap();
+    
+    // Reprice and set an expiry without touching the vault
+    let ix = update_ix(seed, &mint_a, &maker_ata_a, TestContext::serialize_update_instruction(900_000_000, expiry, 0, 0));
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&maker), &[&ctx.maker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(escrow(&ctx).receive, 900_000_000);
+    assert_eq!(escrow(&ctx).expiry, expiry);
+    assert_eq!(escrow(&ctx).deposit, 500_000_000);
+    assert_eq!(ctx.get_token_balance(&vault), 500_000_000);
+    
+    // Top the vault up
+    let ix = update_ix(seed, &mint_a, &maker_ata_a, TestContext::serialize_update_instruction(900_000_000, expiry, 100_000_000, 0));
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&maker), &[&ctx.maker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(escrow(&ctx).deposit, 600_000_000);
+    assert_eq!(ctx.get_token_balance(&vault), 600_000_000);
+    assert_eq!(ctx.get_token_balance(&maker_ata_a), 400_000_000);
+    
+    // Take some back out
+    let ix = update_ix(seed, &mint_a, &maker_ata_a, TestContext::serialize_update_instruction(900_000_000, expiry, 0, 200_000_000));
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&maker), &[&ctx.maker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(escrow(&ctx).deposit, 400_000_000);
+    assert_eq!(ctx.get_token_balance(&vault), 400_000_000);
+    assert_eq!(ctx.get_token_balance(&maker_ata_a), 600_000_000);
+    
+    // Depositing and withdrawing at once is rejected
+    let ix = update_ix(seed, &mint_a, &maker_ata_a, TestContext::serialize_update_instruction(900_000_000, expiry, 10_000_000, 10_000_000));
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&maker), &[&ctx.maker], ctx.svm.latest_blockhash());
+    assert_eq!(
+        ctx.svm.send_transaction(tx).unwrap_err().err,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+    assert_eq!(escrow(&ctx).deposit, 400_000_000);
+    assert_eq!(ctx.get_token_balance(&vault), 400_000_000);
+    
+    // A native SOL escrow withdraws through the ["unwrap", maker] account and leaves the
+    // maker's own wSOL ATA alone
+    let native_mint = spl_token::native_mint::id();
+    let native_seed = 43u64;
+    let make_data = TestContext::serialize_make_instruction(native_seed, 800_000_000, LAMPORTS_PER_SOL, 0, 0, &Pubkey::default(), &[0; 32], (0, 0, 0), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
+    let mut make_ix = ctx.make_instruction(&maker, native_seed, &ctx.mint_b.pubkey(), make_data);
+    let (native_escrow, _) = ctx.get_escrow_pda(&maker, native_seed);
+    let native_vault = ctx.get_associated_token_address(&native_escrow, &native_mint);
+    let maker_wsol_ata = ctx.get_associated_token_address(&maker, &native_mint);
+    make_ix.accounts[2] = AccountMeta::new_readonly(native_mint, false);
+    make_ix.accounts[4] = AccountMeta::new(maker_wsol_ata, false);
+    make_ix.accounts[5] = AccountMeta::new(native_vault, false);
+    let create_wsol_ata_ix = spl_associated_token_account::instruction::create_associated_token_account(&maker, &maker, &native_mint, &spl_token::ID);
+    let tx = Transaction::new_signed_with_payer(&[create_wsol_ata_ix, make_ix], Some(&maker), &[&ctx.maker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    // The mint authority pays the fees so the maker's lamports move by the withdrawal alone
+    let (unwrap_account, _) = Pubkey::find_program_address(&[b"unwrap", maker.as_ref()], &program_id);
+    let maker_lamports = ctx.svm.get_account(&maker).unwrap().lamports;
+    let ix = update_ix(native_seed, &native_mint, &unwrap_account, TestContext::serialize_update_instruction(800_000_000, 0, 0, LAMPORTS_PER_SOL / 4));
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.mint_authority.pubkey()), &[&ctx.mint_authority, &ctx.maker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(ctx.svm.get_account(&maker).unwrap().lamports, maker_lamports + LAMPORTS_PER_SOL / 4);
+    assert_eq!(ctx.get_token_balance(&native_vault), LAMPORTS_PER_SOL * 3 / 4);
+    assert!(ctx.svm.get_account(&unwrap_account).is_none_or(|account| account.lamports == 0));
+    assert!(ctx.svm.get_account(&maker_wsol_ata).is_some_and(|account| account.lamports > 0));
+    
+    println!("Update escrow test passed!");
+}
+
+#[test]
+fn test_initialize_config() {
+    let mut ctx = TestContext::new();
+    if !ctx.deploy() {
+        return;
+    }
+    
+    // The upgrade authority sets up the singleton config and becomes its admin
+    let admin = Keypair::new();
+    let intruder = Keypair::new();
+    ctx.svm.airdrop(&admin.pubkey(), LAMPORTS_PER_SOL).unwrap();
+    ctx.svm.airdrop(&intruder.pubkey(), LAMPORTS_PER_SOL).unwrap();
+    let (config, _bump) = ctx.get_config_pda();
+    
+    // Only the upgrade authority recorded in the program's ProgramData may initialize.
+    // The program is loaded without one, so write the ProgramData header directly
+    assert_eq!(BPF_LOADER_UPGRADEABLE_ID, solana_sdk::bpf_loader_upgradeable::ID.to_bytes());
+    let (program_data, _) = Pubkey::find_program_address(
+        &[ctx.program_id.as_ref()],
+        &solana_sdk::bpf_loader_upgradeable::ID,
+    );
+    let mut data = Vec::new();
+    data.extend_from_slice(&3u32.to_le_bytes()); // ProgramData
+    data.extend_from_slice(&0u64.to_le_bytes()); // deploy slot
+    data.push(1); // has an upgrade authority
+    data.extend_from_slice(admin.pubkey().as_ref());
+    let program_data_account = Account {
+        lamports: ctx.svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner: solana_sdk::bpf_loader_upgradeable::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    ctx.svm.set_account(program_data, program_data_account).unwrap();
+    
+    let initialize_ix = |signer: &Pubkey| Instruction {
+        program_id: ctx.program_id,
+        accounts: vec![
+            AccountMeta::new(*signer, true),
+            AccountMeta::new(config, false),
+            AccountMeta::new_readonly(program_data, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: TestContext::serialize_initialize_config_instruction(&ctx.treasury.pubkey(), 30, 50),
+    };
+    let intruder_ix = initialize_ix(&intruder.pubkey());
+    let admin_ix = initialize_ix(&admin.pubkey());
+    
+    // Anyone else racing the deployer is turned away
+    let tx = Transaction::new_signed_with_payer(&[intruder_ix], Some(&intruder.pubkey()), &[&intruder], ctx.svm.latest_blockhash());
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::InvalidOwner);
+    assert!(ctx.svm.get_account(&config).is_none_or(|account| account.lamports == 0));
+    
+    let tx = Transaction::new_signed_with_payer(std::slice::from_ref(&admin_ix), Some(&admin.pubkey()), &[&admin], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    // The config holds a 30 bps fee paid to the treasury
+    let config_account = ctx.svm.get_account(&config).unwrap();
+    assert_eq!(config_account.owner, ctx.program_id);
+    let state = Config::load(&config_account.data).unwrap();
+    assert_eq!(state.admin, admin.pubkey().to_bytes());
+    assert_eq!(state.fee_recipient, ctx.treasury.pubkey().to_bytes());
+    assert_eq!(state.fee_bps(), 30);
+    assert_eq!(state.max_referral_bps(), 50);
+    assert!(!state.is_paused());
+    
+    // The config can only be set up once
+    ctx.svm.expire_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[admin_ix], Some(&admin.pubkey()), &[&admin], ctx.svm.latest_blockhash());
+    assert!(ctx.svm.send_transaction(tx).is_err());
+    
+    println!("Initialize config test passed!");
+}
+
+#[test]
+fn test_protocol_fee_amounts() {
+    let mut config = Config {
+        discriminator: CONFIG_DISCRIMINATOR,
+        admin: [1; 32],
+        fee_recipient: [2; 32],
+        fee_bps: 30u16.to_le_bytes(),
+        max_referral_bps: 50u16.to_le_bytes(),
+        paused: [0],
+        bump: [255],
+    };
+    assert_eq!(Config::LEN, 78);
+    assert_eq!(config.fee_bps(), 30);
+    
+    // 30 bps of the mint B leg goes to the treasury, rounded down
+    assert_eq!(config.fee_for(800_000_000), 2_400_000);
+    assert_eq!(config.fee_for(333), 0);
+    
+    // A zero fee leaves the maker's proceeds untouched
+    config.set_fee_bps(0);
+    assert_eq!(config.fee_for(800_000_000), 0);
+    
+    // The full 10_000 bps would take the whole leg
+    config.set_fee_bps(10_000);
+    assert_eq!(config.fee_for(800_000_000), 800_000_000);
+    
+    println!("Protocol fee amounts test passed!");
+}
+
+#[test]
+fn test_referral_fee_amounts() {
+    let config = Config {
+        discriminator: CONFIG_DISCRIMINATOR,
+        admin: [1; 32],
+        fee_recipient: [2; 32],
+        fee_bps: 30u16.to_le_bytes(),
+        max_referral_bps: 5_000u16.to_le_bytes(),
+        paused: [0],
+        bump: [255],
+    };
+    assert_eq!(config.max_referral_bps(), 5_000);
+    
+    // Referrers can claim up to the configured share of the protocol fee
+    let receive = 800_000_000u64;
+    let fee = config.fee_for(receive);
+    assert_eq!(fee, 2_400_000);
+    assert_eq!(config.referral_fee_for(fee, 2_500), Some(600_000));
+    assert_eq!(config.referral_fee_for(fee, 5_000), Some(1_200_000));
+    assert_eq!(config.referral_fee_for(fee, 0), Some(0));
+    assert_eq!(config.referral_fee_for(fee, 5_001), None);
+    
+    // The maker keeps the same amount with or without a referrer, so a self-referring
+    // taker only splits the treasury's cut
+    let maker_receive = receive - fee;
+    assert_eq!(maker_receive, 797_600_000);
+    let treasury_receive = fee - config.referral_fee_for(fee, 2_500).unwrap();
+    assert_eq!(treasury_receive, 1_800_000);
+    
+    println!("Referral fee amounts test passed!");
+}
+
+#[test]
+fn test_set_paused() {
+    let mut ctx = TestContext::new();
+    
+    let instruction_data = TestContext::serialize_set_paused_instruction(true);
+    assert_eq!(instruction_data, vec![SET_PAUSED_DISCRIMINATOR, 1]);
+    
+    let mut state = Config {
+        discriminator: CONFIG_DISCRIMINATOR,
+        admin: Pubkey::new_unique().to_bytes(),
+        fee_recipient: ctx.treasury.pubkey().to_bytes(),
+        fee_bps: 30u16.to_le_bytes(),
+        max_referral_bps: 50u16.to_le_bytes(),
+        paused: [0],
+        bump: [255],
+    };
+    assert!(!state.is_paused());
+    state.set_paused(true);
+    assert!(state.is_paused());
+    assert_eq!(state.paused, [1]);
+    
+    if !ctx.deploy() {
+        return;
+    }
+    ctx.set_config(0);
+    ctx.create_mints();
+    ctx.create_token_accounts();
+    ctx.mint_tokens(1_000_000_000, 2_000_000_000);
+    
+    // An escrow opened before the pause
+    let seed = 1u64;
+    let make_data = TestContext::serialize_make_instruction(seed, 800_000_000, 500_000_000, 0, 0, &Pubkey::default(), &[0; 32], (0, 0, 0), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
+    let make_ix = ctx.make_instruction(&ctx.maker.pubkey(), seed, &ctx.mint_b.pubkey(), make_data);
+    let tx = Transaction::new_signed_with_payer(&[make_ix], Some(&ctx.maker.pubkey()), &[&ctx.maker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    // The admin (the mint authority, as written by set_config) flips the circuit breaker
+    let (config, _bump) = ctx.get_config_pda();
+    let ix = Instruction {
+        program_id: ctx.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(ctx.mint_authority.pubkey(), true),
+            AccountMeta::new(config, false),
+        ],
+        data: instruction_data,
+    };
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.mint_authority.pubkey()), &[&ctx.mint_authority], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert!(Config::load(&ctx.svm.get_account(&config).unwrap().data).unwrap().is_paused());
+    
+    // New escrows can't be made
+    let make_data = TestContext::serialize_make_instruction(seed + 1, 800_000_000, 500_000_000, 0, 0, &Pubkey::default(), &[0; 32], (0, 0, 0), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
+    let make_ix = ctx.make_instruction(&ctx.maker.pubkey(), seed + 1, &ctx.mint_b.pubkey(), make_data);
+    let tx = Transaction::new_signed_with_payer(&[make_ix], Some(&ctx.maker.pubkey()), &[&ctx.maker], ctx.svm.latest_blockhash());
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::ProgramPaused);
+    
+    // ...nor open ones taken
+    let take_data = TestContext::serialize_take_instruction(800_000_000, 0, 800_000_000, 500_000_000, &[]);
+    let take_ix = ctx.take_instruction(&ctx.taker.pubkey(), seed, take_data, &[]);
+    let tx = Transaction::new_signed_with_payer(&[take_ix], Some(&ctx.taker.pubkey()), &[&ctx.taker], ctx.svm.latest_blockhash());
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::ProgramPaused);
+    
+    // But the maker can always get their deposit back
+    let (escrow_pda, _) = ctx.get_escrow_pda(&ctx.maker.pubkey(), seed);
+    let vault = ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey());
+    let maker_ata_a = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_a.pubkey());
+    let refund_ix = Instruction {
+        program_id: ctx.program_id,
+        accounts: vec![
+            AccountMeta::new(ctx.maker.pubkey(), true),
+            AccountMeta::new(escrow_pda, false),
+            AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(maker_ata_a, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        ],
+        data: TestContext::serialize_refund_instruction(),
+    };
+    let tx = Transaction::new_signed_with_payer(&[refund_ix], Some(&ctx.maker.pubkey()), &[&ctx.maker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(ctx.get_token_balance(&maker_ata_a), 1_000_000_000);
+    assert!(ctx.svm.get_account(&escrow_pda).is_none_or(|account| account.lamports == 0));
+    
+    println!("Set paused test passed!");
+}
+
+#[test]
+fn test_make_arbiter_escrow() {
+    let mut ctx = TestContext::new();
+    
+    // The taker delivers goods off-chain, so they are the beneficiary rather than a counterparty
+    let expiry = 1_735_689_600i64;
+    let arbiter = Keypair::new();
+    let program_id = ctx.program_id;
+    let maker = ctx.maker.pubkey();
+    let taker = ctx.taker.pubkey();
+    let arbiter_pda = |seed: u64| Pubkey::find_program_address(&[b"arbiter", maker.as_ref(), &seed.to_le_bytes()], &program_id).0;
+    assert_ne!(arbiter_pda(42), ctx.get_escrow_pda(&maker, 42).0);
+    
+    let make_data = |seed: u64| TestContext::serialize_make_arbiter_escrow_instruction(
+        seed,
+        expiry,
+        &taker,
+        &arbiter.pubkey(),
+        &[200_000_000, 300_000_000],
+    );
+    assert_eq!(make_data(42).len(), 97); // 1 + 8 + 8 + 32 + 32 + 2 * 8
+    assert_eq!(RELEASE_DISCRIMINATOR, 9);
+    assert_eq!(ARBITER_REFUND_DISCRIMINATOR, 10);
+    assert_eq!(DISPUTE_DISCRIMINATOR, 11);
+    
+    if !ctx.deploy() {
+        return;
+    }
+    ctx.set_config(0);
+    ctx.set_clock(expiry - 3_600);
+    ctx.create_mints();
+    ctx.create_token_accounts();
+    ctx.mint_tokens(1_000_000_000, 2_000_000_000);
+    ctx.svm.airdrop(&arbiter.pubkey(), LAMPORTS_PER_SOL).unwrap();
+    
+    let mint_a = ctx.mint_a.pubkey();
+    let (config, _) = ctx.get_config_pda();
+    let maker_ata_a = ctx.get_associated_token_address(&maker, &mint_a);
+    let taker_ata_a = ctx.get_associated_token_address(&taker, &mint_a);
+    let vault = |seed: u64| spl_associated_token_account::get_associated_token_address(&arbiter_pda(seed), &mint_a);
+    let refund_ix = |authority: &Pubkey, seed: u64, data: u8| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(maker, false),
+            AccountMeta::new(arbiter_pda(seed), false),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new(vault(seed), false),
+            AccountMeta::new(maker_ata_a, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        ],
+        data: vec![data],
+    };
+    
+    // Two escrows holding both milestones, 500 of mint A each
+    for seed in [42u64, 43] {
+        let ix = Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(maker, true),
+                AccountMeta::new(arbiter_pda(seed), false),
+                AccountMeta::new_readonly(mint_a, false),
+                AccountMeta::new(maker_ata_a, false),
+                AccountMeta::new(vault(seed), false),
+                AccountMeta::new_readonly(config, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+            ],
+            data: make_data(seed),
+        };
+        let tx = Transaction::new_signed_with_payer(&[ix], Some(&maker), &[&ctx.maker], ctx.svm.latest_blockhash());
+        ctx.svm.send_transaction(tx).unwrap();
+        assert_eq!(ctx.get_token_balance(&vault(seed)), 500_000_000);
+    }
+    assert_eq!(ctx.get_token_balance(&maker_ata_a), 0);
+    
+    // The maker can't pull the deposit back before the expiry
+    let tx = Transaction::new_signed_with_payer(&[refund_ix(&maker, 42, ARBITER_REFUND_DISCRIMINATOR)], Some(&maker), &[&ctx.maker], ctx.svm.latest_blockhash());
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::EscrowNotExpired);
+    
+    // The arbiter releases the first escrow to the beneficiary on delivery
+    let release_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(arbiter.pubkey(), true),
+            AccountMeta::new(maker, false),
+            AccountMeta::new_readonly(taker, false),
+            AccountMeta::new(arbiter_pda(42), false),
+            AccountMeta::new_readonly(mint_a, false),
+            AccountMeta::new(vault(42), false),
+            AccountMeta::new(taker_ata_a, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        ],
+        data: vec![RELEASE_DISCRIMINATOR],
+    };
+    let tx = Transaction::new_signed_with_payer(&[release_ix], Some(&arbiter.pubkey()), &[&arbiter], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(ctx.get_token_balance(&taker_ata_a), 500_000_000);
+    assert!(ctx.svm.get_account(&arbiter_pda(42)).is_none_or(|account| account.lamports == 0));
+    assert!(ctx.svm.get_account(&vault(42)).is_none_or(|account| account.lamports == 0));
+    
+    // The beneficiary disputes the second, which stops it from ever expiring
+    let dispute_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(taker, true),
+            AccountMeta::new(arbiter_pda(43), false),
+        ],
+        data: vec![DISPUTE_DISCRIMINATOR],
+    };
+    let tx = Transaction::new_signed_with_payer(&[dispute_ix], Some(&taker), &[&ctx.taker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    ctx.set_clock(expiry + 1);
+    ctx.svm.expire_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[refund_ix(&maker, 43, ARBITER_REFUND_DISCRIMINATOR)], Some(&maker), &[&ctx.maker], ctx.svm.latest_blockhash());
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::EscrowNotExpired);
+    assert_eq!(ctx.get_token_balance(&vault(43)), 500_000_000);
+    
+    // Only the arbiter can settle it now, here back to the maker
+    let tx = Transaction::new_signed_with_payer(&[refund_ix(&arbiter.pubkey(), 43, ARBITER_REFUND_DISCRIMINATOR)], Some(&arbiter.pubkey()), &[&arbiter], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(ctx.get_token_balance(&maker_ata_a), 500_000_000);
+    assert!(ctx.svm.get_account(&arbiter_pda(43)).is_none_or(|account| account.lamports == 0));
+    
+    println!("Make arbiter escrow test passed!");
+}
+
+#[test]
+fn test_arbiter_escrow_dispute() {
+    let mut escrow = ArbiterEscrow {
+        discriminator: ARBITER_ESCROW_DISCRIMINATOR,
+        seed: 42,
+        maker: [1; 32],
+        beneficiary: [2; 32],
+        arbiter: [3; 32],
+        mint_a: [4; 32],
+        expiry: 1_735_689_600,
+        milestones: [500_000_000, 0, 0, 0],
+        released: [0],
+        disputed: [0],
+        bump: [255],
+    };
+    assert_eq!(ArbiterEscrow::LEN, 187);
+    
+    // Undisputed escrows expire like swap escrows, opening the maker's refund
+    assert!(!escrow.is_expired(1_735_689_600));
+    assert!(escrow.is_expired(1_735_689_601));
+    
+    // A dispute freezes expiry so only the arbiter can settle
+    escrow.set_disputed(true);
+    assert!(escrow.is_disputed());
+    assert!(!escrow.is_expired(i64::MAX));
+    
+    println!("Arbiter escrow dispute test passed!");
+}
+
+#[test]
+fn test_arbiter_escrow_milestones() {
+    let mut escrow = ArbiterEscrow {
+        discriminator: ARBITER_ESCROW_DISCRIMINATOR,
+        seed: 42,
+        maker: [1; 32],
+        beneficiary: [2; 32],
+        arbiter: [3; 32],
+        mint_a: [4; 32],
+        expiry: 0,
+        milestones: [100, 200, 300, 0],
+        released: [0],
+        disputed: [0],
+        bump: [255],
+    };
+    assert_eq!(escrow.milestone_count(), 3);
+    
+    // Tranches can be paid in any order; the escrow only closes once all are out
+    escrow.set_milestone_released(2);
+    escrow.set_milestone_released(0);
+    assert!(escrow.is_milestone_released(0));
+    assert!(!escrow.is_milestone_released(1));
+    assert!(!escrow.is_fully_released());
+    
+    escrow.set_milestone_released(1);
+    assert!(escrow.is_fully_released());
+    assert_eq!(escrow.released, [0b111]);
+    
+    let release_data = TestContext::serialize_release_milestone_instruction(1);
+    assert_eq!(release_data, vec![RELEASE_MILESTONE_DISCRIMINATOR, 1]);
+    
+    println!("Arbiter escrow milestones test passed!");
+}
+
+#[test]
+fn test_vesting_escrow_unlock() {
+    let mut escrow = VestingEscrow {
+        discriminator: VESTING_ESCROW_DISCRIMINATOR,
+        seed: 7,
+        maker: [1; 32],
+        beneficiary: [2; 32],
+        mint_a: [3; 32],
+        start: 1_000,
+        cliff: 1_250,
+        end: 2_000,
+        total: 1_000_000,
+        withdrawn: 0,
+        bump: [255],
+    };
+    assert_eq!(VestingEscrow::LEN, 153);
+    
+    // Nothing unlocks before the cliff, then everything since start does
+    assert_eq!(escrow.unlocked_at(1_249), 0);
+    assert_eq!(escrow.unlocked_at(1_250), 250_000);
+    assert_eq!(escrow.unlocked_at(1_500), 500_000);
+    assert_eq!(escrow.unlocked_at(2_000), 1_000_000);
+    assert_eq!(escrow.unlocked_at(i64::MAX), 1_000_000);
+    
+    // Claims only pay out what has vested since the last one
+    escrow.set_withdrawn(500_000);
+    assert_eq!(escrow.claimable_at(1_500), 0);
+    assert_eq!(escrow.claimable_at(1_750), 250_000);
+    
+    let make_data = TestContext::serialize_make_vesting_escrow_instruction(
+        7,
+        1_000_000,
+        1_000,
+        1_250,
+        2_000,
+        &Pubkey::new_unique(),
+    );
+    assert_eq!(make_data.len(), 73); // 1 + 8 + 8 + 8 + 8 + 8 + 32
+    assert_eq!(CLAIM_DISCRIMINATOR, 14);
+    
+    println!("Vesting escrow unlock test passed!");
+}
+
+#[test]
+fn test_htlc_escrow_timelock() {
+    let preimage = [9u8; 32];
+    let sha256_hash = solana_sdk::hash::hash(&preimage).to_bytes();
+    
+    let escrow = HtlcEscrow {
+        discriminator: HTLC_ESCROW_DISCRIMINATOR,
+        seed: 1,
+        maker: [1; 32],
+        recipient: [2; 32],
+        mint_a: [3; 32],
+        sha256_hash,
+        keccak_hash: [0; 32],
+        expiry: 1_735_689_600,
+        bump: [255],
+    };
+    assert_eq!(HtlcEscrow::LEN, 185);
+    
+    // Claims are open up to and including the timelock, refunds only after it
+    assert!(!escrow.is_expired(1_735_689_600));
+    assert!(escrow.is_expired(1_735_689_601));
+    
+    let make_data = TestContext::serialize_make_htlc_instruction(
+        1,
+        500_000_000,
+        1_735_689_600,
+        &Pubkey::new_unique(),
+        &sha256_hash,
+        &[0; 32],
+    );
+    assert_eq!(make_data.len(), 121); // 1 + 8 + 8 + 8 + 32 + 32 + 32
+    
+    // The preimage is the whole payload after the discriminator
+    let claim_data = TestContext::serialize_claim_htlc_instruction(&preimage);
+    assert_eq!(claim_data.len(), 33);
+    assert_eq!(&claim_data[1..], &preimage);
+    assert_eq!(REFUND_HTLC_DISCRIMINATOR, 17);
+    
+    println!("HTLC escrow timelock test passed!");
+}
+
+#[test]
+fn test_hashlock_verification() {
+    let preimage = b"cross-chain swap secret";
+    let sha256_hash = solana_sdk::hash::hash(preimage).to_bytes();
+    let keccak_hash = solana_sdk::keccak::hash(preimage).to_bytes();
+    
+    // The secret opens a SHA-256 lock alone, and one paired with a Keccak-256 lock
+    assert!(verify_hashlock(preimage, &sha256_hash, &[0; 32]));
+    assert!(verify_hashlock(preimage, &sha256_hash, &keccak_hash));
+    
+    // Any other preimage is rejected, including the right one with a byte dropped
+    assert!(!verify_hashlock(b"wrong secret", &sha256_hash, &[0; 32]));
+    assert!(!verify_hashlock(&preimage[1..], &sha256_hash, &keccak_hash));
+    assert!(!verify_hashlock(&[], &sha256_hash, &[0; 32]));
+    
+    // Both locks must match when the Keccak-256 one is set
+    let other_keccak_hash = solana_sdk::keccak::hash(b"wrong secret").to_bytes();
+    assert!(!verify_hashlock(preimage, &sha256_hash, &other_keccak_hash));
+    
+    println!("Hashlock verification test passed!");
+}
+
+#[test]
+fn test_htlc_claim_and_refund() {
+    let mut ctx = TestContext::new();
+    if !ctx.deploy() {
+        return;
+    }
+    ctx.set_config(0);
+    ctx.set_clock(1_700_000_000);
+    ctx.create_mints();
+    ctx.create_token_accounts();
+    ctx.mint_tokens(1_000_000_000, 2_000_000_000);
+    
+    // The maker locks two swaps for the taker behind the same secret and an hour-long timelock
+    let preimage = b"cross-chain swap secret";
+    let sha256_hash = solana_sdk::hash::hash(preimage).to_bytes();
+    let keccak_hash = solana_sdk::keccak::hash(preimage).to_bytes();
+    let expiry = 1_700_003_600i64;
+    let (config, _) = ctx.get_config_pda();
+    let maker_ata_a = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_a.pubkey());
+    let taker_ata_a = ctx.get_associated_token_address(&ctx.taker.pubkey(), &ctx.mint_a.pubkey());
+    let htlc_pda = |seed: u64| {
+        Pubkey::find_program_address(&[b"htlc", ctx.maker.pubkey().as_ref(), &seed.to_le_bytes()], &ctx.program_id).0
+    };
+    let escrows = [htlc_pda(1), htlc_pda(2)];
+    let vaults = escrows.map(|escrow| ctx.get_associated_token_address(&escrow, &ctx.mint_a.pubkey()));
+    
+    for seed in [1u64, 2] {
+        let ix = Instruction {
+            program_id: ctx.program_id,
+            accounts: vec![
+                AccountMeta::new(ctx.maker.pubkey(), true),
+                AccountMeta::new(escrows[seed as usize - 1], false),
+                AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
+                AccountMeta::new(maker_ata_a, false),
+                AccountMeta::new(vaults[seed as usize - 1], false),
+                AccountMeta::new_readonly(config, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+            ],
+            data: TestContext::serialize_make_htlc_instruction(seed, 300_000_000, expiry, &ctx.taker.pubkey(), &sha256_hash, &keccak_hash),
+        };
+        let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.maker.pubkey()), &[&ctx.maker], ctx.svm.latest_blockhash());
+        ctx.svm.send_transaction(tx).unwrap();
+    }
+    assert_eq!(ctx.get_token_balance(&maker_ata_a), 400_000_000);
+    assert_eq!(ctx.get_token_balance(&vaults[0]), 300_000_000);
+    
+    // A relayer submits the secret, but the tokens only ever go to the recipient
+    let relayer = Keypair::new();
+    ctx.svm.airdrop(&relayer.pubkey(), LAMPORTS_PER_SOL).unwrap();
+    let claim = |index: usize, preimage: &[u8]| Instruction {
+        program_id: ctx.program_id,
+        accounts: vec![
+            AccountMeta::new(relayer.pubkey(), true),
+            AccountMeta::new(ctx.maker.pubkey(), false),
+            AccountMeta::new_readonly(ctx.taker.pubkey(), false),
+            AccountMeta::new(escrows[index], false),
+            AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
+            AccountMeta::new(vaults[index], false),
+            AccountMeta::new(taker_ata_a, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        ],
+        data: TestContext::serialize_claim_htlc_instruction(preimage),
+    };
+    let refund = |index: usize| Instruction {
+        program_id: ctx.program_id,
+        accounts: vec![
+            AccountMeta::new(relayer.pubkey(), true),
+            AccountMeta::new(ctx.maker.pubkey(), false),
+            AccountMeta::new(escrows[index], false),
+            AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
+            AccountMeta::new(vaults[index], false),
+            AccountMeta::new(maker_ata_a, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        ],
+        data: vec![REFUND_HTLC_DISCRIMINATOR],
+    };
+    let (bad_claim_ix, claim_ix, early_refund_ix) = (claim(0, b"wrong secret"), claim(0, preimage), refund(0));
+    let (late_claim_ix, refund_ix) = (claim(1, preimage), refund(1));
+    
+    // A wrong preimage doesn't open the lock
+    let tx = Transaction::new_signed_with_payer(&[bad_claim_ix], Some(&relayer.pubkey()), &[&relayer], ctx.svm.latest_blockhash());
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::InvalidPreimage);
+    
+    // Nobody can refund while the timelock runs
+    let tx = Transaction::new_signed_with_payer(&[early_refund_ix], Some(&relayer.pubkey()), &[&relayer], ctx.svm.latest_blockhash());
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::EscrowNotExpired);
+    
+    // The right preimage before expiry pays the recipient and closes the escrow
+    let tx = Transaction::new_signed_with_payer(&[claim_ix], Some(&relayer.pubkey()), &[&relayer], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(ctx.get_token_balance(&taker_ata_a), 300_000_000);
+    assert!(ctx.svm.get_account(&escrows[0]).is_none_or(|account| account.lamports == 0));
+    assert!(ctx.svm.get_account(&vaults[0]).is_none_or(|account| account.lamports == 0));
+    
+    // Past the timelock the secret no longer works and anyone may refund the maker
+    ctx.set_clock(expiry + 1);
+    let tx = Transaction::new_signed_with_payer(&[late_claim_ix], Some(&relayer.pubkey()), &[&relayer], ctx.svm.latest_blockhash());
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::EscrowExpired);
+    
+    let tx = Transaction::new_signed_with_payer(&[refund_ix], Some(&relayer.pubkey()), &[&relayer], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(ctx.get_token_balance(&maker_ata_a), 700_000_000);
+    assert_eq!(ctx.get_token_balance(&taker_ata_a), 300_000_000);
+    assert!(ctx.svm.get_account(&escrows[1]).is_none_or(|account| account.lamports == 0));
+    assert!(ctx.svm.get_account(&vaults[1]).is_none_or(|account| account.lamports == 0));
+    
+    println!("HTLC claim and refund test passed!");
+}
+
+#[test]
+fn test_english_auction_bids() {
+    let mut auction = AuctionEscrow {
+        discriminator: [0; 8],
+        seed: 0,
+        maker: [0; 32],
+        mint_a: [0; 32],
+        mint_b: [0; 32],
+        reserve: 0,
+        min_increment: 0,
+        end: 0,
+        highest_bid: 0,
+        highest_bidder: [0; 32],
+        bump: [0],
+    };
+    auction.set_inner(7, [1; 32], [2; 32], [3; 32], 1_000_000_000, 50_000_000, 1_735_689_600, [254]);
+    assert_eq!(auction.discriminator, AUCTION_ESCROW_DISCRIMINATOR);
+    assert_eq!(AuctionEscrow::LEN, 177);
+    assert_eq!(Bid::LEN, 81);
+    
+    // The first bid must meet the reserve
+    assert!(!auction.has_bid());
+    assert_eq!(auction.min_bid(), 1_000_000_000);
+    
+    // Each later bid must beat the leader by the minimum increment
+    auction.set_highest_bid([4; 32], 1_000_000_000);
+    assert!(auction.has_bid());
+    assert_eq!(auction.min_bid(), 1_050_000_000);
+    auction.set_highest_bid([5; 32], 1_200_000_000);
+    assert_eq!(auction.min_bid(), 1_250_000_000);
+    assert_eq!(auction.highest_bidder, [5; 32]);
+    
+    // Bidding closes at the end time, which is also when settlement opens
+    assert!(!auction.is_ended(1_735_689_599));
+    assert!(auction.is_ended(1_735_689_600));
+    
+    // An outbid bidder tops up their existing bid rather than opening a new one
+    let mut bid = Bid {
+        discriminator: [0; 8],
+        auction: [0; 32],
+        bidder: [0; 32],
+        amount: 0,
+        bump: [0],
+    };
+    bid.set_inner([6; 32], [4; 32], [253]);
+    assert_eq!(bid.discriminator, BID_DISCRIMINATOR);
+    bid.set_amount(1_000_000_000 + 250_000_000);
+    assert!(bid.amount >= auction.min_bid());
+    
+    // Bid PDAs are unique per auction and bidder
+    let program_id = Pubkey::new_from_array(PROGRAM_ID);
+    let auction_key = Pubkey::new_unique();
+    let (bid_a, _) = Pubkey::find_program_address(&[b"bid", auction_key.as_ref(), Pubkey::new_unique().as_ref()], &program_id);
+    let (bid_b, _) = Pubkey::find_program_address(&[b"bid", auction_key.as_ref(), Pubkey::new_unique().as_ref()], &program_id);
+    assert_ne!(bid_a, bid_b);
+    
+    let make_data = TestContext::serialize_make_auction_instruction(7, 500_000_000, 1_000_000_000, 50_000_000, 1_735_689_600);
+    assert_eq!(make_data.len(), 41); // 1 + 8 + 8 + 8 + 8 + 8
+    assert_eq!(u64::from_le_bytes(make_data[25..33].try_into().unwrap()), 50_000_000);
+    
+    let bid_data = TestContext::serialize_place_bid_instruction(250_000_000);
+    assert_eq!(bid_data.len(), 9);
+    assert_eq!(WITHDRAW_BID_DISCRIMINATOR, 20);
+    assert_eq!(SETTLE_DISCRIMINATOR, 21);
+    
+    println!("English auction bids test passed!");
+}
+
+#[test]
+fn test_auction_settle_and_withdraw_bid() {
+    let mut ctx = TestContext::new();
+    if !ctx.deploy() {
+        return;
+    }
+    let now = 1_700_000_000i64;
+    let end = now + 3_600;
+    ctx.set_config(0);
+    ctx.set_clock(now);
+    ctx.create_mints();
+    ctx.create_token_accounts();
+    ctx.mint_tokens(1_000_000_000, 1_000_000_000);
+    
+    let rival = Keypair::new();
+    ctx.svm.airdrop(&rival.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+    let rival_ata_b = ctx.fund_token_account(&rival.pubkey(), &ctx.mint_b.pubkey(), 1_000_000_000);
+    
+    let program_id = ctx.program_id;
+    let mint_b = ctx.mint_b.pubkey();
+    let (config, _) = ctx.get_config_pda();
+    let seed = 5u64;
+    let (auction, _) = Pubkey::find_program_address(&[b"auction", ctx.maker.pubkey().as_ref(), &seed.to_le_bytes()], &program_id);
+    let vault = ctx.get_associated_token_address(&auction, &ctx.mint_a.pubkey());
+    let maker_ata_a = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_a.pubkey());
+    let maker_ata_b = ctx.get_associated_token_address(&ctx.maker.pubkey(), &mint_b);
+    let taker_ata_a = ctx.get_associated_token_address(&ctx.taker.pubkey(), &ctx.mint_a.pubkey());
+    let taker_ata_b = ctx.get_associated_token_address(&ctx.taker.pubkey(), &mint_b);
+    let bid_accounts = |bidder: &Pubkey| {
+        let (bid, _) = Pubkey::find_program_address(&[b"bid", auction.as_ref(), bidder.as_ref()], &program_id);
+        let bid_vault = spl_associated_token_account::get_associated_token_address(&bid, &mint_b);
+        (bid, bid_vault)
+    };
+    let place_bid_ix = |bidder: &Pubkey, bidder_ata_b: &Pubkey, amount: u64| {
+        let (bid, bid_vault) = bid_accounts(bidder);
+        Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(*bidder, true),
+                AccountMeta::new(auction, false),
+                AccountMeta::new(bid, false),
+                AccountMeta::new_readonly(mint_b, false),
+                AccountMeta::new(*bidder_ata_b, false),
+                AccountMeta::new(bid_vault, false),
+                AccountMeta::new_readonly(config, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+            ],
+            data: TestContext::serialize_place_bid_instruction(amount),
+        }
+    };
+    let withdraw_bid_ix = |bidder: &Pubkey, bidder_ata_b: &Pubkey| {
+        let (bid, bid_vault) = bid_accounts(bidder);
+        Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(*bidder, true),
+                AccountMeta::new_readonly(auction, false),
+                AccountMeta::new(bid, false),
+                AccountMeta::new_readonly(mint_b, false),
+                AccountMeta::new(bid_vault, false),
+                AccountMeta::new(*bidder_ata_b, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+            ],
+            data: vec![WITHDRAW_BID_DISCRIMINATOR],
+        }
+    };
+    
+    // 500 of mint A, reserve 100 of mint B, raised by at least 10 a bid, for an hour
+    let make_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(ctx.maker.pubkey(), true),
+            AccountMeta::new(auction, false),
+            AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new(maker_ata_a, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        ],
+        data: TestContext::serialize_make_auction_instruction(seed, 500_000_000, 100_000_000, 10_000_000, end),
+    };
+    let tx = Transaction::new_signed_with_payer(&[make_ix], Some(&ctx.maker.pubkey()), &[&ctx.maker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    // The taker opens at 200 and the rival takes the lead at 300
+    let ix = place_bid_ix(&ctx.taker.pubkey(), &taker_ata_b, 200_000_000);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.taker.pubkey()), &[&ctx.taker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    let ix = place_bid_ix(&rival.pubkey(), &rival_ata_b, 300_000_000);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&rival.pubkey()), &[&rival], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    // The leading bid stays locked while the auction is open
+    let ix = withdraw_bid_ix(&rival.pubkey(), &rival_ata_b);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&rival.pubkey()), &[&rival], ctx.svm.latest_blockhash());
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::LeadingBid);
+    
+    // The outbid taker can pull their bid out before settlement
+    let (taker_bid, taker_bid_vault) = bid_accounts(&ctx.taker.pubkey());
+    let ix = withdraw_bid_ix(&ctx.taker.pubkey(), &taker_ata_b);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.taker.pubkey()), &[&ctx.taker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(ctx.get_token_balance(&taker_ata_b), 1_000_000_000);
+    assert!(ctx.svm.get_account(&taker_bid).is_none_or(|account| account.lamports == 0));
+    assert!(ctx.svm.get_account(&taker_bid_vault).is_none_or(|account| account.lamports == 0));
+    
+    // ...and come back with a fresh bid that retakes the lead
+    let ix = place_bid_ix(&ctx.taker.pubkey(), &taker_ata_b, 400_000_000);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&ctx.taker.pubkey()), &[&ctx.taker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    let cranker = Keypair::new();
+    ctx.svm.airdrop(&cranker.pubkey(), LAMPORTS_PER_SOL).unwrap();
+    let settle_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(cranker.pubkey(), true),
+            AccountMeta::new(ctx.maker.pubkey(), false),
+            AccountMeta::new(auction, false),
+            AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(ctx.taker.pubkey(), false),
+            AccountMeta::new(taker_ata_a, false),
+            AccountMeta::new(taker_bid, false),
+            AccountMeta::new(taker_bid_vault, false),
+            AccountMeta::new(maker_ata_b, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        ],
+        data: vec![SETTLE_DISCRIMINATOR],
+    };
+    
+    // Settlement opens only once bidding has closed
+    let tx = Transaction::new_signed_with_payer(std::slice::from_ref(&settle_ix), Some(&cranker.pubkey()), &[&cranker], ctx.svm.latest_blockhash());
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::EscrowNotExpired);
+    
+    // After the end the lot goes to the taker, their 400 to the maker, and the auction
+    // and winning bid are closed
+    ctx.set_clock(end);
+    ctx.svm.expire_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[settle_ix], Some(&cranker.pubkey()), &[&cranker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(ctx.get_token_balance(&taker_ata_a), 500_000_000);
+    assert_eq!(ctx.get_token_balance(&maker_ata_b), 400_000_000);
+    assert_eq!(ctx.get_token_balance(&taker_ata_b), 600_000_000);
+    for closed in [auction, vault, taker_bid, taker_bid_vault] {
+        assert!(ctx.svm.get_account(&closed).is_none_or(|account| account.lamports == 0));
+    }
+    
+    // Once the auction is settled the rival's losing bid is released in full
+    let (rival_bid, _) = bid_accounts(&rival.pubkey());
+    let ix = withdraw_bid_ix(&rival.pubkey(), &rival_ata_b);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&rival.pubkey()), &[&rival], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(ctx.get_token_balance(&rival_ata_b), 1_000_000_000);
+    assert!(ctx.svm.get_account(&rival_bid).is_none_or(|account| account.lamports == 0));
+    
+    println!("Auction settle and withdraw bid test passed!");
+}
+
+#[test]
+fn test_bundle_escrow_legs() {
+    let mint_b1 = Pubkey::new_unique();
+    let mint_b2 = Pubkey::new_unique();
+    
+    // Three tokens for one: three mint A amounts, one mint B leg
+    let make_data = TestContext::serialize_make_bundle_instruction(9, &[100, 200, 300], &[(mint_b1, 1_000)]);
+    assert_eq!(make_data.len(), 1 + 8 + 2 + 8 * 3 + 40);
+    assert_eq!(make_data[0], MAKE_BUNDLE_DISCRIMINATOR);
+    let parsed = MakeBundleInstructionData::try_from(&make_data[1..]).unwrap();
+    assert_eq!(parsed.seed, 9);
+    assert_eq!(parsed.legs_a_len, 3);
+    assert_eq!(&parsed.amounts_a[..3], &[100, 200, 300]);
+    assert_eq!(parsed.legs_b_len, 1);
+    assert_eq!(parsed.legs_b[0].mint, mint_b1.to_bytes());
+    assert_eq!(parsed.legs_b[0].amount, 1_000);
+    
+    // Each side needs between one and four legs
+    let empty = TestContext::serialize_make_bundle_instruction(9, &[], &[(mint_b1, 1_000)]);
+    assert!(MakeBundleInstructionData::try_from(&empty[1..]).is_err());
+    let too_many = TestContext::serialize_make_bundle_instruction(9, &[1; MAX_BUNDLE_LEGS + 1], &[(mint_b1, 1_000)]);
+    assert!(MakeBundleInstructionData::try_from(&too_many[1..]).is_err());
+    
+    // Zero amounts, repeated mints and truncated data are rejected
+    let zero = TestContext::serialize_make_bundle_instruction(9, &[100, 0], &[(mint_b1, 1_000)]);
+    assert!(MakeBundleInstructionData::try_from(&zero[1..]).is_err());
+    let repeated = TestContext::serialize_make_bundle_instruction(9, &[100], &[(mint_b1, 1_000), (mint_b1, 5)]);
+    assert!(MakeBundleInstructionData::try_from(&repeated[1..]).is_err());
+    assert!(MakeBundleInstructionData::try_from(&make_data[1..make_data.len() - 1]).is_err());
+    
+    // Only the stored legs are exposed
+    let mut bundle = BundleEscrow {
+        discriminator: [0; 8],
+        seed: 0,
+        maker: [0; 32],
+        legs_a: [Leg::default(); MAX_BUNDLE_LEGS],
+        legs_b: [Leg::default(); MAX_BUNDLE_LEGS],
+        legs_a_len: [0],
+        legs_b_len: [0],
+        bump: [0],
+    };
+    let legs_a = [
+        Leg { mint: [1; 32], amount: 100 },
+        Leg { mint: [2; 32], amount: 200 },
+        Leg { mint: [3; 32], amount: 300 },
+    ];
+    let legs_b = [
+        Leg { mint: mint_b1.to_bytes(), amount: 1_000 },
+        Leg { mint: mint_b2.to_bytes(), amount: 2_000 },
+    ];
+    bundle.set_inner(9, [7; 32], &legs_a, &legs_b, [252]);
+    assert_eq!(bundle.discriminator, BUNDLE_ESCROW_DISCRIMINATOR);
+    assert_eq!(BundleEscrow::LEN, 371);
+    assert_eq!(bundle.legs_a().len(), 3);
+    assert_eq!(bundle.legs_a()[2].amount, 300);
+    assert_eq!(bundle.legs_b().len(), 2);
+    assert_eq!(bundle.legs_b()[1].mint, mint_b2.to_bytes());
+    assert!(!has_duplicate_mints(bundle.legs_a()));
+    assert!(has_duplicate_mints(&[legs_a[0], legs_a[1], legs_a[0]]));
+    
+    // Every mint A leg has its own vault, all owned by the one bundle PDA
+    let ctx = TestContext::new();
+    let program_id = Pubkey::new_from_array(PROGRAM_ID);
+    let (bundle_pda, _) = Pubkey::find_program_address(&[b"bundle", ctx.maker.pubkey().as_ref(), &9u64.to_le_bytes()], &program_id);
+    let vault_1 = ctx.get_associated_token_address(&bundle_pda, &Pubkey::new_from_array([1; 32]));
+    let vault_2 = ctx.get_associated_token_address(&bundle_pda, &Pubkey::new_from_array([2; 32]));
+    assert_ne!(vault_1, vault_2);
+    
+    assert_eq!(TAKE_BUNDLE_DISCRIMINATOR, 23);
+    assert_eq!(REFUND_BUNDLE_DISCRIMINATOR, 24);
+    
+    println!("Bundle escrow legs test passed!");
+}
+
+#[test]
+fn test_counter_offer() {
+    // A taker counters an escrow asking 800 with 700 of mint B
+    let data = TestContext::serialize_counter_offer_instruction(700_000_000);
+    assert_eq!(data.len(), 9);
+    assert_eq!(data[0], COUNTER_OFFER_DISCRIMINATOR);
+    assert_eq!(CounterOfferInstructionData::try_from(&data[1..]).unwrap().receive, 700_000_000);
+    let empty = TestContext::serialize_counter_offer_instruction(0);
+    assert!(CounterOfferInstructionData::try_from(&empty[1..]).is_err());
+    
+    let mut offer = Offer {
+        discriminator: [0; 8],
+        escrow: [0; 32],
+        taker: [0; 32],
+        receive: 0,
+        deposit: 0,
+        bump: [0],
+    };
+    offer.set_inner([1; 32], [2; 32], 700_000_000, 1_000_000_000, [251]);
+    assert_eq!(offer.discriminator, OFFER_DISCRIMINATOR);
+    assert_eq!(Offer::LEN, 89);
+    assert_eq!(offer.receive, 700_000_000);
+    
+    // The offer remembers the deposit it countered, so a fill in between can't shrink it
+    assert_eq!(offer.deposit, 1_000_000_000);
+    
+    // Each taker holds at most one offer per escrow, with its own mint B vault
+    let ctx = TestContext::new();
+    let program_id = Pubkey::new_from_array(PROGRAM_ID);
+    let (escrow_pda, _) = ctx.get_escrow_pda(&ctx.maker.pubkey(), 42);
+    let (offer_a, _) = Pubkey::find_program_address(&[b"offer", escrow_pda.as_ref(), ctx.taker.pubkey().as_ref()], &program_id);
+    let (offer_b, _) = Pubkey::find_program_address(&[b"offer", escrow_pda.as_ref(), Pubkey::new_unique().as_ref()], &program_id);
+    assert_ne!(offer_a, offer_b);
+    assert_ne!(
+        ctx.get_associated_token_address(&offer_a, &ctx.mint_b.pubkey()),
+        ctx.get_associated_token_address(&offer_b, &ctx.mint_b.pubkey())
+    );
+    
+    // Accepting charges the protocol fee on the offer, like a take would
+    let config = Config {
+        discriminator: CONFIG_DISCRIMINATOR,
+        admin: [1; 32],
+        fee_recipient: [2; 32],
+        fee_bps: 30u16.to_le_bytes(),
+        max_referral_bps: 50u16.to_le_bytes(),
+        paused: [0],
+        bump: [255],
+    };
+    let fee = config.fee_for(offer.receive);
+    assert_eq!(fee, 2_100_000);
+    assert_eq!(offer.receive - fee, 697_900_000);
+    
+    assert_eq!(ACCEPT_COUNTER_DISCRIMINATOR, 26);
+    assert_eq!(WITHDRAW_COUNTER_DISCRIMINATOR, 27);
+    
+    println!("Counter offer test passed!");
+}
+
+#[test]
+fn test_take_many_budget() {
+    let data = TestContext::serialize_take_many_instruction(1_000_000_000, 600_000_000);
+    assert_eq!(data.len(), 17);
+    assert_eq!(data[0], TAKE_MANY_DISCRIMINATOR);
+    let parsed = TakeManyInstructionData::try_from(&data[1..]).unwrap();
+    assert_eq!(parsed.budget, 1_000_000_000);
+    assert_eq!(parsed.min_receive, 600_000_000);
+    let empty = TestContext::serialize_take_many_instruction(0, 0);
+    assert!(TakeManyInstructionData::try_from(&empty[1..]).is_err());
+    assert!(TakeManyInstructionData::try_from(&data[1..9]).is_err());
+    assert_eq!(MAX_TAKE_MANY_ESCROWS, 64);
+    
+    let mut ctx = TestContext::new();
+    if !ctx.deploy() {
+        return;
+    }
+    ctx.set_config(0);
+    ctx.set_clock(1_700_000_000);
+    ctx.create_mints();
+    ctx.create_token_accounts();
+    ctx.mint_tokens(1_500_000_000, 2_000_000_000);
+    
+    let other_maker = Keypair::new();
+    ctx.svm.airdrop(&other_maker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+    ctx.fund_token_account(&other_maker.pubkey(), &ctx.mint_a.pubkey(), 1_000_000_000);
+    
+    // An unrelated mint, for an escrow that sells mint A for something else
+    let mint_c = Keypair::new();
+    let rent = ctx.svm.minimum_balance_for_rent_exemption(Mint::LEN);
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(&ctx.mint_authority.pubkey(), &mint_c.pubkey(), rent, Mint::LEN as u64, &spl_token::ID),
+            spl_token::instruction::initialize_mint(&spl_token::ID, &mint_c.pubkey(), &ctx.mint_authority.pubkey(), None, 9).unwrap(),
+        ],
+        Some(&ctx.mint_authority.pubkey()),
+        &[&ctx.mint_authority, &mint_c],
+        ctx.svm.latest_blockhash(),
+    );
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    // Two escrows each sell 500 of mint A for 800 of mint B, next to an expired one
+    // and one asking for mint C
+    let maker = ctx.maker.pubkey();
+    let other = other_maker.pubkey();
+    let mint_b = ctx.mint_b.pubkey();
+    let escrows = [
+        (maker, 1u64, mint_b, 0i64),
+        (other, 2, mint_b, 0),
+        (maker, 3, mint_b, 1_600_000_000),
+        (other, 4, mint_c.pubkey(), 0),
+    ];
+    for (owner, seed, mint, expiry) in escrows {
+        let data = TestContext::serialize_make_instruction(seed, 800_000_000, 500_000_000, expiry, 0, &Pubkey::default(), &[0; 32], (0, 0, 0), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
+        let ix = ctx.make_instruction(&owner, seed, &mint, data);
+        let signer = if owner == maker { &ctx.maker } else { &other_maker };
+        let tx = Transaction::new_signed_with_payer(&[ix], Some(&owner), &[signer], ctx.svm.latest_blockhash());
+        ctx.svm.send_transaction(tx).unwrap();
+    }
+    
+    let (config, _) = ctx.get_config_pda();
+    let taker_ata_a = ctx.get_associated_token_address(&ctx.taker.pubkey(), &ctx.mint_a.pubkey());
+    let taker_ata_b = ctx.get_associated_token_address(&ctx.taker.pubkey(), &mint_b);
+    let treasury_ata_b = ctx.get_associated_token_address(&ctx.treasury.pubkey(), &mint_b);
+    let escrow_pdas = escrows.map(|(owner, seed, _, _)| ctx.get_escrow_pda(&owner, seed).0);
+    let vaults = escrow_pdas.map(|escrow_pda| ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey()));
+    let escrow_pda = |seed: u64| escrow_pdas[seed as usize - 1];
+    let vault = |seed: u64| vaults[seed as usize - 1];
+    let take_many = |seeds: &[u64], budget: u64, min_receive: u64| {
+        let mut accounts = vec![
+            AccountMeta::new(ctx.taker.pubkey(), true),
+            AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
+            AccountMeta::new_readonly(mint_b, false),
+            AccountMeta::new(taker_ata_a, false),
+            AccountMeta::new(taker_ata_b, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(ctx.treasury.pubkey(), false),
+            AccountMeta::new(treasury_ata_b, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        ];
+        for &seed in seeds {
+            let owner = escrows[seed as usize - 1].0;
+            accounts.push(AccountMeta::new(owner, false));
+            accounts.push(AccountMeta::new(escrow_pda(seed), false));
+            accounts.push(AccountMeta::new(vault(seed), false));
+            accounts.push(AccountMeta::new(ctx.get_associated_token_address(&owner, &mint_b), false));
+        }
+        Instruction {
+            program_id: ctx.program_id,
+            accounts,
+            data: TestContext::serialize_take_many_instruction(budget, min_receive),
+        }
+    };
+    let mismatched_ix = take_many(&[1, 4], 1_000_000_000, 0);
+    let sweep_ix = take_many(&[3, 1, 2], 1_000_000_000, 600_000_000);
+    
+    // Mint C can't be bought with mint B, and that aborts the whole sweep
+    let tx = Transaction::new_signed_with_payer(&[mismatched_ix], Some(&ctx.taker.pubkey()), &[&ctx.taker], ctx.svm.latest_blockhash());
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::InvalidMint);
+    assert_eq!(ctx.get_token_balance(&vault(1)), 500_000_000);
+    
+    // The expired escrow is skipped, the first live one fills in full and the second
+    // only as far as the remaining 200 of the budget goes
+    let maker_lamports = ctx.svm.get_account(&maker).unwrap().lamports;
+    let vault_rent = ctx.svm.get_account(&vault(1)).unwrap().lamports;
+    let tx = Transaction::new_signed_with_payer(&[sweep_ix], Some(&ctx.taker.pubkey()), &[&ctx.taker], ctx.svm.latest_blockhash());
+    let result = ctx.svm.send_transaction(tx).unwrap();
+    
+    assert_eq!(ctx.get_token_balance(&taker_ata_a), 625_000_000);
+    assert_eq!(ctx.get_token_balance(&taker_ata_b), 1_000_000_000);
+    assert_eq!(ctx.get_token_balance(&ctx.get_associated_token_address(&maker, &mint_b)), 800_000_000);
+    assert_eq!(ctx.get_token_balance(&ctx.get_associated_token_address(&other, &mint_b)), 200_000_000);
+    
+    // The full fill closes its escrow and vault; the partial one keeps the rest of its terms
+    assert!(ctx.svm.get_account(&escrow_pda(1)).is_none_or(|account| account.lamports == 0));
+    assert!(ctx.svm.get_account(&vault(1)).is_none_or(|account| account.lamports == 0));
+    // The maker gets the vault's rent back, while the escrow's rent goes to the taker as on a single take
+    assert_eq!(ctx.svm.get_account(&maker).unwrap().lamports, maker_lamports + vault_rent);
+    let partial = ctx.parse_escrow_account(&ctx.svm.get_account(&escrow_pda(2)).unwrap().data).unwrap();
+    assert_eq!(partial.receive, 600_000_000);
+    assert_eq!(partial.deposit, 375_000_000);
+    assert_eq!(ctx.get_token_balance(&vault(2)), 375_000_000);
+    
+    // The expired escrow is left as it was
+    assert_eq!(ctx.get_token_balance(&vault(3)), 500_000_000);
+    assert_eq!(ctx.parse_escrow_account(&ctx.svm.get_account(&escrow_pda(3)).unwrap().data).unwrap().deposit, 500_000_000);
+    
+    // Each escrow reports the mint B owed and the mint A released, in the order given
+    assert_eq!(result.return_data.program_id, ctx.program_id);
+    let mut fills = Vec::new();
+    for (receive, amount) in [(0u64, 0u64), (800_000_000, 500_000_000), (200_000_000, 125_000_000)] {
+        fills.extend_from_slice(&receive.to_le_bytes());
+        fills.extend_from_slice(&amount.to_le_bytes());
+    }
+    assert_eq!(fills.len(), 3 * TAKE_MANY_FILL_LEN);
+    assert_eq!(result.return_data.data, fills);
+    
+    println!("Take many test passed!");
+}
+
+#[test]
+fn test_escrow_with_expiry() {
+    let mut ctx = TestContext::new();
+    ctx.create_mints();
+    ctx.create_token_accounts();
+    ctx.mint_tokens(1_000_000_000, 2_000_000_000);
+    
+    let seed = 100u64;
+    let receive = 750_000_000u64;
+    let amount = 400_000_000u64;
+    let expiry = 1735689600i64; // Some future timestamp
+    
+    let (escrow_pda, _bump) = ctx.get_escrow_pda(&ctx.maker.pubkey(), seed);
+    let vault = ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey());
+    let maker_ata_a = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_a.pubkey());
+    
+    let (config, _) = ctx.get_config_pda();
+    
+    let instruction_data = TestContext::serialize_make_instruction(seed, receive, amount, expiry, 0, &Pubkey::default(), &[0; 32], (0, 0, 0), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
+    
+    let accounts = vec![
         AccountMeta::new(ctx.maker.pubkey(), true),
         AccountMeta::new(escrow_pda, false),
         AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
         AccountMeta::new_readonly(ctx.mint_b.pubkey(), false),
         AccountMeta::new(maker_ata_a, false),
         AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(config, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(spl_token::ID, false),
+        AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+    ];
+    
+    let ix = Instruction {
+        program_id: ctx.program_id,
+        accounts,
+        data: instruction_data,
+    };
+    
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&ctx.maker.pubkey()),
+        &[&ctx.maker],
+        ctx.svm.latest_blockhash(),
+    );
+    
+    let _result = ctx.svm.send_transaction(tx);
+    
+    // Note: With the program deployed, we could test expiry logic
+    // by warping the clock and attempting a take after expiry
+    
+    println!("Escrow with expiry test completed (program not deployed)");
+}
+
+#[test]
+fn test_expire_refund_escrow() {
+    let mut ctx = TestContext::new();
+    if !ctx.deploy() {
+        return;
+    }
+    let expiry = 1_735_689_600i64;
+    ctx.set_config(0);
+    ctx.set_clock(expiry - 600);
+    ctx.create_mints();
+    ctx.create_token_accounts();
+    ctx.mint_tokens(1_000_000_000, 2_000_000_000);
+    
+    // An escrow with an expiry and a crank bounty parked on it
+    let seed = 100u64;
+    let bounty = 5_000_000u64;
+    let make_data = TestContext::serialize_make_instruction(seed, 750_000_000, 400_000_000, expiry, bounty, &Pubkey::default(), &[0; 32], (0, 0, 0), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
+    let make_ix = ctx.make_instruction(&ctx.maker.pubkey(), seed, &ctx.mint_b.pubkey(), make_data);
+    let tx = Transaction::new_signed_with_payer(&[make_ix], Some(&ctx.maker.pubkey()), &[&ctx.maker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    
+    // Anyone may crank the refund; the mint authority pays the fees so the cranker's
+    // lamports move by the bounty alone
+    let cranker = Keypair::new();
+    ctx.svm.airdrop(&cranker.pubkey(), LAMPORTS_PER_SOL).unwrap();
+    let (escrow_pda, _bump) = ctx.get_escrow_pda(&ctx.maker.pubkey(), seed);
+    let vault = ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey());
+    let maker_ata_a = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_a.pubkey());
+    let crank_ix = Instruction {
+        program_id: ctx.program_id,
+        accounts: vec![
+            AccountMeta::new(cranker.pubkey(), true),
+            AccountMeta::new(ctx.maker.pubkey(), false),
+            AccountMeta::new(escrow_pda, false),
+            AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(maker_ata_a, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+        ],
+        data: TestContext::serialize_expire_refund_instruction(),
+    };
+    
+    // Cranking at the expiry itself is still too early
+    ctx.set_clock(expiry);
+    let tx = Transaction::new_signed_with_payer(std::slice::from_ref(&crank_ix), Some(&ctx.mint_authority.pubkey()), &[&ctx.mint_authority, &cranker], ctx.svm.latest_blockhash());
+    assert_program_error(ctx.svm.send_transaction(tx), PinocchioError::EscrowNotExpired);
+    assert_eq!(ctx.get_token_balance(&vault), 400_000_000);
+    
+    let lamports = |ctx: &TestContext, key: &Pubkey| ctx.svm.get_account(key).map_or(0, |account| account.lamports);
+    let maker_before = lamports(&ctx, &ctx.maker.pubkey());
+    let cranker_before = lamports(&ctx, &cranker.pubkey());
+    let escrow_rent = lamports(&ctx, &escrow_pda) - bounty;
+    let vault_rent = lamports(&ctx, &vault);
+    
+    // Once expired the deposit and both rents go back to the maker and the cranker
+    // collects the bounty
+    ctx.set_clock(expiry + 1);
+    ctx.svm.expire_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[crank_ix], Some(&ctx.mint_authority.pubkey()), &[&ctx.mint_authority, &cranker], ctx.svm.latest_blockhash());
+    ctx.svm.send_transaction(tx).unwrap();
+    assert_eq!(ctx.get_token_balance(&maker_ata_a), 1_000_000_000);
+    assert_eq!(lamports(&ctx, &ctx.maker.pubkey()), maker_before + escrow_rent + vault_rent);
+    assert_eq!(lamports(&ctx, &cranker.pubkey()), cranker_before + bounty);
+    assert_eq!(lamports(&ctx, &escrow_pda), 0);
+    assert_eq!(lamports(&ctx, &vault), 0);
+    
+    println!("Expire refund escrow test passed!");
+}
+
+#[test]
+fn test_invalid_amount() {
+    let mut ctx = TestContext::new();
+    ctx.create_mints();
+    ctx.create_token_accounts();
+    ctx.mint_tokens(1_000_000_000, 2_000_000_000);
+    
+    let seed = 42u64;
+    let receive = 800_000_000u64;
+    let amount = 0u64; // Invalid: zero amount
+    let expiry = 0i64;
+    
+    let (escrow_pda, _bump) = ctx.get_escrow_pda(&ctx.maker.pubkey(), seed);
+    let vault = ctx.get_associated_token_address(&escrow_pda, &ctx.mint_a.pubkey());
+    let maker_ata_a = ctx.get_associated_token_address(&ctx.maker.pubkey(), &ctx.mint_a.pubkey());
+    
+    let (config, _) = ctx.get_config_pda();
+    
+    let instruction_data = TestContext::serialize_make_instruction(seed, receive, amount, expiry, 0, &Pubkey::default(), &[0; 32], (0, 0, 0), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
+    
+    let accounts = vec![
+        AccountMeta::new(ctx.maker.pubkey(), true),
+        AccountMeta::new(escrow_pda, false),
+        AccountMeta::new_readonly(ctx.mint_a.pubkey(), false),
+        AccountMeta::new_readonly(ctx.mint_b.pubkey(), false),
+        AccountMeta::new(maker_ata_a, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(config, false),
         AccountMeta::new_readonly(system_program::ID, false),
         AccountMeta::new_readonly(spl_token::ID, false),
         AccountMeta::new_readonly(spl_associated_token_account::ID, false),
@@ -592,16 +3169,42 @@ fn test_escrow_data_parsing() {
     data.extend_from_slice(&Pubkey::new_unique().to_bytes()); // mint_a
     data.extend_from_slice(&Pubkey::new_unique().to_bytes()); // mint_b
     data.extend_from_slice(&1_000_000_000u64.to_le_bytes()); // receive
+    data.extend_from_slice(&500_000_000u64.to_le_bytes()); // deposit
     data.extend_from_slice(&1735689600i64.to_le_bytes()); // expiry
+    data.extend_from_slice(&5_000_000u64.to_le_bytes()); // bounty
+    data.extend_from_slice(&[0; 32]); // taker
+    data.extend_from_slice(&[7; 32]); // merkle root
+    data.extend_from_slice(&1_500_000_000u64.to_le_bytes()); // start receive
+    data.extend_from_slice(&1735600000i64.to_le_bytes()); // auction start
+    data.extend_from_slice(&1735680000i64.to_le_bytes()); // auction end
+    let price_feed = Pubkey::new_unique();
+    data.extend_from_slice(&price_feed.to_bytes()); // price feed
+    data.extend_from_slice(&[4; 32]); // feed id
+    data.extend_from_slice(&60u64.to_le_bytes()); // max staleness
+    data.extend_from_slice(&50u16.to_le_bytes()); // max conf bps
+    data.extend_from_slice(&(-100i16).to_le_bytes()); // spread bps
+    data.extend_from_slice(&[0; 32]); // collection
     data.push(255); // bump
-    data.push(0); // padding to reach 130 bytes
     
     let escrow = ctx.parse_escrow_account(&data).unwrap();
     
     assert_eq!(escrow.discriminator, ESCROW_DISCRIMINATOR);
     assert_eq!(escrow.seed, 42);
     assert_eq!(escrow.receive, 1_000_000_000);
+    assert_eq!(escrow.deposit, 500_000_000);
     assert_eq!(escrow.expiry, 1735689600);
+    assert_eq!(escrow.bounty, 5_000_000);
+    assert_eq!(escrow.taker, Pubkey::default());
+    assert_eq!(escrow.merkle_root, [7; 32]);
+    assert_eq!(escrow.start_receive, 1_500_000_000);
+    assert_eq!(escrow.auction_start, 1735600000);
+    assert_eq!(escrow.auction_end, 1735680000);
+    assert_eq!(escrow.price_feed, price_feed);
+    assert_eq!(escrow.feed_id, [4; 32]);
+    assert_eq!(escrow.max_staleness, 60);
+    assert_eq!(escrow.max_conf_bps, 50);
+    assert_eq!(escrow.spread_bps, -100);
+    assert_eq!(escrow.collection, Pubkey::default());
     assert_eq!(escrow.bump, 255);
     
     println!("Escrow data parsing test passed!");
@@ -610,9 +3213,10 @@ fn test_escrow_data_parsing() {
 #[test]
 fn test_instruction_serialization() {
     // Test make instruction serialization
-    let make_data = TestContext::serialize_make_instruction(100, 500_000_000, 250_000_000, 1735689600);
+    let taker = Pubkey::new_unique();
+    let make_data = TestContext::serialize_make_instruction(100, 500_000_000, 250_000_000, 1735689600, 5_000_000, &taker, &[7; 32], (900_000_000, 1735600000, 1735680000), (&Pubkey::default(), &[0; 32], 0, 0, 0), &Pubkey::default());
     assert_eq!(make_data[0], MAKE_DISCRIMINATOR);
-    assert_eq!(make_data.len(), 33); // 1 + 8 + 8 + 8 + 8
+    assert_eq!(make_data.len(), 237); // 1 + 8 + 8 + 8 + 8 + 8 + 32 + 32 + 8 + 8 + 8 + 32 + 32 + 8 + 2 + 2 + 32
     
     let seed = u64::from_le_bytes(make_data[1..9].try_into().unwrap());
     assert_eq!(seed, 100);
@@ -626,15 +3230,73 @@ fn test_instruction_serialization() {
     let expiry = i64::from_le_bytes(make_data[25..33].try_into().unwrap());
     assert_eq!(expiry, 1735689600);
     
+    let bounty = u64::from_le_bytes(make_data[33..41].try_into().unwrap());
+    assert_eq!(bounty, 5_000_000);
+    
+    let designated_taker = Pubkey::new_from_array(make_data[41..73].try_into().unwrap());
+    assert_eq!(designated_taker, taker);
+    
+    let merkle_root: [u8; 32] = make_data[73..105].try_into().unwrap();
+    assert_eq!(merkle_root, [7; 32]);
+    
+    let start_receive = u64::from_le_bytes(make_data[105..113].try_into().unwrap());
+    assert_eq!(start_receive, 900_000_000);
+    
+    let auction_end = i64::from_le_bytes(make_data[121..129].try_into().unwrap());
+    assert_eq!(auction_end, 1735680000);
+    
     // Test take instruction serialization
-    let take_data = TestContext::serialize_take_instruction();
-    assert_eq!(take_data.len(), 1);
+    let take_data = TestContext::serialize_take_instruction(200_000_000, 25, 200_000_000, 125_000_000, &[]);
+    assert_eq!(take_data.len(), 27); // 1 + 8 + 2 + 8 + 8
     assert_eq!(take_data[0], TAKE_DISCRIMINATOR);
     
+    let amount = u64::from_le_bytes(take_data[1..9].try_into().unwrap());
+    assert_eq!(amount, 200_000_000);
+    
+    let referral_bps = u16::from_le_bytes(take_data[9..11].try_into().unwrap());
+    assert_eq!(referral_bps, 25);
+    
+    let max_pay = u64::from_le_bytes(take_data[11..19].try_into().unwrap());
+    assert_eq!(max_pay, 200_000_000);
+    
+    let min_receive = u64::from_le_bytes(take_data[19..27].try_into().unwrap());
+    assert_eq!(min_receive, 125_000_000);
+    
     // Test refund instruction serialization
     let refund_data = TestContext::serialize_refund_instruction();
     assert_eq!(refund_data.len(), 1);
     assert_eq!(refund_data[0], REFUND_DISCRIMINATOR);
     
+    // Test expire refund instruction serialization
+    let expire_refund_data = TestContext::serialize_expire_refund_instruction();
+    assert_eq!(expire_refund_data.len(), 1);
+    assert_eq!(expire_refund_data[0], EXPIRE_REFUND_DISCRIMINATOR);
+    
+    // Test update instruction serialization
+    let update_data = TestContext::serialize_update_instruction(900_000_000, -1, 0, 50_000_000);
+    assert_eq!(update_data.len(), 33); // 1 + 8 + 8 + 8 + 8
+    assert_eq!(update_data[0], UPDATE_DISCRIMINATOR);
+    
+    let expiry = i64::from_le_bytes(update_data[9..17].try_into().unwrap());
+    assert_eq!(expiry, -1);
+    
+    let withdraw = u64::from_le_bytes(update_data[25..33].try_into().unwrap());
+    assert_eq!(withdraw, 50_000_000);
+    
+    // Test config instruction serialization
+    let initialize_config_data = TestContext::serialize_initialize_config_instruction(&taker, 30, 50);
+    assert_eq!(initialize_config_data.len(), 37); // 1 + 32 + 2 + 2
+    assert_eq!(initialize_config_data[0], INITIALIZE_CONFIG_DISCRIMINATOR);
+    
+    let update_config_data = TestContext::serialize_update_config_instruction(&taker, &taker, 50, 25);
+    assert_eq!(update_config_data.len(), 69); // 1 + 32 + 32 + 2 + 2
+    assert_eq!(update_config_data[0], UPDATE_CONFIG_DISCRIMINATOR);
+    
+    let fee_bps = u16::from_le_bytes(update_config_data[65..67].try_into().unwrap());
+    assert_eq!(fee_bps, 50);
+    
+    let max_referral_bps = u16::from_le_bytes(update_config_data[67..69].try_into().unwrap());
+    assert_eq!(max_referral_bps, 25);
+    
     println!("Instruction serialization test passed!");
 }
\ No newline at end of file